@@ -343,7 +343,7 @@ fn make_panel(ctx: &mut EventCtx, story: &StoryMap, mode: &Mode, dirty: bool) ->
                     RewriteColor::ChangeAll(Color::hex("#4CA7E9")),
                 )
             } else {
-                Btn::svg_def("system/assets/tools/pan.svg").build(ctx, "pan", Key::Escape)
+                Btn::svg_def("system/assets/tools/pan.svg").build(ctx, "pan", None)
             },
             match mode {
                 Mode::Freehand(_) => Widget::draw_svg_transform(
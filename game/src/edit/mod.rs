@@ -835,7 +835,7 @@ impl ConfirmDiscard {
                 ]),
                 "Are you sure you want to discard changes you made?".draw_text(ctx),
                 Widget::row(vec![
-                    Btn::plaintext("Cancel").build_def(ctx, Key::Escape),
+                    Btn::plaintext("Cancel").build_def(ctx, None),
                     Btn::text_bg2("Yes, discard").build_def(ctx, None),
                 ])
                 .align_right(),
@@ -126,7 +126,7 @@ pub fn cmp_duration_shorter(after: Duration, before: Duration) -> Vec<TextSpan>
 pub fn color_for_mode(app: &App, m: TripMode) -> Color {
     match m {
         TripMode::Walk => app.cs.unzoomed_pedestrian,
-        TripMode::Bike => app.cs.unzoomed_bike,
+        TripMode::Bike | TripMode::Scooter => app.cs.unzoomed_bike,
         TripMode::Transit => app.cs.unzoomed_bus,
         TripMode::Drive => app.cs.unzoomed_car,
     }
@@ -139,6 +139,7 @@ pub fn color_for_agent_type(app: &App, a: AgentType) -> Color {
         AgentType::Bus | AgentType::Train => app.cs.unzoomed_bus,
         AgentType::TransitRider => app.cs.bus_trip,
         AgentType::Car => app.cs.unzoomed_car,
+        AgentType::ParkedCar => app.cs.parked_car,
     }
 }
 
@@ -153,6 +154,7 @@ pub fn color_for_trip_phase(app: &App, tpt: TripPhaseType) -> Color {
         TripPhaseType::Cancelled | TripPhaseType::Finished => unreachable!(),
         TripPhaseType::DelayedStart => Color::YELLOW,
         TripPhaseType::Remote => Color::PINK,
+        TripPhaseType::Dwelling => Color::PURPLE,
     }
 }
 
@@ -358,6 +358,8 @@ fn delay_plot(
         max_x: Some(limit),
         max_y: None,
         disabled: opts.disabled_series(),
+        log_y: false,
+        thresholds: Vec::new(),
     };
     Widget::col(vec![
         Line("Delay through intersection").small_heading().draw(ctx),
@@ -88,6 +88,8 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
                 max_x: None,
                 max_y: Some(capacity),
                 disabled: HashSet::new(),
+                log_y: false,
+                thresholds: Vec::new(),
             },
         ));
     }
@@ -50,6 +50,8 @@ pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: ParkingLot
             max_x: None,
             max_y: Some(capacity),
             disabled: HashSet::new(),
+            log_y: false,
+            thresholds: Vec::new(),
         },
     ));
 
@@ -141,7 +141,7 @@ pub fn trips(
                     ctx.prerender,
                     match trip.mode {
                         TripMode::Walk => "system/assets/meters/pedestrian.svg",
-                        TripMode::Bike => "system/assets/meters/bike.svg",
+                        TripMode::Bike | TripMode::Scooter => "system/assets/meters/bike.svg",
                         TripMode::Drive => "system/assets/meters/car.svg",
                         TripMode::Transit => "system/assets/meters/bus.svg",
                     },
@@ -391,6 +391,7 @@ pub fn schedule(
                 }
             }
             TripEndpoint::Border(_, _) => "off-map".to_string(),
+            TripEndpoint::SuddenlyAppear(_) => "somewhere on the street".to_string(),
         };
         rows.push(
             Text::from(Line(format!(
@@ -420,6 +421,7 @@ pub fn schedule(
             }
         }
         TripEndpoint::Border(_, _) => "off-map".to_string(),
+        TripEndpoint::SuddenlyAppear(_) => "somewhere on the street".to_string(),
     };
     rows.push(
         Text::from(Line(format!(
@@ -577,6 +577,7 @@ fn make_timeline(
                     TripPhaseType::DelayedStart => "system/assets/timeline/delayed_start.svg",
                     // TODO What icon should represent this?
                     TripPhaseType::Remote => "system/assets/timeline/delayed_start.svg",
+                    TripPhaseType::Dwelling => "system/assets/timeline/delayed_start.svg",
                 },
             )
             .centered_on(Pt2D::new(x1 + phase_width / 2.0, icon_height / 2.0)),
@@ -948,5 +949,16 @@ fn endpoint(endpt: &TripEndpoint, app: &App) -> (ID, Pt2D, String) {
                 ),
             )
         }
+        TripEndpoint::SuddenlyAppear(pos) => (
+            ID::Lane(pos.lane()),
+            pos.pt(&app.primary.map),
+            format!(
+                "somewhere along {}",
+                app.primary
+                    .map
+                    .get_parent(pos.lane())
+                    .get_name(app.opts.language.as_ref())
+            ),
+        ),
     }
 }
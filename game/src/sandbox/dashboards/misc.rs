@@ -214,6 +214,9 @@ impl State for TransitRoutes {
                     return Transition::Keep;
                 }
             }
+            Outcome::DoubleClicked(_) | Outcome::Dismissed | Outcome::ContextAction(_, _) => {
+                return Transition::Keep;
+            }
             Outcome::Nothing => {
                 if let Some(routes) = self.panel.autocomplete_done("search") {
                     if !routes.is_empty() {
@@ -131,9 +131,13 @@ fn produce_raw_data(app: &App) -> (Vec<FinishedTrip>, Vec<CancelledTrip>) {
         };
 
         if maybe_mode.is_none() || duration_before.is_none() {
-            let reason = trip.cancellation_reason.clone().unwrap_or(format!(
-                "trip succeeded now, but not before the current proposal"
-            ));
+            let reason = trip
+                .cancellation_reason
+                .as_ref()
+                .map(|r| r.to_string())
+                .unwrap_or(format!(
+                    "trip succeeded now, but not before the current proposal"
+                ));
             cancelled.push(CancelledTrip {
                 id: *id,
                 mode: trip.mode,
@@ -2,7 +2,7 @@ use rand::seq::SliceRandom;
 use rand::Rng;
 
 use abstutil::Timer;
-use geom::{Distance, Polygon};
+use geom::{Circle, Distance, Polygon};
 use map_model::{BuildingID, IntersectionID, Position, NORMAL_LANE_THICKNESS};
 use sim::{
     DrivingGoal, IndividTrip, PersonID, PersonSpec, Scenario, SidewalkSpot, SpawnTrip,
@@ -295,6 +295,7 @@ impl State for AgentSpawner {
                                     TripPurpose::Shopping,
                                     trip,
                                 )],
+                                vehicles: Vec::new(),
                             });
                         }
                     }
@@ -441,6 +442,9 @@ impl State for AgentSpawner {
                 match endpt {
                     TripEndpoint::Border(i, _) => app.primary.map.get_i(*i).polygon.clone(),
                     TripEndpoint::Bldg(b) => app.primary.map.get_b(*b).polygon.clone(),
+                    TripEndpoint::SuddenlyAppear(pos) => {
+                        Circle::new(pos.pt(&app.primary.map), Distance::meters(3.0)).to_polygon()
+                    }
                 },
             );
         }
@@ -450,6 +454,9 @@ impl State for AgentSpawner {
                 match endpt {
                     TripEndpoint::Border(i, _) => app.primary.map.get_i(*i).polygon.clone(),
                     TripEndpoint::Bldg(b) => app.primary.map.get_b(*b).polygon.clone(),
+                    TripEndpoint::SuddenlyAppear(pos) => {
+                        Circle::new(pos.pt(&app.primary.map), Distance::meters(3.0)).to_polygon()
+                    }
                 },
             );
             if let Some(p) = poly {
@@ -1073,6 +1073,7 @@ impl TutorialState {
                                 is_bike: false,
                             },
                         )],
+                        vehicles: Vec::new(),
                     });
                     // Will definitely get there first
                     for i in 0..map.get_b(goal_bldg).num_parking_spots() {
@@ -1091,6 +1092,7 @@ impl TutorialState {
                                     is_bike: false,
                                 },
                             )],
+                            vehicles: Vec::new(),
                         });
                     }
                     let mut rng = app.primary.current_flags.sim_flags.make_rng();
@@ -218,12 +218,14 @@ fn clip_trips(map: &Map, popdat: &PopDat, huge_map: &Map, timer: &mut Timer) ->
                         (&incoming_borders_walking, &outgoing_borders_walking)
                     }
                     TripMode::Drive => (&incoming_borders_driving, &outgoing_borders_driving),
-                    TripMode::Bike => (&incoming_borders_biking, &outgoing_borders_biking),
+                    TripMode::Bike | TripMode::Scooter => {
+                        (&incoming_borders_biking, &outgoing_borders_biking)
+                    }
                 },
                 match orig.mode {
                     TripMode::Walk | TripMode::Transit => PathConstraints::Pedestrian,
                     TripMode::Drive => PathConstraints::Car,
-                    TripMode::Bike => PathConstraints::Bike,
+                    TripMode::Bike | TripMode::Scooter => PathConstraints::Bike,
                 },
                 maybe_huge_map.as_ref(),
             )?;
@@ -300,6 +302,7 @@ pub fn make_weekday_scenario(
             id,
             orig_id: Some(orig_id),
             trips,
+            vehicles: Vec::new(),
         });
     }
     for maybe_t in individ_trips {
@@ -371,6 +374,7 @@ pub fn make_weekday_scenario_with_everyone(
             id,
             orig_id: Some(orig_id),
             trips,
+            vehicles: Vec::new(),
         });
     }
     for maybe_t in individ_trips {
@@ -151,6 +151,27 @@ impl Path {
         }
     }
 
+    /// Only for tests that don't have a real map. Without one, the length of each step isn't
+    /// known, so the total length is just `end_dist`.
+    pub fn without_map(steps: Vec<PathStep>, end_dist: Distance) -> Path {
+        let mut total_lanes = 0;
+        for s in &steps {
+            match s {
+                PathStep::Lane(_) | PathStep::ContraflowLane(_) => total_lanes += 1,
+                _ => {}
+            }
+        }
+        Path {
+            steps: VecDeque::from(steps),
+            end_dist,
+            total_length: end_dist,
+            crossed_so_far: Distance::ZERO,
+            total_lanes,
+            uber_turns: VecDeque::new(),
+            currently_inside_ut: None,
+        }
+    }
+
     pub fn one_step(l: LaneID, map: &Map) -> Path {
         Path::new(
             map,
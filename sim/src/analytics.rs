@@ -64,6 +64,11 @@ pub struct Analytics {
     /// Per parking lane or lot, when does a spot become filled (true) or free (false)
     pub parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
     pub parking_lot_changes: BTreeMap<ParkingLotID, Vec<(Time, bool)>>,
+    /// Every time a car looking for parking had to drive on past its destination to keep looking.
+    /// Compare runs with and without `SimOptions::reserve_parking_on_spawn` to see how much
+    /// circling around it saves. Not kept in prebaked results.
+    #[serde(skip)]
+    pub parking_search_reroutes: Vec<(Time, TripID)>,
 
     pub(crate) alerts: Vec<(Time, AlertLocation, String)>,
 
@@ -91,6 +96,7 @@ impl Analytics {
             intersection_delays: BTreeMap::new(),
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
+            parking_search_reroutes: Vec::new(),
             alerts: Vec::new(),
             record_anything: true,
         }
@@ -278,6 +284,9 @@ impl Analytics {
             Event::PathAmended(path) => {
                 self.record_demand(&path, map);
             }
+            Event::ParkingSearchRerouted(id) => {
+                self.parking_search_reroutes.push((time, id));
+            }
             Event::Alert(loc, msg) => {
                 self.alerts.push((time, loc, msg));
             }
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use map_model::BuildingID;
+
+use crate::{CarID, TripID, Vehicle, VehicleType};
+
+/// Shared bikes owned by nobody, waiting at docks. Somebody takes a bike from one dock and must
+/// return it to another dock with a free slot.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BikeDocks {
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    docks: BTreeMap<BuildingID, Dock>,
+    /// Bikes currently being ridden, and the dock where they'll be returned
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    in_use: BTreeMap<TripID, (Vehicle, BuildingID)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Dock {
+    capacity: usize,
+    bikes: Vec<Vehicle>,
+    /// Slots promised to bikes on their way here
+    reserved: usize,
+}
+
+impl Dock {
+    fn free_slots(&self) -> usize {
+        self.capacity - self.bikes.len() - self.reserved
+    }
+}
+
+impl BikeDocks {
+    pub fn new() -> BikeDocks {
+        BikeDocks {
+            docks: BTreeMap::new(),
+            in_use: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_dock(&mut self, b: BuildingID, capacity: usize, bikes: Vec<Vehicle>) {
+        assert!(!self.docks.contains_key(&b), "{} already has a dock", b);
+        assert!(bikes.len() <= capacity);
+        for bike in &bikes {
+            assert_eq!(bike.vehicle_type, VehicleType::Bike);
+            assert!(bike.owner.is_none());
+        }
+        self.docks.insert(
+            b,
+            Dock {
+                capacity,
+                bikes,
+                reserved: 0,
+            },
+        );
+    }
+
+    /// Takes a bike from one dock, reserving a slot to return it to at another. Fails with a
+    /// description of the problem if that isn't possible.
+    pub fn check_out(
+        &mut self,
+        trip: TripID,
+        from: BuildingID,
+        to: BuildingID,
+    ) -> Result<Vehicle, String> {
+        if !self.docks.contains_key(&from) {
+            return Err(format!("no bike dock at {}", from));
+        }
+        match self.docks.get(&to) {
+            Some(dock) => {
+                if dock.free_slots() == 0 {
+                    return Err(format!("no free slot to return a bike at {}", to));
+                }
+            }
+            None => {
+                return Err(format!("no bike dock at {}", to));
+            }
+        }
+        let bike = if let Some(bike) = self.docks.get_mut(&from).unwrap().bikes.pop() {
+            bike
+        } else {
+            return Err(format!("no bike available at {}", from));
+        };
+        self.docks.get_mut(&to).unwrap().reserved += 1;
+        self.in_use.insert(trip, (bike.clone(), to));
+        Ok(bike)
+    }
+
+    /// The bike for this trip reached its destination dock.
+    pub fn check_in(&mut self, trip: TripID) {
+        let (bike, to) = self.in_use.remove(&trip).unwrap();
+        let dock = self.docks.get_mut(&to).unwrap();
+        dock.reserved -= 1;
+        dock.bikes.push(bike);
+    }
+
+    /// If a trip using a shared bike gets cancelled, warp the bike to its destination dock.
+    pub fn trip_cancelled(&mut self, trip: TripID) {
+        if self.in_use.contains_key(&trip) {
+            self.check_in(trip);
+        }
+    }
+
+    pub fn bike_for_trip(&self, trip: TripID) -> Option<CarID> {
+        self.in_use.get(&trip).map(|(bike, _)| bike.id)
+    }
+
+    /// Adds the docks from another simulation, using `fix_trip` and `fix_vehicle` to shift its
+    /// IDs. Each simulation had its own bikes, so a dock in both keeps all of them, and room for
+    /// them.
+    pub fn merge<F1: Fn(TripID) -> TripID, F2: Fn(Vehicle) -> Vehicle>(
+        &mut self,
+        other: BikeDocks,
+        fix_trip: F1,
+        fix_vehicle: F2,
+    ) {
+        for (b, theirs) in other.docks {
+            let dock = self.docks.entry(b).or_insert_with(|| Dock {
+                capacity: 0,
+                bikes: Vec::new(),
+                reserved: 0,
+            });
+            dock.capacity += theirs.capacity;
+            dock.reserved += theirs.reserved;
+            dock.bikes
+                .extend(theirs.bikes.into_iter().map(&fix_vehicle));
+        }
+        for (trip, (bike, to)) in other.in_use {
+            self.in_use.insert(fix_trip(trip), (fix_vehicle(bike), to));
+        }
+    }
+
+    /// Returns (available bikes, free slots)
+    pub fn dock_status(&self, b: BuildingID) -> Option<(usize, usize)> {
+        let dock = self.docks.get(&b)?;
+        Some((dock.bikes.len(), dock.free_slots()))
+    }
+}
@@ -1,21 +1,25 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use serde::{Deserialize, Serialize};
 
 use geom::{Duration, Time};
 use map_model::{LaneID, Map, Path, PathConstraints, PathRequest, PathStep};
 
-use crate::{CarID, VehicleType};
+use crate::{CarID, Event, TripID};
 
 // Note this only indexes into the zones we track here, not all of them in the map.
 type ZoneIdx = usize;
 
 /// Some roads (grouped into zones) may have a cap on the number of vehicles that can enter per
-/// hour. CapSimState enforces this, just for driving trips.
+/// hour. CapSimState enforces this, by default just for driving trips.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct CapSimState {
     lane_to_zone: BTreeMap<LaneID, ZoneIdx>,
     zones: Vec<Zone>,
+    /// Paths with these constraints ignore all caps, and don't count against them either
+    exempt: BTreeSet<PathConstraints>,
+
+    events: Vec<Event>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -31,13 +35,27 @@ impl CapSimState {
         let mut sim = CapSimState {
             lane_to_zone: BTreeMap::new(),
             zones: Vec::new(),
+            exempt: vec![
+                PathConstraints::Pedestrian,
+                PathConstraints::Bike,
+                PathConstraints::Bus,
+                PathConstraints::Train,
+            ]
+            .into_iter()
+            .collect(),
+            events: Vec::new(),
         };
+        let vehicles = vec![
+            PathConstraints::Car,
+            PathConstraints::Bike,
+            PathConstraints::Bus,
+        ];
         for z in map.all_zones() {
             if let Some(cap) = z.restrictions.cap_vehicles_per_hour {
                 let idx = sim.zones.len();
                 for r in &z.members {
                     for l in map.get_r(*r).all_lanes() {
-                        if PathConstraints::Car.can_use(map.get_l(l), map) {
+                        if vehicles.iter().any(|c| c.can_use(map.get_l(l), map)) {
                             sim.lane_to_zone.insert(l, idx);
                         }
                     }
@@ -52,11 +70,27 @@ impl CapSimState {
         sim
     }
 
-    fn allow_trip(&mut self, now: Time, car: CarID, path: &Path) -> bool {
-        if car.1 != VehicleType::Car {
+    /// Only cars are capped by default. Exempting buses means transit keeps running no matter how
+    /// congested a zone is.
+    pub fn set_exempt(&mut self, constraints: PathConstraints, exempt: bool) {
+        if exempt {
+            self.exempt.insert(constraints);
+        } else {
+            self.exempt.remove(&constraints);
+        }
+    }
+
+    fn allow_trip(
+        &mut self,
+        now: Time,
+        car: CarID,
+        constraints: PathConstraints,
+        steps: &VecDeque<PathStep>,
+    ) -> bool {
+        if self.exempt.contains(&constraints) {
             return true;
         }
-        for step in path.get_steps() {
+        for step in steps {
             if let PathStep::Lane(l) = step {
                 if let Some(idx) = self.lane_to_zone.get(l) {
                     let zone = &mut self.zones[*idx];
@@ -79,19 +113,24 @@ impl CapSimState {
     }
 
     /// Before the driving portion of a trip begins, check that the desired path doesn't exceed any
-    /// caps. If so, attempt to reroute around.
+    /// caps. If so, attempt to reroute around. The request's constraints decide if the vehicle is
+    /// exempt.
     pub fn validate_path(
         &mut self,
         req: &PathRequest,
         path: Path,
         now: Time,
         car: CarID,
+        trip: TripID,
         capped: &mut bool,
         map: &Map,
     ) -> Option<Path> {
-        if self.allow_trip(now, car, &path) {
+        if self.allow_trip(now, car, req.constraints, path.get_steps()) {
             return Some(path);
         }
+        if !*capped {
+            self.events.push(Event::TripCapped(trip));
+        }
         *capped = true;
 
         // TODO Make the responses configurable: cancel the trip, reroute, delay an hour, switch
@@ -109,6 +148,10 @@ impl CapSimState {
         map.pathfind_avoiding_zones(req.clone(), avoid_lanes)
     }
 
+    pub fn collect_events(&mut self) -> Vec<Event> {
+        std::mem::replace(&mut self.events, Vec::new())
+    }
+
     pub fn get_cap_counter(&self, l: LaneID) -> usize {
         if let Some(idx) = self.lane_to_zone.get(&l) {
             self.zones[*idx].entered_in_last_hour.len()
@@ -117,3 +160,66 @@ impl CapSimState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use geom::Distance;
+    use map_model::Position;
+
+    use crate::VehicleType;
+
+    fn capped_zone(cap: usize) -> CapSimState {
+        let mut sim = CapSimState {
+            lane_to_zone: BTreeMap::new(),
+            zones: vec![Zone {
+                cap,
+                entered_in_last_hour: BTreeSet::new(),
+                hour_started: Time::START_OF_DAY,
+            }],
+            exempt: BTreeSet::new(),
+            events: Vec::new(),
+        };
+        sim.set_exempt(PathConstraints::Bus, true);
+        sim.lane_to_zone.insert(LaneID(0), 0);
+        sim
+    }
+
+    /// Does a path along LaneID(0) go through untouched? Rerouting needs a real map, so this only
+    /// works while the zone has room.
+    fn allowed(sim: &mut CapSimState, car: CarID, constraints: PathConstraints) -> bool {
+        let req = PathRequest {
+            start: Position::start(LaneID(0)),
+            end: Position::new(LaneID(0), Distance::meters(10.0)),
+            constraints,
+        };
+        let path = Path::without_map(vec![PathStep::Lane(LaneID(0))], req.end.dist_along());
+        let mut capped = false;
+        let result = sim.validate_path(
+            &req,
+            path.clone(),
+            Time::START_OF_DAY,
+            car,
+            TripID(0),
+            &mut capped,
+            &Map::blank(),
+        );
+        result == Some(path) && !capped
+    }
+
+    #[test]
+    fn test_bus_exempt() {
+        let mut sim = capped_zone(2);
+        let car = CarID(1, VehicleType::Car);
+        let bus = CarID(2, VehicleType::Bus);
+        assert!(allowed(&mut sim, car, PathConstraints::Car));
+        assert!(allowed(&mut sim, bus, PathConstraints::Bus));
+        // The bus didn't count against the cap
+        assert_eq!(sim.get_cap_counter(LaneID(0)), 1);
+
+        sim.set_exempt(PathConstraints::Bus, false);
+        assert!(allowed(&mut sim, bus, PathConstraints::Bus));
+        assert_eq!(sim.get_cap_counter(LaneID(0)), 2);
+    }
+}
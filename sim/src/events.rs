@@ -58,6 +58,11 @@ pub enum Event {
         blocked_time: Duration,
     },
     TripCancelled(TripID),
+    /// A congestion cap affected a trip for the first time
+    TripCapped(TripID),
+    /// A trip started after its scheduled departure, because the person's previous trip hadn't
+    /// finished yet. How late did it start?
+    DelayedTripStarted(TripID, Duration),
     TripPhaseStarting(TripID, PersonID, Option<PathRequest>, TripPhaseType),
     /// TripID, TurnID (Where the delay was encountered), Time spent waiting at that turn
     TripIntersectionDelay(TripID, TurnID, AgentID, Duration),
@@ -67,6 +72,9 @@ pub enum Event {
     /// Just use for parking replanning. Not happy about copying the full path in here, but the way
     /// to plumb info into Analytics is Event.
     PathAmended(Path),
+    /// A car looking for parking didn't find any free spot on its current lane, so it has to drive
+    /// on to keep looking.
+    ParkingSearchRerouted(TripID),
 
     Alert(AlertLocation, String),
 }
@@ -79,11 +87,16 @@ pub enum AlertLocation {
     Building(BuildingID),
 }
 
+/// Each phase of a trip starts with a TripPhaseStarting event and lasts until the next one.
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum TripPhaseType {
+    /// Emitted when the car actually appears, not when the leg is scheduled, since spawning might
+    /// be retried.
     Driving,
+    /// Emitted when the pedestrian actually appears, including the walks to and from a parked car
     Walking,
     Biking,
+    /// Only emitted when a driver has to search for a different spot than the one they planned
     Parking,
     WaitingForBus(BusRouteID, BusStopID),
     /// What stop did they board at?
@@ -92,6 +105,8 @@ pub enum TripPhaseType {
     Finished,
     DelayedStart,
     Remote,
+    /// Lingering at an intermediate waypoint
+    Dwelling,
 }
 
 impl TripPhaseType {
@@ -109,6 +124,7 @@ impl TripPhaseType {
             TripPhaseType::Finished => "Trip finished".to_string(),
             TripPhaseType::DelayedStart => "Delayed by a previous trip taking too long".to_string(),
             TripPhaseType::Remote => "Remote trip outside is the map boundaries".to_string(),
+            TripPhaseType::Dwelling => "Stopping at a waypoint".to_string(),
         }
     }
 }
@@ -25,6 +25,7 @@ pub use crate::render::{
 };
 
 pub use self::analytics::{Analytics, TripPhase};
+pub(crate) use self::bike_share::BikeDocks;
 pub(crate) use self::cap::CapSimState;
 pub(crate) use self::events::Event;
 pub use self::events::{AlertLocation, TripPhaseType};
@@ -38,14 +39,17 @@ pub(crate) use self::mechanics::{
 };
 pub(crate) use self::pandemic::PandemicModel;
 pub(crate) use self::router::{ActionAtEnd, Router};
-pub(crate) use self::scheduler::{Command, Scheduler};
+pub(crate) use self::scheduler::{Command, CommandType, Scheduler};
 pub use self::sim::{AgentProperties, AlertHandler, Sim, SimCallback, SimOptions};
 pub(crate) use self::transit::TransitSimState;
-pub use self::trips::{Person, PersonState, TripInfo, TripResult};
-pub use self::trips::{TripEndpoint, TripMode};
-pub(crate) use self::trips::{TripLeg, TripManager};
+pub(crate) use self::trips::TripManager;
+pub use self::trips::{
+    BlockedCause, CancellationKind, CancellationReason, Person, PersonState, TripInfo, TripResult,
+};
+pub use self::trips::{ScheduledTrip, TripEndpoint, TripLeg, TripMode, TripRecord, UnmetDemand};
 
 mod analytics;
+mod bike_share;
 mod cap;
 mod events;
 mod make;
@@ -162,9 +166,12 @@ pub enum AgentType {
     Train,
     Pedestrian,
     TransitRider,
+    /// Not an agent at all, just for counting vehicles. See `Sim::num_agents_detailed`.
+    ParkedCar,
 }
 
 impl AgentType {
+    /// Excludes ParkedCar, which never shows up in trips or analytics
     pub fn all() -> Vec<AgentType> {
         vec![
             AgentType::Car,
@@ -184,6 +191,7 @@ impl AgentType {
             AgentType::Train => "Train",
             AgentType::Pedestrian => "Pedestrian",
             AgentType::TransitRider => "Transit rider",
+            AgentType::ParkedCar => "Parked car",
         }
     }
 
@@ -195,6 +203,7 @@ impl AgentType {
             AgentType::Train => "trains",
             AgentType::Pedestrian => "pedestrians",
             AgentType::TransitRider => "transit riders",
+            AgentType::ParkedCar => "parked cars",
         }
     }
 
@@ -202,7 +211,7 @@ impl AgentType {
         match self {
             AgentType::Car => "driving",
             AgentType::Bike => "biking",
-            AgentType::Bus | AgentType::Train => unreachable!(),
+            AgentType::Bus | AgentType::Train | AgentType::ParkedCar => unreachable!(),
             AgentType::Pedestrian => "walking",
             AgentType::TransitRider => "riding transit",
         }
@@ -308,6 +317,16 @@ pub struct VehicleSpec {
     pub max_speed: Option<Speed>,
 }
 
+impl Vehicle {
+    pub fn to_spec(&self) -> VehicleSpec {
+        VehicleSpec {
+            vehicle_type: self.vehicle_type,
+            length: self.length,
+            max_speed: self.max_speed,
+        }
+    }
+}
+
 impl VehicleSpec {
     pub fn make(self, id: CarID, owner: Option<PersonID>) -> Vehicle {
         assert_eq!(id.1, self.vehicle_type);
@@ -343,6 +362,9 @@ pub struct ParkedCar {
 pub enum DrivingGoal {
     ParkNear(BuildingID),
     Border(IntersectionID, LaneID, Option<OffMapLocation>),
+    /// Stop somewhere along a lane and vanish. Like a vehicle starting from
+    /// `TripEndpoint::SuddenlyAppear`, this won't survive map edits.
+    Vanish(Position),
 }
 
 impl DrivingGoal {
@@ -373,6 +395,7 @@ impl DrivingGoal {
                 }
             },
             DrivingGoal::Border(_, l, _) => Some(Position::end(*l, map)),
+            DrivingGoal::Vanish(pos) => Some(*pos),
         }
     }
 
@@ -388,6 +411,7 @@ impl DrivingGoal {
             DrivingGoal::Border(i, last_lane, _) => {
                 Router::end_at_border(owner, path, map.get_l(*last_lane).length(), *i)
             }
+            DrivingGoal::Vanish(pos) => Router::vanish_at(owner, path, pos.dist_along()),
         }
     }
 
@@ -395,6 +419,7 @@ impl DrivingGoal {
         match self {
             DrivingGoal::ParkNear(b) => map.get_b(*b).polygon.center(),
             DrivingGoal::Border(i, _, _) => map.get_i(*i).polygon.center(),
+            DrivingGoal::Vanish(pos) => pos.pt(map),
         }
     }
 }
@@ -159,6 +159,8 @@ impl ScenarioGenerator {
                     (TripEndpoint::Border(_, _), TripEndpoint::Border(_, _)) => {
                         num_trips_passthru += 1;
                     }
+                    // Only buildings and borders are picked above
+                    _ => unreachable!(),
                 };
 
                 (home, work, fork_rng(rng))
@@ -283,6 +285,7 @@ fn create_prole(
             IndividTrip::new(depart_am, TripPurpose::Work, goto_work),
             IndividTrip::new(depart_pm, TripPurpose::Home, return_home),
         ],
+        vehicles: Vec::new(),
     })
 }
 
@@ -56,6 +56,7 @@ impl ExternalPerson {
                 id: PersonID(results.len()),
                 orig_id: None,
                 trips: Vec::new(),
+                vehicles: Vec::new(),
             };
             let mut from = lookup_pt(person.origin)?;
             for trip in person.trips {
@@ -194,6 +194,7 @@ impl SpawnOverTime {
                         TripPurpose::Shopping,
                         SpawnTrip::UsingParkedCar(from_bldg, goal),
                     )],
+                    vehicles: Vec::new(),
                 });
                 return;
             }
@@ -212,6 +213,7 @@ impl SpawnOverTime {
                         TripPurpose::Shopping,
                         SpawnTrip::UsingBike(from_bldg, goal),
                     )],
+                    vehicles: Vec::new(),
                 });
                 return;
             }
@@ -238,6 +240,7 @@ impl SpawnOverTime {
                             TripPurpose::Shopping,
                             SpawnTrip::UsingTransit(start_spot, goal, route, stop1, maybe_stop2),
                         )],
+                        vehicles: Vec::new(),
                     });
                     return;
                 }
@@ -251,6 +254,7 @@ impl SpawnOverTime {
                     TripPurpose::Shopping,
                     SpawnTrip::JustWalking(start_spot, goal),
                 )],
+                vehicles: Vec::new(),
             });
             return;
         }
@@ -307,6 +311,7 @@ impl BorderSpawnOverTime {
                                     maybe_stop2,
                                 ),
                             )],
+                            vehicles: Vec::new(),
                         });
                         continue;
                     }
@@ -320,6 +325,7 @@ impl BorderSpawnOverTime {
                         TripPurpose::Shopping,
                         SpawnTrip::JustWalking(start.clone(), goal),
                     )],
+                    vehicles: Vec::new(),
                 });
             }
         }
@@ -351,6 +357,7 @@ impl BorderSpawnOverTime {
                             origin: None,
                         },
                     )],
+                    vehicles: Vec::new(),
                 });
             }
         }
@@ -14,6 +14,7 @@ use map_model::{
 };
 
 use crate::make::fork_rng;
+use crate::trips::equiv_pos;
 use crate::{
     CarID, DrivingGoal, OrigPersonID, ParkingSpot, PersonID, SidewalkPOI, SidewalkSpot, Sim,
     TripEndpoint, TripMode, TripSpec, Vehicle, VehicleSpec, VehicleType, BIKE_LENGTH,
@@ -37,6 +38,11 @@ pub struct PersonSpec {
     /// Just used for debugging
     pub orig_id: Option<OrigPersonID>,
     pub trips: Vec<IndividTrip>,
+    /// Usually empty, meaning vehicles are randomly generated for the trips that need them. When a
+    /// scenario is exported from a simulation, this records the person's actual vehicles, so that
+    /// instantiating the scenario again reuses them.
+    #[serde(default)]
+    pub vehicles: Vec<VehicleSpec>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -102,7 +108,7 @@ pub struct OffMapLocation {
 }
 
 /// Lifted from Seattle's Soundcast model, but seems general enough to use anyhere.
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TripPurpose {
     Home,
     Work,
@@ -170,8 +176,11 @@ impl Scenario {
                 panic!("{}", err);
             }
 
-            let (vehicle_specs, cars_initially_parked_at, vehicle_foreach_trip) =
+            let (mut vehicle_specs, cars_initially_parked_at, vehicle_foreach_trip) =
                 p.get_vehicles(rng);
+            if let Err(err) = p.use_recorded_vehicles(&mut vehicle_specs) {
+                timer.error(format!("{}, so using random vehicles instead", err));
+            }
             sim.new_person(
                 p.id,
                 p.orig_id,
@@ -429,7 +438,7 @@ fn find_spot_near_building(
 }
 
 impl SpawnTrip {
-    fn to_trip_spec(
+    pub(crate) fn to_trip_spec(
         self,
         use_vehicle: Option<CarID>,
         rng: &mut XorShiftRng,
@@ -546,9 +555,7 @@ impl SpawnTrip {
                 match spot.connection {
                     SidewalkPOI::Building(b) => TripEndpoint::Bldg(b),
                     SidewalkPOI::Border(i, ref loc) => TripEndpoint::Border(i, loc.clone()),
-                    SidewalkPOI::SuddenlyAppear => {
-                        TripEndpoint::Border(map.get_l(spot.sidewalk_pos.lane()).src_i, None)
-                    }
+                    SidewalkPOI::SuddenlyAppear => TripEndpoint::SuddenlyAppear(spot.sidewalk_pos),
                     _ => unreachable!(),
                 }
             }
@@ -567,11 +574,13 @@ impl SpawnTrip {
             | SpawnTrip::UsingBike(_, ref goal) => match goal {
                 DrivingGoal::ParkNear(b) => TripEndpoint::Bldg(*b),
                 DrivingGoal::Border(i, _, ref loc) => TripEndpoint::Border(*i, loc.clone()),
+                DrivingGoal::Vanish(pos) => TripEndpoint::SuddenlyAppear(*pos),
             },
             SpawnTrip::JustWalking(_, ref spot) | SpawnTrip::UsingTransit(_, ref spot, _, _, _) => {
                 match spot.connection {
                     SidewalkPOI::Building(b) => TripEndpoint::Bldg(b),
                     SidewalkPOI::Border(i, ref loc) => TripEndpoint::Border(i, loc.clone()),
+                    SidewalkPOI::SuddenlyAppear => TripEndpoint::SuddenlyAppear(spot.sidewalk_pos),
                     _ => unreachable!(),
                 }
             }
@@ -599,8 +608,14 @@ impl SpawnTrip {
                     is_bike: false,
                     origin: origin.clone(),
                 },
+                TripEndpoint::SuddenlyAppear(pos) => SpawnTrip::VehicleAppearing {
+                    start: equiv_pos(pos, PathConstraints::Car, map)?,
+                    goal: to.driving_goal(PathConstraints::Car, map)?,
+                    is_bike: false,
+                },
             },
-            TripMode::Bike => match from {
+            // TODO Scooters aren't simulated separately yet, so they wind up as bike trips
+            TripMode::Bike | TripMode::Scooter => match from {
                 TripEndpoint::Bldg(b) => {
                     SpawnTrip::UsingBike(b, to.driving_goal(PathConstraints::Bike, map)?)
                 }
@@ -610,6 +625,11 @@ impl SpawnTrip {
                     is_bike: true,
                     origin: origin.clone(),
                 },
+                TripEndpoint::SuddenlyAppear(pos) => SpawnTrip::VehicleAppearing {
+                    start: equiv_pos(pos, PathConstraints::Bike, map)?,
+                    goal: to.driving_goal(PathConstraints::Bike, map)?,
+                    is_bike: true,
+                },
             },
             TripMode::Walk => {
                 SpawnTrip::JustWalking(from.start_sidewalk_spot(map)?, to.end_sidewalk_spot(map)?)
@@ -645,11 +665,11 @@ impl PersonSpec {
             // Once off-map, re-enter via any border node.
             let end_bldg = match pair.0.trip.end(map) {
                 TripEndpoint::Bldg(b) => Some(b),
-                TripEndpoint::Border(_, _) => None,
+                TripEndpoint::Border(_, _) | TripEndpoint::SuddenlyAppear(_) => None,
             };
             let start_bldg = match pair.1.trip.start(map) {
                 TripEndpoint::Bldg(b) => Some(b),
-                TripEndpoint::Border(_, _) => None,
+                TripEndpoint::Border(_, _) | TripEndpoint::SuddenlyAppear(_) => None,
             };
 
             if end_bldg != start_bldg {
@@ -674,6 +694,40 @@ impl PersonSpec {
         Ok(())
     }
 
+    /// Record the vehicles a person actually used, as long as they're the ones this person's
+    /// trips need. Otherwise, `vehicles` is left empty, so they're randomly generated when
+    /// instantiating.
+    pub(crate) fn record_vehicles(&mut self, vehicles: Vec<VehicleSpec>) {
+        self.vehicles = Vec::new();
+        // Only the types matter, so use a dummy RNG
+        let mut rng = XorShiftRng::from_seed([0; 16]);
+        let (needed, _, _) = self.get_vehicles(&mut rng);
+        if same_vehicle_types(&needed, &vehicles) {
+            self.vehicles = vehicles;
+        }
+    }
+
+    /// If vehicles were recorded for this person, replace the randomly generated `specs` with
+    /// them. Fails if they don't line up with what the trips need, leaving `specs` alone.
+    fn use_recorded_vehicles(&self, specs: &mut Vec<VehicleSpec>) -> Result<(), String> {
+        if self.vehicles.is_empty() {
+            return Ok(());
+        }
+        if !same_vehicle_types(specs, &self.vehicles) {
+            return Err(format!(
+                "{} has recorded vehicles {:?}, but their trips need {:?}",
+                self.id,
+                self.vehicles
+                    .iter()
+                    .map(|v| v.vehicle_type)
+                    .collect::<Vec<_>>(),
+                specs.iter().map(|v| v.vehicle_type).collect::<Vec<_>>()
+            ));
+        }
+        *specs = self.vehicles.clone();
+        Ok(())
+    }
+
     fn get_vehicles(
         &self,
         rng: &mut XorShiftRng,
@@ -726,7 +780,7 @@ impl PersonSpec {
                             DrivingGoal::ParkNear(b) => {
                                 car_locations.push((idx, Some(*b)));
                             }
-                            DrivingGoal::Border(_, _, _) => {
+                            DrivingGoal::Border(_, _, _) | DrivingGoal::Vanish(_) => {
                                 car_locations.push((idx, None));
                             }
                         }
@@ -756,7 +810,7 @@ impl PersonSpec {
                         DrivingGoal::ParkNear(b) => {
                             car_locations.push((idx, Some(*b)));
                         }
-                        DrivingGoal::Border(_, _, _) => {
+                        DrivingGoal::Border(_, _, _) | DrivingGoal::Vanish(_) => {
                             car_locations.push((idx, None));
                         }
                     }
@@ -794,3 +848,89 @@ impl PersonSpec {
         )
     }
 }
+
+fn same_vehicle_types(specs1: &[VehicleSpec], specs2: &[VehicleSpec]) -> bool {
+    specs1.len() == specs2.len()
+        && specs1
+            .iter()
+            .zip(specs2)
+            .all(|(v1, v2)| v1.vehicle_type == v2.vehicle_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use map_model::Direction;
+
+    fn person() -> PersonSpec {
+        PersonSpec {
+            id: PersonID(0),
+            orig_id: None,
+            trips: vec![
+                IndividTrip::new(
+                    Time::START_OF_DAY + Duration::hours(8),
+                    TripPurpose::Work,
+                    SpawnTrip::UsingParkedCar(BuildingID(0), DrivingGoal::ParkNear(BuildingID(1))),
+                ),
+                IndividTrip::new(
+                    Time::START_OF_DAY + Duration::hours(17),
+                    TripPurpose::Home,
+                    SpawnTrip::UsingParkedCar(BuildingID(1), DrivingGoal::ParkNear(BuildingID(0))),
+                ),
+                IndividTrip::new(
+                    Time::START_OF_DAY + Duration::hours(20),
+                    TripPurpose::Recreation,
+                    SpawnTrip::FromBorder {
+                        dr: DirectedRoadID {
+                            id: RoadID(0),
+                            dir: Direction::Fwd,
+                        },
+                        goal: DrivingGoal::ParkNear(BuildingID(0)),
+                        is_bike: true,
+                        origin: None,
+                    },
+                ),
+            ],
+            vehicles: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_vehicles_round_trip() {
+        // Instantiate the original, randomly picking vehicles
+        let (used, _, _) = person().get_vehicles(&mut XorShiftRng::from_seed([1; 16]));
+        // Export
+        let mut exported = person();
+        exported.record_vehicles(used.clone());
+        assert_eq!(exported.vehicles, used);
+        // Import, with a different RNG
+        let imported: PersonSpec =
+            abstutil::from_json(&abstutil::to_json(&exported).into_bytes()).unwrap();
+        let (mut reused, _, _) = imported.get_vehicles(&mut XorShiftRng::from_seed([2; 16]));
+        imported.use_recorded_vehicles(&mut reused).unwrap();
+        assert_eq!(reused, used);
+        // Export again
+        let mut exported_again = person();
+        exported_again.record_vehicles(reused);
+        assert_eq!(exported_again.vehicles, exported.vehicles);
+    }
+
+    #[test]
+    fn test_vehicles_mismatch() {
+        let mut rng = XorShiftRng::from_seed([1; 16]);
+        // The trips need a car and a bike
+        let wrong = vec![Scenario::rand_bike(&mut rng), Scenario::rand_car(&mut rng)];
+
+        let mut exported = person();
+        exported.record_vehicles(wrong.clone());
+        assert!(exported.vehicles.is_empty());
+
+        let mut imported = person();
+        imported.vehicles = wrong;
+        let (mut specs, _, _) = imported.get_vehicles(&mut rng);
+        let random = specs.clone();
+        assert!(imported.use_recorded_vehicles(&mut specs).is_err());
+        assert_eq!(specs, random);
+    }
+}
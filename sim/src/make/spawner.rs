@@ -6,12 +6,14 @@ use serde::{Deserialize, Serialize};
 use abstutil::{Parallelism, Timer};
 use geom::{Duration, Time};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, PathConstraints, PathRequest, Position,
+    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, Path, PathConstraints, PathRequest,
+    Position,
 };
 
 use crate::{
-    CarID, Command, DrivingGoal, OffMapLocation, Person, PersonID, Scheduler, SidewalkSpot,
-    TripEndpoint, TripLeg, TripManager, TripMode, TripPurpose, VehicleType,
+    CancellationReason, CarID, Command, DrivingGoal, OffMapLocation, Person, PersonID, Scheduler,
+    SidewalkSpot, TripEndpoint, TripLeg, TripManager, TripMode, TripPurpose, VehicleType,
+    SPAWN_DIST,
 };
 
 // TODO Some of these fields are unused now that we separately pass TripEndpoint
@@ -57,6 +59,27 @@ pub enum TripSpec {
         stop1: BusStopID,
         maybe_stop2: Option<BusStopID>,
     },
+    /// Enter the map already riding a bus or train, instead of walking to a stop first.
+    StartOnBus {
+        /// This must be currently running the route, with maybe_stop2 still ahead of it.
+        bus: CarID,
+        route: BusRouteID,
+        maybe_stop2: Option<BusStopID>,
+        goal: SidewalkSpot,
+    },
+    /// Walk to a dock, ride a shared bike to another dock, and walk to the goal.
+    UsingBikeShare {
+        start: BuildingID,
+        from_dock: BuildingID,
+        to_dock: BuildingID,
+        goal: BuildingID,
+    },
+    /// Call a ride-hailing vehicle to the building. If none are available when the trip starts,
+    /// the trip is cancelled.
+    UsingRideHail {
+        start: BuildingID,
+        goal: DrivingGoal,
+    },
     /// Completely off-map trip. Don't really simulate much of it.
     Remote {
         from: OffMapLocation,
@@ -107,14 +130,22 @@ impl TripSpawner {
                 if start_pos.dist_along() >= map.get_l(start_pos.lane()).length() {
                     panic!("Can't spawn at {}; it isn't that long", start_pos);
                 }
-                if let DrivingGoal::Border(_, end_lane, _) = goal {
-                    if start_pos.lane() == *end_lane
-                        && start_pos.dist_along() == map.get_l(*end_lane).length()
-                    {
-                        panic!(
-                            "Can't start at {}; it's the edge of a border already",
-                            start_pos
-                        );
+                match goal {
+                    DrivingGoal::ParkNear(_) => {}
+                    DrivingGoal::Border(_, end_lane, _) => {
+                        if start_pos.lane() == *end_lane
+                            && start_pos.dist_along() == map.get_l(*end_lane).length()
+                        {
+                            panic!(
+                                "Can't start at {}; it's the edge of a border already",
+                                start_pos
+                            );
+                        }
+                    }
+                    DrivingGoal::Vanish(end_pos) => {
+                        if start_pos == end_pos {
+                            panic!("Can't start at {}; it's where the trip ends", start_pos);
+                        }
                     }
                 }
 
@@ -158,6 +189,12 @@ impl TripSpawner {
                             }
                         })
                     }
+                    DrivingGoal::Vanish(pos) => TripEndpoint::SuddenlyAppear(*pos)
+                        .end_sidewalk_spot(map)
+                        .map(|goal| TripSpec::JustWalking {
+                            start: SidewalkSpot::building(*start, map),
+                            goal,
+                        }),
                 };
 
                 if let Some(start_spot) = SidewalkSpot::bike_rack(*start, map) {
@@ -190,6 +227,20 @@ impl TripSpawner {
                 }
             }
             TripSpec::UsingTransit { .. } => {}
+            TripSpec::StartOnBus { .. } => {}
+            TripSpec::UsingBikeShare {
+                from_dock, to_dock, ..
+            } => {
+                if SidewalkSpot::bike_rack(*from_dock, map).is_none()
+                    || SidewalkSpot::bike_rack(*to_dock, map).is_none()
+                {
+                    panic!(
+                        "Bike share trip between docks {} and {} can't start or stop biking",
+                        from_dock, to_dock
+                    );
+                }
+            }
+            TripSpec::UsingRideHail { .. } => {}
             TripSpec::Remote { .. } => {}
         };
 
@@ -243,155 +294,17 @@ impl TripSpawner {
             // TODO clone() is super weird to do here, but we just need to make the borrow checker
             // happy. All we're doing is grabbing IDs off this.
             let person = trips.get_person(p).unwrap().clone();
-            // Just create the trip for each case.
-            // TODO Not happy about this clone()
-            let trip = match spec.clone() {
-                TripSpec::VehicleAppearing {
-                    goal, use_vehicle, ..
-                } => {
-                    let mut legs = vec![TripLeg::Drive(use_vehicle, goal.clone())];
-                    if let DrivingGoal::ParkNear(b) = goal {
-                        legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
-                    }
-                    trips.new_trip(
-                        person.id,
-                        start_time,
-                        trip_start,
-                        if use_vehicle.1 == VehicleType::Bike {
-                            TripMode::Bike
-                        } else {
-                            TripMode::Drive
-                        },
-                        purpose,
-                        modified,
-                        legs,
-                        map,
-                    )
-                }
-                TripSpec::NoRoomToSpawn {
-                    goal, use_vehicle, ..
-                } => {
-                    let mut legs = vec![TripLeg::Drive(use_vehicle, goal.clone())];
-                    if let DrivingGoal::ParkNear(b) = goal {
-                        legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
-                    }
-                    trips.new_trip(
-                        person.id,
-                        start_time,
-                        trip_start,
-                        if use_vehicle.1 == VehicleType::Bike {
-                            TripMode::Bike
-                        } else {
-                            TripMode::Drive
-                        },
-                        purpose,
-                        modified,
-                        legs,
-                        map,
-                    )
-                }
-                TripSpec::UsingParkedCar { car, goal, .. } => {
-                    let mut legs = vec![
-                        TripLeg::Walk(SidewalkSpot::deferred_parking_spot()),
-                        TripLeg::Drive(car, goal.clone()),
-                    ];
-                    match goal {
-                        DrivingGoal::ParkNear(b) => {
-                            legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
-                        }
-                        DrivingGoal::Border(_, _, _) => {}
-                    }
-                    trips.new_trip(
-                        person.id,
-                        start_time,
-                        trip_start,
-                        TripMode::Drive,
-                        purpose,
-                        modified,
-                        legs,
-                        map,
-                    )
-                }
-                TripSpec::JustWalking { goal, .. } => trips.new_trip(
-                    person.id,
-                    start_time,
-                    trip_start,
-                    TripMode::Walk,
-                    purpose,
-                    modified,
-                    vec![TripLeg::Walk(goal.clone())],
-                    map,
-                ),
-                TripSpec::UsingBike { bike, start, goal } => {
-                    let walk_to = SidewalkSpot::bike_rack(start, map).unwrap();
-                    let mut legs = vec![
-                        TripLeg::Walk(walk_to.clone()),
-                        TripLeg::Drive(bike, goal.clone()),
-                    ];
-                    match goal {
-                        DrivingGoal::ParkNear(b) => {
-                            legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
-                        }
-                        DrivingGoal::Border(_, _, _) => {}
-                    };
-                    trips.new_trip(
-                        person.id,
-                        start_time,
-                        trip_start,
-                        TripMode::Bike,
-                        purpose,
-                        modified,
-                        legs,
-                        map,
-                    )
-                }
-                TripSpec::UsingTransit {
-                    route,
-                    stop1,
-                    maybe_stop2,
-                    goal,
-                    ..
-                } => {
-                    let walk_to = SidewalkSpot::bus_stop(stop1, map);
-                    let legs = if let Some(stop2) = maybe_stop2 {
-                        vec![
-                            TripLeg::Walk(walk_to.clone()),
-                            TripLeg::RideBus(route, Some(stop2)),
-                            TripLeg::Walk(goal),
-                        ]
-                    } else {
-                        vec![
-                            TripLeg::Walk(walk_to.clone()),
-                            TripLeg::RideBus(route, None),
-                        ]
-                    };
-                    trips.new_trip(
-                        person.id,
-                        start_time,
-                        trip_start,
-                        TripMode::Transit,
-                        purpose,
-                        modified,
-                        legs,
-                        map,
-                    )
-                }
-                TripSpec::Remote { to, mode, .. } => trips.new_trip(
-                    person.id,
-                    start_time,
-                    trip_start,
-                    mode,
-                    purpose,
-                    modified,
-                    vec![TripLeg::Remote(to)],
-                    map,
-                ),
-            };
+            let (mode, legs) = spec.legs(trips, map);
+            let trip = trips.new_trip(
+                person.id, start_time, trip_start, mode, purpose, modified, legs, map,
+            );
 
             if cancelled {
                 trips.cancel_unstarted_trip(
                     trip,
-                    format!("traffic pattern modifier cancelled this trip"),
+                    CancellationReason::Other(format!(
+                        "traffic pattern modifier cancelled this trip"
+                    )),
                 );
             } else {
                 scheduler.push(
@@ -404,6 +317,148 @@ impl TripSpawner {
 }
 
 impl TripSpec {
+    /// The mode and legs of the trip this spec will start
+    pub(crate) fn legs(&self, trips: &TripManager, map: &Map) -> (TripMode, Vec<TripLeg>) {
+        match self.clone() {
+            TripSpec::VehicleAppearing {
+                goal, use_vehicle, ..
+            }
+            | TripSpec::NoRoomToSpawn {
+                goal, use_vehicle, ..
+            } => {
+                let mut legs = vec![TripLeg::Drive(use_vehicle, goal.clone())];
+                if let DrivingGoal::ParkNear(b) = goal {
+                    legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
+                }
+                (
+                    if use_vehicle.1 == VehicleType::Bike {
+                        TripMode::Bike
+                    } else {
+                        TripMode::Drive
+                    },
+                    legs,
+                )
+            }
+            TripSpec::UsingParkedCar { car, goal, .. } => {
+                let mut legs = vec![
+                    TripLeg::Walk(SidewalkSpot::deferred_parking_spot()),
+                    TripLeg::Drive(car, goal.clone()),
+                ];
+                match goal {
+                    DrivingGoal::ParkNear(b) => {
+                        legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
+                    }
+                    DrivingGoal::Border(_, _, _) | DrivingGoal::Vanish(_) => {}
+                }
+                (TripMode::Drive, legs)
+            }
+            TripSpec::JustWalking { goal, .. } => (TripMode::Walk, vec![TripLeg::Walk(goal)]),
+            TripSpec::UsingBike { bike, start, goal } => {
+                let walk_to = SidewalkSpot::bike_rack(start, map).unwrap();
+                let mut legs = vec![TripLeg::Walk(walk_to), TripLeg::Drive(bike, goal.clone())];
+                match goal {
+                    DrivingGoal::ParkNear(b) => {
+                        legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
+                    }
+                    DrivingGoal::Border(_, _, _) | DrivingGoal::Vanish(_) => {}
+                };
+                (TripMode::Bike, legs)
+            }
+            TripSpec::UsingTransit {
+                route,
+                stop1,
+                maybe_stop2,
+                goal,
+                ..
+            } => {
+                let walk_to = SidewalkSpot::bus_stop(stop1, map);
+                let legs = if let Some(stop2) = maybe_stop2 {
+                    vec![
+                        TripLeg::Walk(walk_to),
+                        TripLeg::RideBus(route, Some(stop2)),
+                        TripLeg::Walk(goal),
+                    ]
+                } else {
+                    vec![TripLeg::Walk(walk_to), TripLeg::RideBus(route, None)]
+                };
+                (TripMode::Transit, legs)
+            }
+            TripSpec::StartOnBus {
+                route,
+                maybe_stop2,
+                goal,
+                ..
+            } => {
+                let legs = if let Some(stop2) = maybe_stop2 {
+                    vec![TripLeg::RideBus(route, Some(stop2)), TripLeg::Walk(goal)]
+                } else {
+                    vec![TripLeg::RideBus(route, None)]
+                };
+                (TripMode::Transit, legs)
+            }
+            TripSpec::UsingBikeShare {
+                from_dock,
+                to_dock,
+                goal,
+                ..
+            } => (
+                TripMode::Bike,
+                vec![
+                    TripLeg::Walk(SidewalkSpot::bike_rack(from_dock, map).unwrap()),
+                    TripLeg::BikeShare(from_dock, to_dock),
+                    TripLeg::Walk(SidewalkSpot::building(goal, map)),
+                ],
+            ),
+            TripSpec::UsingRideHail { goal, .. } => {
+                (TripMode::Drive, trips.ride_hail_legs(goal, map))
+            }
+            TripSpec::Remote { to, mode, .. } => (mode, vec![TripLeg::Remote(to)]),
+        }
+    }
+
+    /// A NoRoomToSpawn trip couldn't find anywhere to appear when it was created, but the map
+    /// might've changed since then. If there's somewhere the vehicle can appear and a path from
+    /// there, returns the trip to start instead.
+    pub(crate) fn find_room_to_spawn(&self, map: &Map) -> Option<(TripSpec, PathRequest, Path)> {
+        if let TripSpec::NoRoomToSpawn {
+            i,
+            goal,
+            use_vehicle,
+            origin,
+            ..
+        } = self
+        {
+            let constraints = if use_vehicle.1 == VehicleType::Bike {
+                PathConstraints::Bike
+            } else {
+                PathConstraints::Car
+            };
+            let end = goal.goal_pos(constraints, map)?;
+            let start = map
+                .get_i(*i)
+                .get_outgoing_lanes(map, constraints)
+                .into_iter()
+                .find(|l| map.get_l(*l).length() > SPAWN_DIST)
+                .map(|l| Position::new(l, SPAWN_DIST))?;
+            let req = PathRequest {
+                start,
+                end,
+                constraints,
+            };
+            let path = map.pathfind(req.clone())?;
+            let spec = TripSpec::VehicleAppearing {
+                start_pos: start,
+                goal: goal.clone(),
+                use_vehicle: *use_vehicle,
+                retry_if_no_room: true,
+                origin: origin.clone(),
+            };
+            Some((spec, req, path))
+        } else {
+            None
+        }
+    }
+
     pub(crate) fn get_pathfinding_request(&self, map: &Map) -> Option<PathRequest> {
         match self {
             TripSpec::VehicleAppearing {
@@ -441,6 +496,19 @@ impl TripSpec {
                 end: SidewalkSpot::bus_stop(*stop1, map).sidewalk_pos,
                 constraints: PathConstraints::Pedestrian,
             }),
+            // The bus is already following its route
+            TripSpec::StartOnBus { .. } => None,
+            TripSpec::UsingBikeShare {
+                start, from_dock, ..
+            } => Some(PathRequest {
+                start: map.get_b(*start).sidewalk_pos,
+                end: SidewalkSpot::bike_rack(*from_dock, map)
+                    .unwrap()
+                    .sidewalk_pos,
+                constraints: PathConstraints::Pedestrian,
+            }),
+            // The vehicle and its path are picked when the trip starts
+            TripSpec::UsingRideHail { .. } => None,
             TripSpec::Remote { .. } => None,
         }
     }
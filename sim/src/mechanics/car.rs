@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 
 use serde::{Deserialize, Serialize};
 
@@ -6,8 +6,8 @@ use geom::{Distance, Duration, PolyLine, Time};
 use map_model::{Direction, Map, Traversable};
 
 use crate::{
-    CarStatus, DistanceInterval, DrawCarInput, ParkingSpot, PersonID, Router, TimeInterval,
-    TransitSimState, TripID, Vehicle, VehicleType,
+    BlockedCause, CarStatus, DistanceInterval, DrawCarInput, ParkingSpot, PersonID, Router,
+    TimeInterval, TransitSimState, TripID, Vehicle, VehicleType,
 };
 
 /// Represents a single vehicle. Note "car" is a misnomer; it could also be a bus or bike.
@@ -21,6 +21,8 @@ pub struct Car {
     pub trip_and_person: Option<(TripID, PersonID)>,
     pub started_at: Time,
     pub total_blocked_time: Duration,
+    /// The same total, split up by cause
+    pub blocked_time_breakdown: BTreeMap<BlockedCause, Duration>,
 
     /// In reverse order -- most recently left is first. The sum length of these must be >=
     /// vehicle.length.
@@ -28,6 +30,24 @@ pub struct Car {
 }
 
 impl Car {
+    pub fn add_blocked_time(&mut self, cause: BlockedCause, dt: Duration) {
+        self.total_blocked_time += dt;
+        *self
+            .blocked_time_breakdown
+            .entry(cause)
+            .or_insert(Duration::ZERO) += dt;
+    }
+
+    /// Why this car is stuck, as far as it can tell by itself. Once it starts looking for
+    /// parking, any delay counts towards that.
+    pub fn blocked_cause(&self) -> BlockedCause {
+        if self.router.is_parking() {
+            BlockedCause::Parking
+        } else {
+            BlockedCause::Intersection
+        }
+    }
+
     /// Assumes the current head of the path is the thing to cross.
     pub fn crossing_state(&self, start_dist: Distance, start_time: Time, map: &Map) -> CarState {
         let dist_int = DistanceInterval::new_driving(
@@ -4,16 +4,17 @@ use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use geom::{Distance, Duration, PolyLine, Speed, Time};
-use map_model::{LaneID, Map, Path, PathStep, Traversable};
+use map_model::{LaneID, Map, Path, PathRequest, PathStep, Position, Traversable};
 
 use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::Queue;
 use crate::sim::Ctx;
 use crate::{
-    ActionAtEnd, AgentID, AgentProperties, CarID, Command, CreateCar, DistanceInterval,
-    DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSim, ParkingSimState, ParkingSpot,
-    PersonID, Scheduler, SimOptions, TimeInterval, TransitSimState, TripID, TripManager,
-    UnzoomedAgent, Vehicle, WalkingSimState, FOLLOWING_DISTANCE,
+    ActionAtEnd, AgentID, AgentProperties, BlockedCause, CancellationReason, CarID, Command,
+    CreateCar, DistanceInterval, DrawCarInput, DrivingGoal, Event, IntersectionSimState, ParkedCar,
+    ParkingSim, ParkingSimState, ParkingSpot, PersonID, Scheduler, SimOptions, TimeInterval,
+    TransitSimState, TripID, TripManager, UnzoomedAgent, Vehicle, WalkingSimState,
+    FOLLOWING_DISTANCE,
 };
 
 const TIME_TO_WAIT_AT_BUS_STOP: Duration = Duration::const_seconds(10.0);
@@ -88,7 +89,7 @@ impl DrivingSimState {
         params: CreateCar,
         map: &Map,
         intersections: &IntersectionSimState,
-        parking: &ParkingSimState,
+        parking: &mut ParkingSimState,
         scheduler: &mut Scheduler,
     ) -> bool {
         let first_lane = params.router.head().as_lane();
@@ -111,6 +112,7 @@ impl DrivingSimState {
                 last_steps: VecDeque::new(),
                 started_at: now,
                 total_blocked_time: Duration::ZERO,
+                blocked_time_breakdown: BTreeMap::new(),
                 trip_and_person: params.trip_and_person,
             };
             if let Some(p) = params.maybe_parked_car {
@@ -336,7 +338,8 @@ impl DrivingSimState {
                             // If they're on their last step, they might be ending early and not
                             // right behind us.
                             if !follower.router.last_step() {
-                                follower.total_blocked_time += now - blocked_since;
+                                // Stuck behind the bus
+                                follower.add_blocked_time(BlockedCause::Other, now - blocked_since);
                                 follower.state = follower.crossing_state(
                                     // Since the follower was Queued, this must be where they are.
                                     dist - car.vehicle.length - FOLLOWING_DISTANCE,
@@ -410,7 +413,7 @@ impl DrivingSimState {
                     car.trip_and_person,
                     &mut self.events,
                 );
-                car.total_blocked_time += now - blocked_since;
+                car.add_blocked_time(car.blocked_cause(), now - blocked_since);
                 car.state = car.crossing_state(Distance::ZERO, now, ctx.map);
                 ctx.scheduler
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
@@ -484,25 +487,35 @@ impl DrivingSimState {
                     &mut self.events,
                 ) {
                     Some(ActionAtEnd::VanishAtBorder(i)) => {
-                        car.total_blocked_time += now - blocked_since;
+                        car.add_blocked_time(car.blocked_cause(), now - blocked_since);
                         // Don't do this for buses
                         if car.trip_and_person.is_some() {
                             trips.car_or_bike_reached_border(
                                 now,
                                 car.vehicle.id,
                                 i,
-                                car.total_blocked_time,
+                                &car.blocked_time_breakdown,
                                 ctx,
                             );
                         }
                         false
                     }
-                    Some(ActionAtEnd::GiveUpOnParking) => {
-                        car.total_blocked_time += now - blocked_since;
+                    Some(ActionAtEnd::Vanish) => {
+                        car.add_blocked_time(car.blocked_cause(), now - blocked_since);
+                        trips.car_or_bike_vanished(
+                            now,
+                            car.vehicle.id,
+                            &car.blocked_time_breakdown,
+                            ctx,
+                        );
+                        false
+                    }
+                    Some(ActionAtEnd::GiveUpOnParking(b)) => {
+                        car.add_blocked_time(BlockedCause::Parking, now - blocked_since);
                         trips.cancel_trip(
                             now,
                             car.trip_and_person.unwrap().0,
-                            format!("no available parking anywhere"),
+                            CancellationReason::NoParking(b),
                             // If we couldn't find parking normally, doesn't make sense to warp the
                             // car to the destination. There's no parking!
                             None,
@@ -511,7 +524,7 @@ impl DrivingSimState {
                         false
                     }
                     Some(ActionAtEnd::StartParking(spot)) => {
-                        car.total_blocked_time += now - blocked_since;
+                        car.add_blocked_time(BlockedCause::Parking, now - blocked_since);
                         let delay = match spot {
                             ParkingSpot::Onstreet(_, _) => self.time_to_park_onstreet,
                             ParkingSpot::Offstreet(_, _) | ParkingSpot::Lot(_, _) => {
@@ -529,26 +542,37 @@ impl DrivingSimState {
                         true
                     }
                     Some(ActionAtEnd::GotoLaneEnd) => {
-                        car.total_blocked_time += now - blocked_since;
+                        car.add_blocked_time(car.blocked_cause(), now - blocked_since);
                         car.state = car.crossing_state(our_dist, now, ctx.map);
                         ctx.scheduler
                             .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                         true
                     }
                     Some(ActionAtEnd::StopBiking(bike_rack)) => {
-                        car.total_blocked_time += now - blocked_since;
+                        car.add_blocked_time(car.blocked_cause(), now - blocked_since);
                         trips.bike_reached_end(
                             now,
                             car.vehicle.id,
                             bike_rack,
-                            car.total_blocked_time,
+                            &car.blocked_time_breakdown,
                             ctx.map,
                             ctx.scheduler,
                         );
                         false
                     }
+                    Some(ActionAtEnd::DropOff(spot)) => {
+                        car.add_blocked_time(car.blocked_cause(), now - blocked_since);
+                        trips.person_dropped_off(
+                            now,
+                            car.vehicle.id,
+                            spot,
+                            &car.blocked_time_breakdown,
+                            ctx,
+                        );
+                        false
+                    }
                     Some(ActionAtEnd::BusAtStop) => {
-                        car.total_blocked_time += now - blocked_since;
+                        car.add_blocked_time(car.blocked_cause(), now - blocked_since);
                         if transit.bus_arrived_at_stop(now, car.vehicle.id, trips, walking, ctx) {
                             car.state = CarState::IdlingAtStop(
                                 our_dist,
@@ -601,7 +625,7 @@ impl DrivingSimState {
                     now,
                     car.vehicle.id,
                     spot,
-                    car.total_blocked_time,
+                    &car.blocked_time_breakdown,
                     ctx,
                 );
                 false
@@ -617,6 +641,7 @@ impl DrivingSimState {
         );
         let idx = dists.iter().position(|(id, _)| *id == c).unwrap();
         let mut car = self.cars.remove(&c).unwrap();
+        car.router.release_parking_spot(ctx.parking);
 
         // Hacks to delete cars that're mid-turn
         if let Traversable::Turn(_) = car.router.head() {
@@ -673,7 +698,12 @@ impl DrivingSimState {
             match follower.state {
                 CarState::Queued { blocked_since } => {
                     // Prevent them from jumping forwards.
-                    follower.total_blocked_time += now - blocked_since;
+                    let cause = if let CarState::Parking(_, _, _) = car.state {
+                        BlockedCause::Parking
+                    } else {
+                        follower.blocked_cause()
+                    };
+                    follower.add_blocked_time(cause, now - blocked_since);
                     follower.state = follower.crossing_state(follower_dist, now, ctx.map);
                     ctx.scheduler.update(
                         follower.state.get_end_time(),
@@ -869,6 +899,79 @@ impl DrivingSimState {
         self.cars.contains_key(&id)
     }
 
+    /// Replace the route of a car that's partway along a lane, starting from its current position.
+    /// Fails without changing anything if the car is in the middle of a turn, waiting to start one,
+    /// about to finish, parking, or if there's no path to the new goal.
+    pub fn reroute_car(
+        &mut self,
+        now: Time,
+        id: CarID,
+        goal: &DrivingGoal,
+        parking: &mut ParkingSimState,
+        map: &Map,
+    ) -> Result<(), String> {
+        let car = self
+            .cars
+            .get(&id)
+            .ok_or_else(|| format!("{} doesn't exist", id))?;
+        let lane = match car.router.head() {
+            Traversable::Lane(l) => l,
+            Traversable::Turn(_) => {
+                return Err(format!("{} is in the middle of a turn", id));
+            }
+        };
+        if car.router.last_step() {
+            return Err(format!("{} is about to finish its route", id));
+        }
+        match car.state {
+            CarState::Crossing(_, _) | CarState::Queued { .. } => {}
+            // Anything else may have already requested the next turn or be using a parking spot
+            _ => {
+                return Err(format!("{} isn't freely driving along a lane", id));
+            }
+        }
+
+        let front = self.queues[&car.router.head()]
+            .get_car_positions(now, &self.cars, &self.queues)
+            .into_iter()
+            .find(|(c, _)| *c == id)
+            .unwrap()
+            .1;
+        let constraints = car.vehicle.vehicle_type.to_constraints();
+        let req = PathRequest {
+            start: Position::new(lane, front),
+            end: goal
+                .goal_pos(constraints, map)
+                .ok_or_else(|| format!("{} can't reach {:?}", id, goal))?,
+            constraints,
+        };
+        let path = map
+            .pathfind(req.clone())
+            .ok_or_else(|| format!("no path for {}", req))?;
+        // The car's current state assumes it'll reach the end of this lane and keep going
+        if path.get_steps().len() == 1 {
+            return Err(format!("{}'s new goal is on its current lane", id));
+        }
+
+        self.events.push(Event::PathAmended(path.clone()));
+        let car = self.cars.get_mut(&id).unwrap();
+        car.router.release_parking_spot(parking);
+        car.router = goal.make_router(id, path, map);
+        Ok(())
+    }
+
+    /// Reserve a parking spot near the end of a car's route, so that nobody else takes it before
+    /// the car arrives. See `Router::reserve_parking_spot`.
+    pub fn reserve_parking_spot(
+        &mut self,
+        id: CarID,
+        parking: &mut ParkingSimState,
+        map: &Map,
+    ) -> bool {
+        let car = self.cars.get_mut(&id).unwrap();
+        car.router.reserve_parking_spot(&car.vehicle, parking, map)
+    }
+
     pub fn get_all_draw_cars(
         &self,
         now: Time,
@@ -27,6 +27,8 @@ pub trait ParkingSim {
     fn get_free_offstreet_spots(&self, b: BuildingID) -> Vec<ParkingSpot>;
     fn get_free_lot_spots(&self, pl: ParkingLotID) -> Vec<ParkingSpot>;
     fn reserve_spot(&mut self, spot: ParkingSpot);
+    // Does nothing if the spot isn't reserved.
+    fn unreserve_spot(&mut self, spot: ParkingSpot);
     fn remove_parked_car(&mut self, p: ParkedCar);
     fn add_parked_car(&mut self, p: ParkedCar);
     fn get_draw_cars(&self, id: LaneID, map: &Map) -> Vec<DrawCarInput>;
@@ -51,7 +53,10 @@ pub trait ParkingSim {
     fn spot_to_driving_pos(&self, spot: ParkingSpot, vehicle: &Vehicle, map: &Map) -> Position;
     fn spot_to_sidewalk_pos(&self, spot: ParkingSpot, map: &Map) -> Position;
     fn get_owner_of_car(&self, id: CarID) -> Option<PersonID>;
+    // Does nothing if the car isn't parked.
+    fn set_owner_of_car(&mut self, id: CarID, owner: PersonID);
     fn lookup_parked_car(&self, id: CarID) -> Option<&ParkedCar>;
+    fn num_parked_cars(&self) -> usize;
     // (Filled, available)
     fn get_all_parking_spots(&self) -> (Vec<ParkingSpot>, Vec<ParkingSpot>);
     // Unrealistically assumes the driver has knowledge of currently free parking spots, even if
@@ -271,6 +276,10 @@ impl ParkingSim for NormalParkingSimState {
         }
     }
 
+    fn unreserve_spot(&mut self, spot: ParkingSpot) {
+        self.reserved_spots.remove(&spot);
+    }
+
     fn remove_parked_car(&mut self, p: ParkedCar) {
         self.parked_cars
             .remove(&p.vehicle.id)
@@ -487,9 +496,17 @@ impl ParkingSim for NormalParkingSimState {
     fn get_owner_of_car(&self, id: CarID) -> Option<PersonID> {
         self.parked_cars.get(&id).and_then(|p| p.vehicle.owner)
     }
+    fn set_owner_of_car(&mut self, id: CarID, owner: PersonID) {
+        if let Some(p) = self.parked_cars.get_mut(&id) {
+            p.vehicle.owner = Some(owner);
+        }
+    }
     fn lookup_parked_car(&self, id: CarID) -> Option<&ParkedCar> {
         self.parked_cars.get(&id)
     }
+    fn num_parked_cars(&self) -> usize {
+        self.parked_cars.len()
+    }
 
     fn get_all_parking_spots(&self) -> (Vec<ParkingSpot>, Vec<ParkingSpot>) {
         let mut spots = Vec::new();
@@ -789,6 +806,10 @@ impl ParkingSim for InfiniteParkingSimState {
         self.reserved_spots.insert(spot);
     }
 
+    fn unreserve_spot(&mut self, spot: ParkingSpot) {
+        self.reserved_spots.remove(&spot);
+    }
+
     fn remove_parked_car(&mut self, p: ParkedCar) {
         self.parked_cars
             .remove(&p.vehicle.id)
@@ -893,9 +914,17 @@ impl ParkingSim for InfiniteParkingSimState {
     fn get_owner_of_car(&self, id: CarID) -> Option<PersonID> {
         self.parked_cars.get(&id).and_then(|p| p.vehicle.owner)
     }
+    fn set_owner_of_car(&mut self, id: CarID, owner: PersonID) {
+        if let Some(p) = self.parked_cars.get_mut(&id) {
+            p.vehicle.owner = Some(owner);
+        }
+    }
     fn lookup_parked_car(&self, id: CarID) -> Option<&ParkedCar> {
         self.parked_cars.get(&id)
     }
+    fn num_parked_cars(&self) -> usize {
+        self.parked_cars.len()
+    }
 
     fn get_all_parking_spots(&self) -> (Vec<ParkingSpot>, Vec<ParkingSpot>) {
         unreachable!()
@@ -200,7 +200,12 @@ impl WalkingSimState {
                             ctx.scheduler
                                 .push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
                         }
-                        SidewalkPOI::SuddenlyAppear => unreachable!(),
+                        SidewalkPOI::SuddenlyAppear => {
+                            self.peds_per_traversable
+                                .remove(ped.path.current_step().as_traversable(), ped.id);
+                            trips.ped_vanished(now, ped.id, ped.total_blocked_time, ctx);
+                            self.peds.remove(&id);
+                        }
                         SidewalkPOI::DeferredParkingSpot => unreachable!(),
                     }
                 } else {
@@ -384,6 +389,13 @@ impl WalkingSimState {
         self.peds.contains_key(&id)
     }
 
+    pub fn is_waiting_for_bus(&self, id: PedestrianID) -> bool {
+        self.peds
+            .get(&id)
+            .map(|p| matches!(p.state, PedState::WaitingForBus(_, _)))
+            .unwrap_or(false)
+    }
+
     pub fn get_draw_peds_on(
         &self,
         now: Time,
@@ -672,7 +684,10 @@ impl Pedestrian {
                 PedState::WaitingToTurn(_, _) => Some(self.path.next_step().as_turn()),
                 _ => None,
             },
-            preparing_bike: matches!(self.state, PedState::StartingToBike(_, _, _) | PedState::FinishingBiking(_, _, _)),
+            preparing_bike: matches!(
+                self.state,
+                PedState::StartingToBike(_, _, _) | PedState::FinishingBiking(_, _, _)
+            ),
             waiting_for_bus: matches!(self.state, PedState::WaitingForBus(_, _)),
             on,
         }
@@ -28,11 +28,13 @@ pub struct Router {
 #[derive(Debug)]
 pub enum ActionAtEnd {
     VanishAtBorder(IntersectionID),
+    Vanish,
     StartParking(ParkingSpot),
     GotoLaneEnd,
     StopBiking(SidewalkSpot),
+    DropOff(SidewalkSpot),
     BusAtStop,
-    GiveUpOnParking,
+    GiveUpOnParking(BuildingID),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -44,14 +46,27 @@ enum Goal {
         /// No parking available at all!
         stuck_end_dist: Option<Distance>,
         started_looking: bool,
+        /// The spot was reserved when the car started, so nobody else can take it in the
+        /// meantime. The reservation is released when the car starts parking there or gives up on
+        /// it.
+        reserved: bool,
     },
     EndAtBorder {
         end_dist: Distance,
         i: IntersectionID,
     },
+    /// Stop along the last driving lane and disappear.
+    Vanish {
+        end_dist: Distance,
+    },
     BikeThenStop {
         goal: SidewalkSpot,
     },
+    /// Stop along the last driving lane, letting a passenger out to walk from the goal.
+    DropOff {
+        end_dist: Distance,
+        goal: SidewalkSpot,
+    },
     FollowBusRoute {
         end_dist: Distance,
     },
@@ -70,6 +85,13 @@ impl Router {
             owner,
         }
     }
+    pub fn vanish_at(owner: CarID, path: Path, end_dist: Distance) -> Router {
+        Router {
+            path,
+            goal: Goal::Vanish { end_dist },
+            owner,
+        }
+    }
     pub fn vanish_bus(owner: CarID, l: LaneID, map: &Map) -> Router {
         let lane = map.get_l(l);
         Router {
@@ -90,6 +112,7 @@ impl Router {
                 spot: None,
                 stuck_end_dist: None,
                 started_looking: false,
+                reserved: false,
             },
             owner,
         }
@@ -103,6 +126,14 @@ impl Router {
         }
     }
 
+    pub fn drop_off(owner: CarID, path: Path, end_dist: Distance, goal: SidewalkSpot) -> Router {
+        Router {
+            goal: Goal::DropOff { end_dist, goal },
+            path,
+            owner,
+        }
+    }
+
     pub fn follow_bus_route(owner: CarID, path: Path, end_dist: Distance) -> Router {
         Router {
             path,
@@ -136,12 +167,14 @@ impl Router {
         assert!(self.last_step());
         match self.goal {
             Goal::EndAtBorder { end_dist, .. } => end_dist,
+            Goal::Vanish { end_dist } => end_dist,
             Goal::ParkNearBuilding {
                 spot,
                 stuck_end_dist,
                 ..
             } => stuck_end_dist.unwrap_or_else(|| spot.unwrap().1),
             Goal::BikeThenStop { ref goal } => goal.sidewalk_pos.dist_along(),
+            Goal::DropOff { end_dist, .. } => end_dist,
             Goal::FollowBusRoute { end_dist } => end_dist,
         }
     }
@@ -154,7 +187,7 @@ impl Router {
     pub fn advance(
         &mut self,
         vehicle: &Vehicle,
-        parking: &ParkingSimState,
+        parking: &mut ParkingSimState,
         map: &Map,
         trip_and_person: Option<(TripID, PersonID)>,
         events: &mut Vec<Event>,
@@ -192,7 +225,7 @@ impl Router {
         &mut self,
         front: Distance,
         vehicle: &Vehicle,
-        parking: &ParkingSimState,
+        parking: &mut ParkingSimState,
         map: &Map,
         // TODO Not so nice to plumb all of this here
         trip_and_person: Option<(TripID, PersonID)>,
@@ -206,54 +239,57 @@ impl Router {
                     None
                 }
             }
+            Goal::Vanish { end_dist } => {
+                if end_dist == front {
+                    Some(ActionAtEnd::Vanish)
+                } else {
+                    None
+                }
+            }
             Goal::ParkNearBuilding {
                 ref mut spot,
                 ref mut stuck_end_dist,
                 target,
                 ref mut started_looking,
+                ref mut reserved,
             } => {
                 if let Some(d) = stuck_end_dist {
                     if *d == front {
-                        return Some(ActionAtEnd::GiveUpOnParking);
+                        return Some(ActionAtEnd::GiveUpOnParking(target));
                     } else {
                         return None;
                     }
                 }
 
+                let current_lane = self.path.current_step().as_lane();
+                if *reserved {
+                    let (s, _) = spot.unwrap();
+                    // Live map edits might've cleared the reservation. Or the car might've changed
+                    // lanes since the spot was picked, so it's no longer beside the spot.
+                    if parking.is_free(s)
+                        || parking.spot_to_driving_pos(s, vehicle, map).lane() != current_lane
+                    {
+                        parking.unreserve_spot(s);
+                        *reserved = false;
+                        *spot = None;
+                    }
+                }
+
                 let need_new_spot = match spot {
-                    Some((s, _)) => !parking.is_free(*s),
+                    Some((s, _)) => !*reserved && !parking.is_free(*s),
                     None => true,
                 };
                 if need_new_spot {
                     *started_looking = true;
-                    let current_lane = self.path.current_step().as_lane();
                     let candidates = parking.get_all_free_spots(
                         Position::new(current_lane, front),
                         vehicle,
                         target,
                         map,
                     );
-                    let best =
-                        if let Some((driving_pos, _)) = map.get_b(target).driving_connection(map) {
-                            if driving_pos.lane() == current_lane {
-                                let target_dist = driving_pos.dist_along();
-                                // Closest to the building
-                                candidates
-                                    .into_iter()
-                                    .min_by_key(|(_, pos)| (pos.dist_along() - target_dist).abs())
-                            } else {
-                                // Closest to the road endpoint, I guess
-                                candidates
-                                    .into_iter()
-                                    .min_by_key(|(_, pos)| pos.dist_along())
-                            }
-                        } else {
-                            // Closest to the road endpoint, I guess
-                            candidates
-                                .into_iter()
-                                .min_by_key(|(_, pos)| pos.dist_along())
-                        };
-                    if let Some((new_spot, new_pos)) = best {
+                    if let Some((new_spot, new_pos)) =
+                        closest_spot(candidates, current_lane, target, map)
+                    {
                         if let Some((t, p)) = trip_and_person {
                             events.push(Event::TripPhaseStarting(
                                 t,
@@ -281,6 +317,7 @@ impl Router {
                             events.push(Event::PathAmended(self.path.clone()));
                             // TODO This path might not be the same as the one found here...
                             if let Some((t, p)) = trip_and_person {
+                                events.push(Event::ParkingSearchRerouted(t));
                                 events.push(Event::TripPhaseStarting(
                                     t,
                                     p,
@@ -310,6 +347,11 @@ impl Router {
                 }
 
                 if spot.unwrap().1 == front {
+                    if *reserved {
+                        // The caller reserves the spot again while parking
+                        parking.unreserve_spot(spot.unwrap().0);
+                        *reserved = false;
+                    }
                     Some(ActionAtEnd::StartParking(spot.unwrap().0))
                 } else {
                     None
@@ -322,6 +364,13 @@ impl Router {
                     None
                 }
             }
+            Goal::DropOff { end_dist, ref goal } => {
+                if end_dist == front {
+                    Some(ActionAtEnd::DropOff(goal.clone()))
+                } else {
+                    None
+                }
+            }
             Goal::FollowBusRoute { end_dist } => {
                 if end_dist == front {
                     Some(ActionAtEnd::BusAtStop)
@@ -447,4 +496,79 @@ impl Router {
             _ => None,
         }
     }
+
+    /// Pick a free spot on the last lane of the path now and reserve it, instead of looking for
+    /// one when the car arrives. Returns false if the car isn't going to park, already has a spot
+    /// in mind, or there's nothing free on the last lane; the car will look for parking normally
+    /// then.
+    pub fn reserve_parking_spot(
+        &mut self,
+        vehicle: &Vehicle,
+        parking: &mut ParkingSimState,
+        map: &Map,
+    ) -> bool {
+        // A one-step path already picked a spot when the car spawned
+        if self.last_step() {
+            return false;
+        }
+        let last_lane = self.path.last_step().as_lane();
+        if let Goal::ParkNearBuilding {
+            ref mut spot,
+            ref mut reserved,
+            target,
+            ..
+        } = self.goal
+        {
+            if spot.is_some() {
+                return false;
+            }
+            let candidates =
+                parking.get_all_free_spots(Position::start(last_lane), vehicle, target, map);
+            if let Some((s, pos)) = closest_spot(candidates, last_lane, target, map) {
+                parking.reserve_spot(s);
+                *spot = Some((s, pos.dist_along()));
+                *reserved = true;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// If a parking spot was reserved for this car, free it up. Call this before the car vanishes
+    /// or switches to a different goal.
+    pub fn release_parking_spot(&mut self, parking: &mut ParkingSimState) {
+        if let Goal::ParkNearBuilding {
+            ref mut spot,
+            ref mut reserved,
+            ..
+        } = self.goal
+        {
+            if *reserved {
+                parking.unreserve_spot(spot.take().unwrap().0);
+                *reserved = false;
+            }
+        }
+    }
+}
+
+/// Of some free spots along a lane, which should a car heading to the target building use?
+fn closest_spot(
+    candidates: Vec<(ParkingSpot, Position)>,
+    lane: LaneID,
+    target: BuildingID,
+    map: &Map,
+) -> Option<(ParkingSpot, Position)> {
+    if let Some((driving_pos, _)) = map.get_b(target).driving_connection(map) {
+        if driving_pos.lane() == lane {
+            let target_dist = driving_pos.dist_along();
+            // Closest to the building
+            return candidates
+                .into_iter()
+                .min_by_key(|(_, pos)| (pos.dist_along() - target_dist).abs());
+        }
+    }
+    // Closest to the road endpoint, I guess
+    candidates
+        .into_iter()
+        .min_by_key(|(_, pos)| pos.dist_along())
 }
@@ -25,8 +25,11 @@ pub enum Command {
     Callback(Duration),
     Pandemic(pandemic::Cmd),
     FinishRemoteTrip(TripID),
+    FinishDwell(TripID),
     /// The Time is redundant, just used to dedupe commands
     StartBus(BusRouteID, Time),
+    /// Cancel a trip if it's still going after the maximum trip duration
+    AbortLongTrip(TripID),
 }
 
 impl Command {
@@ -50,7 +53,9 @@ impl Command {
             Command::Callback(_) => CommandType::Callback,
             Command::Pandemic(ref p) => CommandType::Pandemic(p.clone()),
             Command::FinishRemoteTrip(t) => CommandType::FinishRemoteTrip(*t),
+            Command::FinishDwell(t) => CommandType::FinishDwell(*t),
             Command::StartBus(r, t) => CommandType::StartBus(*r, *t),
+            Command::AbortLongTrip(t) => CommandType::AbortLongTrip(*t),
         }
     }
 }
@@ -67,7 +72,9 @@ pub enum CommandType {
     Callback,
     Pandemic(pandemic::Cmd),
     FinishRemoteTrip(TripID),
+    FinishDwell(TripID),
     StartBus(BusRouteID, Time),
+    AbortLongTrip(TripID),
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -169,6 +176,29 @@ impl Scheduler {
         });
     }
 
+    /// Move an already queued command to a different time, earlier or later. Returns false if
+    /// nothing of this type is queued.
+    pub fn reschedule(&mut self, cmd_type: CommandType, new_time: Time) -> bool {
+        if new_time < self.latest_time {
+            panic!(
+                "It's at least {}, so can't schedule a command for {}",
+                self.latest_time, new_time
+            );
+        }
+        if let Some((_, time)) = self.queued_commands.get_mut(&cmd_type) {
+            *time = new_time;
+            self.last_time = self.last_time.max(new_time);
+            // The old item is skipped when it comes up, since the time won't match
+            self.items.push(Item {
+                time: new_time,
+                cmd_type,
+            });
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn cancel(&mut self, cmd: Command) {
         // It's fine if a previous command hasn't actually been scheduled.
         self.queued_commands.remove(&cmd.to_type());
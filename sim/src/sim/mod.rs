@@ -17,17 +17,26 @@ use map_model::{
 
 pub use self::queries::AgentProperties;
 use crate::{
-    AgentID, AlertLocation, Analytics, CapSimState, CarID, Command, CreateCar, DrivingSimState,
-    Event, IntersectionSimState, OrigPersonID, PandemicModel, ParkedCar, ParkingSim,
-    ParkingSimState, ParkingSpot, Person, PersonID, Router, Scheduler, SidewalkPOI, SidewalkSpot,
-    TransitSimState, TripID, TripManager, TripPhaseType, TripSpawner, Vehicle, VehicleSpec,
-    VehicleType, WalkingSimState, BUS_LENGTH, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH, SPAWN_DIST,
+    AgentID, AlertLocation, Analytics, CancellationReason, CapSimState, CarID, Command, CreateCar,
+    DrivingGoal, DrivingSimState, Event, IntersectionSimState, OrigPersonID, PandemicModel,
+    ParkedCar, ParkingSim, ParkingSimState, ParkingSpot, Person, PersonID, PersonState, Router,
+    Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripID, TripManager, TripMode,
+    TripPhaseType, TripResult, TripSpawner, Vehicle, VehicleSpec, VehicleType, WalkingSimState,
+    BUS_LENGTH, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH, SPAWN_DIST,
 };
 
 mod queries;
 
-// TODO Do something else.
+/// When a vehicle can't spawn because there's no room, wait this long times the number of failed
+/// attempts before trying again.
 const BLIND_RETRY_TO_SPAWN: Duration = Duration::const_seconds(5.0);
+/// Never wait longer than this between attempts, so the vehicle appears soon after room frees up.
+const MAX_RETRY_TO_SPAWN: Duration = Duration::const_seconds(60.0);
+/// After this many failed attempts to spawn a vehicle, give up and cancel the trip.
+const MAX_SPAWN_ATTEMPTS: usize = 10;
+/// When a trip runs too long but its vehicle is still waiting to spawn, check again this much
+/// later.
+const RECHECK_LONG_TRIP: Duration = Duration::const_seconds(5.0);
 
 /// The Sim ties together all the pieces of the simulation. Its main property is the current time.
 #[derive(Serialize, Deserialize, Clone)]
@@ -98,6 +107,12 @@ pub struct SimOptions {
     /// At the beginning of the simulation, precompute the route for all trips for the entire
     /// scenario.
     pub pathfinding_upfront: bool,
+    /// Record how many people are inside each building over time. This costs memory on large
+    /// runs, so it's off by default.
+    pub track_building_occupancy: bool,
+    /// Reserve a parking spot for cars when they start driving, instead of looking for one when
+    /// they arrive. This stops cars from heading for a spot somebody else just took.
+    pub reserve_parking_on_spawn: bool,
     /// Ignore parking data in the map and instead treat every building as if it has unlimited
     /// capacity for vehicles.
     pub infinite_parking: bool,
@@ -139,6 +154,8 @@ impl SimOptions {
                 })
                 .unwrap_or(AlertHandler::Print),
             pathfinding_upfront: args.enabled("--pathfinding_upfront"),
+            track_building_occupancy: args.enabled("--track_building_occupancy"),
+            reserve_parking_on_spawn: args.enabled("--reserve_parking_on_spawn"),
             infinite_parking: args.enabled("--infinite_parking"),
             disable_turn_conflicts: args.enabled("--disable_turn_conflicts"),
         }
@@ -173,6 +190,8 @@ impl SimOptions {
             enable_pandemic_model: None,
             alerts: AlertHandler::Print,
             pathfinding_upfront: false,
+            track_building_occupancy: false,
+            reserve_parking_on_spawn: false,
             infinite_parking: false,
             disable_turn_conflicts: false,
         }
@@ -190,7 +209,7 @@ impl Sim {
             intersections: IntersectionSimState::new(map, &mut scheduler, &opts),
             transit: TransitSimState::new(map),
             cap: CapSimState::new(map),
-            trips: TripManager::new(opts.pathfinding_upfront),
+            trips: TripManager::new(&opts),
             pandemic: if let Some(rng) = opts.enable_pandemic_model {
                 Some(PandemicModel::new(rng))
             } else {
@@ -297,6 +316,168 @@ impl Sim {
     pub fn random_person(&mut self, ped_speed: Speed, vehicle_specs: Vec<VehicleSpec>) -> &Person {
         self.trips.random_person(ped_speed, vehicle_specs)
     }
+    pub fn add_vehicle(&mut self, person: PersonID, spec: VehicleSpec) -> CarID {
+        self.trips.add_vehicle(person, spec)
+    }
+    pub fn add_bike_dock(&mut self, b: BuildingID, capacity: usize, bikes: Vec<VehicleSpec>) {
+        self.trips.add_bike_dock(b, capacity, bikes);
+    }
+    pub fn add_ride_hail_vehicle(&mut self, spec: VehicleSpec) -> CarID {
+        self.trips.add_ride_hail_vehicle(spec)
+    }
+    /// Switch a trip that hasn't started yet to a different mode. See
+    /// `TripManager::reassign_mode`.
+    pub fn reassign_mode(&mut self, id: TripID, mode: TripMode, map: &Map) -> Result<(), String> {
+        self.trips.reassign_mode(id, mode, map, &mut self.scheduler)
+    }
+    /// Move the departure of a trip that hasn't started yet. See
+    /// `TripManager::reschedule_departure`.
+    pub fn reschedule_departure(&mut self, id: TripID, new_time: Time) -> Result<(), String> {
+        self.trips
+            .reschedule_departure(self.time, id, new_time, &mut self.scheduler)
+    }
+    /// Run something whenever a trip is cancelled. This has to be registered again after loading a
+    /// savestate. See `TripManager::set_on_cancel`.
+    pub fn set_on_cancel(&mut self, cb: Box<dyn FnMut(TripID, &CancellationReason)>) {
+        self.trips.set_on_cancel(cb);
+    }
+    /// Add the people and trips of another finished simulation on the same map. Nothing else about
+    /// `other` is kept. See `TripManager::merge_into`.
+    pub fn merge_trips(&mut self, other: Sim, map: &Map) -> Result<(), String> {
+        self.trips.merge_into(other.trips, map)
+    }
+    /// Cancel any trip still going this long after its departure time, so that a few stuck agents
+    /// can't keep the simulation from finishing.
+    pub fn set_max_trip_duration(&mut self, limit: Duration) {
+        self.trips
+            .set_max_trip_duration(self.time, limit, &mut self.scheduler);
+    }
+    /// See `TripManager::set_pathfinding_upfront`.
+    pub fn set_pathfinding_upfront(&mut self, upfront: bool) {
+        self.trips.set_pathfinding_upfront(upfront);
+    }
+    /// Decide if vehicles using some constraints ignore congestion caps. See
+    /// `CapSimState::set_exempt`.
+    pub fn set_cap_exempt(&mut self, constraints: PathConstraints, exempt: bool) {
+        self.cap.set_exempt(constraints, exempt);
+    }
+    /// Cancel a trip without warping the person to their destination. See
+    /// `TripManager::cancel_trip_stay_put`.
+    pub fn cancel_trip_stay_put(
+        &mut self,
+        id: TripID,
+        reason: CancellationReason,
+        map: &Map,
+    ) -> Result<(), String> {
+        let vehicle = self.delete_agent_for_trip(id, map)?;
+        let mut ctx = Ctx {
+            parking: &mut self.parking,
+            intersections: &mut self.intersections,
+            cap: &mut self.cap,
+            scheduler: &mut self.scheduler,
+            map,
+        };
+        self.trips
+            .cancel_trip_stay_put(self.time, id, reason, vehicle, &mut ctx)
+    }
+    /// Instantly finish an ongoing trip, as if the person just arrived. See
+    /// `TripManager::fast_forward_trip`.
+    pub fn fast_forward_trip(&mut self, id: TripID, map: &Map) -> Result<(), String> {
+        let vehicle = self.delete_agent_for_trip(id, map)?;
+        let mut ctx = Ctx {
+            parking: &mut self.parking,
+            intersections: &mut self.intersections,
+            cap: &mut self.cap,
+            scheduler: &mut self.scheduler,
+            map,
+        };
+        self.trips
+            .fast_forward_trip(self.time, id, vehicle, &mut ctx)
+    }
+    /// Cancel all of a person's unfinished trips, including the one they're doing right now. See
+    /// `TripManager::cancel_all_trips_for_person`.
+    pub fn cancel_all_trips_for_person(
+        &mut self,
+        p: PersonID,
+        reason: CancellationReason,
+        map: &Map,
+    ) -> Result<usize, String> {
+        let vehicle = match self.trips.get_person(p).map(|person| person.state.clone()) {
+            Some(PersonState::Trip(id)) => self.delete_agent_for_trip(id, map)?,
+            Some(_) => None,
+            None => {
+                return Err(format!("{} doesn't exist", p));
+            }
+        };
+        let mut ctx = Ctx {
+            parking: &mut self.parking,
+            intersections: &mut self.intersections,
+            cap: &mut self.cap,
+            scheduler: &mut self.scheduler,
+            map,
+        };
+        Ok(self
+            .trips
+            .cancel_all_trips_for_person(self.time, p, reason, vehicle, &mut ctx))
+    }
+    /// Remove whatever agent is doing an ongoing trip, returning the vehicle it was using.
+    fn delete_agent_for_trip(&mut self, id: TripID, map: &Map) -> Result<Option<Vehicle>, String> {
+        let mut ctx = Ctx {
+            parking: &mut self.parking,
+            intersections: &mut self.intersections,
+            cap: &mut self.cap,
+            scheduler: &mut self.scheduler,
+            map,
+        };
+        Ok(match self.trips.trip_to_agent(id) {
+            TripResult::Ok(AgentID::Car(car)) => {
+                if !self.driving.does_car_exist(car) {
+                    return Err(format!("{} hasn't spawned yet", car));
+                }
+                Some(self.driving.delete_car(car, self.time, &mut ctx))
+            }
+            TripResult::Ok(AgentID::Pedestrian(ped)) => {
+                if !self.walking.does_ped_exist(ped) {
+                    return Err(format!("{} hasn't spawned yet", ped));
+                }
+                if self.walking.is_waiting_for_bus(ped) {
+                    self.transit.ped_stopped_waiting(ped);
+                }
+                self.walking.delete_ped(ped, ctx.scheduler);
+                None
+            }
+            TripResult::Ok(AgentID::BusPassenger(person, bus)) => {
+                self.transit.remove_passenger(bus, person);
+                None
+            }
+            _ => None,
+        })
+    }
+    /// See `TripManager::move_vehicle`.
+    pub fn move_vehicle(&mut self, car: CarID, from: PersonID, to: PersonID) -> Result<(), String> {
+        self.trips.move_vehicle(car, from, to, &mut self.parking)
+    }
+    /// Send a car partway through a trip to a new goal. See `TripManager::reroute_active_car`.
+    pub fn reroute_active_car(
+        &mut self,
+        car: CarID,
+        new_goal: DrivingGoal,
+        map: &Map,
+    ) -> Result<(), String> {
+        self.trips.reroute_active_car(
+            self.time,
+            car,
+            new_goal,
+            &mut self.driving,
+            &mut self.parking,
+            map,
+        )?;
+        if self.trips.reserve_parking_on_spawn {
+            self.driving
+                .reserve_parking_spot(car, &mut self.parking, map);
+        }
+        Ok(())
+    }
     pub(crate) fn seed_parked_car(&mut self, vehicle: Vehicle, spot: ParkingSpot) {
         self.parking.reserve_spot(spot);
         self.parking.add_parked_car(ParkedCar {
@@ -435,12 +616,23 @@ impl Sim {
                     create_car.clone(),
                     map,
                     &self.intersections,
-                    &self.parking,
+                    &mut self.parking,
                     &mut self.scheduler,
                 ) {
+                    if create_car.trip_and_person.is_some() && self.trips.reserve_parking_on_spawn {
+                        self.driving.reserve_parking_spot(
+                            create_car.vehicle.id,
+                            &mut self.parking,
+                            map,
+                        );
+                    }
                     if let Some((trip, person)) = create_car.trip_and_person {
-                        self.trips
-                            .agent_starting_trip_leg(AgentID::Car(create_car.vehicle.id), trip);
+                        self.trips.agent_starting_trip_leg(
+                            AgentID::Car(create_car.vehicle.id),
+                            trip,
+                            create_car.req.constraints,
+                            create_car.router.get_path(),
+                        );
                         events.push(Event::TripPhaseStarting(
                             trip,
                             person,
@@ -467,40 +659,63 @@ impl Sim {
                     }
                     self.analytics
                         .record_demand(create_car.router.get_path(), map);
-                } else if retry_if_no_room {
-                    // TODO Record this in the trip log
-                    self.scheduler.push(
-                        self.time + BLIND_RETRY_TO_SPAWN,
-                        Command::SpawnCar(create_car, retry_if_no_room),
-                    );
                 } else {
                     // Buses don't use Command::SpawnCar, so this must exist.
                     let (trip, person) = create_car.trip_and_person.unwrap();
-                    // Have to redeclare for the borrow checker
-                    let mut ctx = Ctx {
-                        parking: &mut self.parking,
-                        intersections: &mut self.intersections,
-                        cap: &mut self.cap,
-                        scheduler: &mut self.scheduler,
-                        map,
+                    let attempts = if retry_if_no_room {
+                        Some(self.trips.spawn_failed(trip))
+                    } else {
+                        None
                     };
-                    self.trips.cancel_trip(
-                        self.time,
-                        trip,
-                        format!(
-                            "no room to spawn car for {} by {}, not retrying",
-                            trip, person
-                        ),
-                        Some(create_car.vehicle),
-                        &mut ctx,
-                    );
+                    match attempts {
+                        Some(n) if n < MAX_SPAWN_ATTEMPTS => {
+                            // TODO Record this in the trip log
+                            self.scheduler.push(
+                                self.time
+                                    + (BLIND_RETRY_TO_SPAWN * (n as f64)).min(MAX_RETRY_TO_SPAWN),
+                                Command::SpawnCar(create_car, retry_if_no_room),
+                            );
+                        }
+                        _ => {
+                            let reason = if let Some(n) = attempts {
+                                format!(
+                                    "no room to spawn car for {} by {} after {} attempts",
+                                    trip, person, n
+                                )
+                            } else {
+                                format!(
+                                    "no room to spawn car for {} by {}, not retrying",
+                                    trip, person
+                                )
+                            };
+                            // Have to redeclare for the borrow checker
+                            let mut ctx = Ctx {
+                                parking: &mut self.parking,
+                                intersections: &mut self.intersections,
+                                cap: &mut self.cap,
+                                scheduler: &mut self.scheduler,
+                                map,
+                            };
+                            self.trips.cancel_trip(
+                                self.time,
+                                trip,
+                                CancellationReason::Other(reason),
+                                Some(create_car.vehicle),
+                                &mut ctx,
+                            );
+                        }
+                    }
                 }
             }
             Command::SpawnPed(create_ped) => {
                 // Do the order a bit backwards so we don't have to clone the
                 // CreatePedestrian. spawn_ped can't fail.
-                self.trips
-                    .agent_starting_trip_leg(AgentID::Pedestrian(create_ped.id), create_ped.trip);
+                self.trips.agent_starting_trip_leg(
+                    AgentID::Pedestrian(create_ped.id),
+                    create_ped.trip,
+                    create_ped.req.constraints,
+                    &create_ped.path,
+                );
                 events.push(Event::TripPhaseStarting(
                     create_ped.trip,
                     create_ped.person,
@@ -575,9 +790,62 @@ impl Sim {
             Command::FinishRemoteTrip(trip) => {
                 self.trips.remote_trip_finished(self.time, trip, &mut ctx);
             }
+            Command::FinishDwell(trip) => {
+                self.trips.finish_dwell(self.time, trip, &mut ctx);
+            }
             Command::StartBus(r, _) => {
                 self.start_bus(map.get_br(r), map);
             }
+            Command::AbortLongTrip(trip) => {
+                let reason = CancellationReason::Other("exceeded max duration".to_string());
+                match self.trips.trip_to_agent(trip) {
+                    TripResult::Ok(_) | TripResult::ModeChange => {
+                        match self.delete_agent_for_trip(trip, map) {
+                            Ok(vehicle) => {
+                                let mut ctx = Ctx {
+                                    parking: &mut self.parking,
+                                    intersections: &mut self.intersections,
+                                    cap: &mut self.cap,
+                                    scheduler: &mut self.scheduler,
+                                    map,
+                                };
+                                self.trips
+                                    .abort_trip(self.time, trip, reason, vehicle, &mut ctx);
+                            }
+                            // The agent is still waiting to spawn, so check again later
+                            Err(_) => {
+                                self.scheduler.push(
+                                    self.time + RECHECK_LONG_TRIP,
+                                    Command::AbortLongTrip(trip),
+                                );
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let new_bus_riders = self.trips.collect_new_bus_riders();
+        if !new_bus_riders.is_empty() {
+            let mut ctx = Ctx {
+                parking: &mut self.parking,
+                intersections: &mut self.intersections,
+                cap: &mut self.cap,
+                scheduler: &mut self.scheduler,
+                map,
+            };
+            for (person, bus, maybe_stop2) in new_bus_riders {
+                match self.transit.add_passenger(bus, person, maybe_stop2) {
+                    Some(stop) => {
+                        self.trips.bus_rider_boarded(person, bus, stop);
+                    }
+                    None => {
+                        self.trips
+                            .bus_rider_cant_board(self.time, person, bus, &mut ctx);
+                    }
+                }
+            }
         }
 
         // Record events at precisely the time they occur.
@@ -593,10 +861,23 @@ impl Sim {
         events.extend(self.walking.collect_events());
         events.extend(self.intersections.collect_events());
         events.extend(self.parking.collect_events());
+        events.extend(self.cap.collect_events());
         for ev in events {
             if let Some(ref mut m) = self.pandemic {
                 m.handle_event(self.time, &ev, &mut self.scheduler);
             }
+            match ev {
+                Event::TripIntersectionDelay(_, turn, _, delay) => {
+                    self.trips.intersection_delay_measured(turn.parent, delay);
+                }
+                Event::PersonEntersBuilding(_, b) => {
+                    self.trips.building_occupancy_changed(self.time, b, true);
+                }
+                Event::PersonLeavesBuilding(_, b) => {
+                    self.trips.building_occupancy_changed(self.time, b, false);
+                }
+                _ => {}
+            }
 
             self.analytics.event(ev, self.time, map);
         }
@@ -612,6 +893,7 @@ impl Sim {
         let end_time = self.time + dt;
         let start = Instant::now();
         let mut last_update = Instant::now();
+        self.trips.start_step();
 
         timer.start(format!("Advance sim to {}", end_time));
         while self.time < end_time {
@@ -667,6 +949,7 @@ impl Sim {
     ) {
         let started_at = Instant::now();
         let end_time = self.time + dt;
+        self.trips.start_step();
 
         while self.time < end_time && Duration::realtime_elapsed(started_at) < real_time_limit {
             if self.minimal_step(map, end_time - self.time, maybe_cb) {
@@ -886,7 +1169,7 @@ impl Sim {
                     self.trips.cancel_trip(
                         self.time,
                         trip,
-                        format!("map edited without reset"),
+                        CancellationReason::Other(format!("map edited without reset")),
                         Some(vehicle),
                         &mut ctx,
                     );
@@ -896,7 +1179,7 @@ impl Sim {
                     self.trips.cancel_trip(
                         self.time,
                         trip,
-                        format!("map edited without reset"),
+                        CancellationReason::Other(format!("map edited without reset")),
                         None,
                         &mut ctx,
                     );
@@ -968,7 +1251,7 @@ impl Sim {
             self.trips.cancel_trip(
                 self.time,
                 trip,
-                format!("{} deleted manually through the UI", id),
+                CancellationReason::Other(format!("{} deleted manually through the UI", id)),
                 Some(vehicle),
                 &mut ctx,
             );
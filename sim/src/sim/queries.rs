@@ -5,15 +5,17 @@ use std::collections::{BTreeMap, HashSet};
 use abstutil::Counter;
 use geom::{Distance, Duration, PolyLine, Pt2D, Time};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, IntersectionID, Lane, LaneID, Map, Path, Position,
-    Traversable, TurnID,
+    BuildingID, BusRouteID, BusStopID, IntersectionID, Lane, LaneID, Map, Path, PathConstraints,
+    Position, Traversable, TurnID,
 };
 
 use crate::analytics::Window;
 use crate::{
-    AgentID, AgentType, Analytics, CarID, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput,
-    OrigPersonID, PandemicModel, ParkedCar, ParkingSim, PedestrianID, Person, PersonID,
-    PersonState, Scenario, Sim, TripID, TripInfo, TripResult, UnzoomedAgent, VehicleType,
+    AgentID, AgentType, Analytics, BlockedCause, CarID, DrawCarInput, DrawPedCrowdInput,
+    DrawPedestrianInput, OrigPersonID, PandemicModel, ParkedCar, ParkingSim, ParkingSpot,
+    PedestrianID, Person, PersonID, PersonState, Scenario, ScheduledTrip, Sim, TripEndpoint,
+    TripID, TripInfo, TripLeg, TripMode, TripPhaseType, TripPurpose, TripRecord, TripResult,
+    UnmetDemand, UnzoomedAgent, VehicleType,
 };
 
 // TODO Many of these just delegate to an inner piece. This is unorganized and hard to maintain.
@@ -37,6 +39,10 @@ impl Sim {
     pub fn num_agents(&self) -> Counter<AgentType> {
         self.trips.num_agents(&self.transit)
     }
+    /// See `TripManager::num_agents_detailed`.
+    pub fn num_agents_detailed(&self) -> Counter<AgentType> {
+        self.trips.num_agents_detailed(&self.transit, &self.parking)
+    }
     /// (total number of people, just in buildings, just off map)
     pub fn num_ppl(&self) -> (usize, usize, usize) {
         self.trips.num_ppl()
@@ -94,6 +100,11 @@ impl Sim {
         self.trips.active_agents()
     }
 
+    /// Every active agent, along with the mode of their trip and what they're currently doing.
+    pub fn active_agents_with_legs(&self) -> Vec<(AgentID, TripMode, TripPhaseType)> {
+        self.trips.active_agents_with_legs(&self.walking)
+    }
+
     pub fn agent_to_trip(&self, id: AgentID) -> Option<TripID> {
         self.trips.agent_to_trip(id)
     }
@@ -101,13 +112,25 @@ impl Sim {
     pub fn trip_to_agent(&self, id: TripID) -> TripResult<AgentID> {
         self.trips.trip_to_agent(id)
     }
+    /// See `TripManager::agent_for_trip_changed_this_step`. A step is one call to `timed_step` or
+    /// `time_limited_step`.
+    pub fn agent_for_trip_changed_this_step(&self, id: TripID) -> Option<(AgentID, bool)> {
+        self.trips.agent_for_trip_changed_this_step(id)
+    }
 
     pub fn trip_info(&self, id: TripID) -> TripInfo {
         self.trips.trip_info(id)
     }
+    pub fn person_schedule(&self, p: PersonID) -> Vec<ScheduledTrip> {
+        self.trips.person_schedule(p)
+    }
     pub fn all_trip_info(&self) -> Vec<(TripID, TripInfo)> {
         self.trips.all_trip_info()
     }
+    /// Every trip that's finished or been cancelled so far
+    pub fn export_finished_trips(&self) -> Vec<TripRecord> {
+        self.trips.export_finished_trips()
+    }
     /// If trip is finished, returns (total time, total waiting time)
     pub fn finished_trip_time(&self, id: TripID) -> Option<(Duration, Duration)> {
         self.trips.finished_trip_time(id)
@@ -116,6 +139,100 @@ impl Sim {
     pub fn trip_blocked_time(&self, id: TripID) -> Duration {
         self.trips.trip_blocked_time(id)
     }
+    pub fn trip_start_delay(&self, id: TripID) -> Option<Duration> {
+        self.trips.trip_start_delay(id)
+    }
+    pub fn num_delayed_trips(&self) -> usize {
+        self.trips.num_delayed_trips()
+    }
+    pub fn delayed_trips_for(&self, person: PersonID) -> Vec<TripID> {
+        self.trips.delayed_trips_for(person)
+    }
+    pub fn commute_trips(&self, person: PersonID) -> Vec<TripID> {
+        self.trips.commute_trips(person)
+    }
+    pub fn blocked_time_breakdown(&self, id: TripID) -> BTreeMap<BlockedCause, Duration> {
+        self.trips.blocked_time_breakdown(id)
+    }
+    /// See `TripManager::trip_distance`.
+    pub fn trip_distance(&self, id: TripID) -> Option<Distance> {
+        self.trips.trip_distance(id)
+    }
+    /// See `TripManager::trip_distance_breakdown`.
+    pub fn trip_distance_breakdown(
+        &self,
+        id: TripID,
+    ) -> Option<BTreeMap<PathConstraints, Distance>> {
+        self.trips.trip_distance_breakdown(id)
+    }
+    pub fn num_transfers(&self, id: TripID) -> usize {
+        self.trips.num_transfers(id)
+    }
+    pub fn trips_blocked_longer_than(&self, threshold: Duration) -> Vec<(TripID, Duration)> {
+        self.trips.trips_blocked_longer_than(threshold)
+    }
+    pub fn trips_between(&self, from: TripEndpoint, to: TripEndpoint) -> Vec<TripID> {
+        self.trips.trips_between(from, to)
+    }
+    pub fn trips_from_building(&self, b: BuildingID) -> Vec<TripID> {
+        self.trips.trips_from_building(b)
+    }
+    pub fn trips_to_building(&self, b: BuildingID) -> Vec<TripID> {
+        self.trips.trips_to_building(b)
+    }
+    /// Returns (available bikes, free slots) for a bike share dock
+    pub fn bike_dock_status(&self, b: BuildingID) -> Option<(usize, usize)> {
+        self.trips.bike_dock_status(b)
+    }
+    pub fn travel_times_by_mode(&self) -> BTreeMap<TripMode, Vec<Duration>> {
+        self.trips.travel_times_by_mode()
+    }
+    pub fn blocked_times_by_mode(&self) -> BTreeMap<TripMode, Vec<Duration>> {
+        self.trips.blocked_times_by_mode()
+    }
+    pub fn capped_trips(&self) -> Vec<TripID> {
+        self.trips.capped_trips()
+    }
+    pub fn num_capped(&self) -> usize {
+        self.trips.num_capped()
+    }
+    pub fn estimate_duration(
+        &self,
+        from: TripEndpoint,
+        to: TripEndpoint,
+        mode: TripMode,
+        map: &Map,
+    ) -> Option<Duration> {
+        self.trips.estimate_duration(from, to, mode, map)
+    }
+    pub fn trips_overlapping(&self, start: Time, end: Time) -> Vec<TripID> {
+        self.trips.trips_overlapping(start, end)
+    }
+    /// See `TripManager::unmet_demand`.
+    pub fn unmet_demand(&self) -> UnmetDemand {
+        self.trips.unmet_demand()
+    }
+    /// See `TripManager::mode_share_up_to`.
+    pub fn mode_share_up_to(&self, t: Time) -> BTreeMap<TripMode, usize> {
+        self.trips.mode_share_up_to(t)
+    }
+    /// See `TripManager::trip_leg_history`.
+    pub fn trip_leg_history(&self, id: TripID) -> Vec<(TripLeg, Time)> {
+        self.trips.trip_leg_history(id)
+    }
+    pub fn trips_with_purpose(&self, purpose: TripPurpose) -> Vec<TripID> {
+        self.trips.trips_with_purpose(purpose)
+    }
+    pub fn purpose_histogram(&self) -> Counter<TripPurpose> {
+        self.trips.purpose_histogram()
+    }
+    /// See `TripManager::intersection_delay`.
+    pub fn intersection_delay(&self) -> BTreeMap<IntersectionID, Duration> {
+        self.trips.intersection_delay()
+    }
+    pub fn unattributed_delay(&self) -> Duration {
+        self.trips.unattributed_delay()
+    }
 
     pub fn trip_to_person(&self, id: TripID) -> PersonID {
         self.trips.trip_to_person(id)
@@ -155,6 +272,10 @@ impl Sim {
     pub fn get_person(&self, id: PersonID) -> &Person {
         self.trips.get_person(id).unwrap()
     }
+    /// Which of a person's vehicles are parked right now, and where
+    pub fn parked_vehicles(&self, p: PersonID) -> Vec<(CarID, ParkingSpot)> {
+        self.trips.parked_vehicles(p, &self.parking)
+    }
     pub fn find_person_by_orig_id(&self, id: OrigPersonID) -> Option<PersonID> {
         for p in self.get_all_people() {
             if p.orig_id == Some(id) {
@@ -291,6 +412,14 @@ impl Sim {
     pub fn bldg_to_people(&self, b: BuildingID) -> Vec<PersonID> {
         self.trips.bldg_to_people(b)
     }
+    /// See `TripManager::person_active_span`.
+    pub fn person_active_span(&self, p: PersonID) -> Option<(Time, Time)> {
+        self.trips.person_active_span(p)
+    }
+    /// See `TripManager::building_occupancy_timeseries`.
+    pub fn building_occupancy_timeseries(&self, b: BuildingID) -> Vec<(Time, usize)> {
+        self.trips.building_occupancy_timeseries(b)
+    }
 
     pub fn get_pandemic_model(&self) -> Option<&PandemicModel> {
         self.pandemic.as_ref()
@@ -345,6 +474,11 @@ impl Sim {
         pts_per_type.into_iter().collect()
     }
 
+    /// When every finished trip ending at this border left the map, sorted by time
+    pub fn all_departures_at_border(&self, i: IntersectionID, map: &Map) -> Vec<(Time, AgentType)> {
+        self.trips.all_departures_at_border(i, map)
+    }
+
     /// (number of vehicles in the lane, penalty if a bike or other slow vehicle is present)
     pub fn target_lane_penalty(&self, lane: &Lane) -> (usize, usize) {
         if lane.is_walkable() {
@@ -364,6 +498,15 @@ impl Sim {
     pub fn generate_scenario(&self, map: &Map, name: String) -> Scenario {
         self.trips.generate_scenario(map, name)
     }
+    /// See `TripManager::generate_scenario_filtered`.
+    pub fn generate_scenario_filtered<F: Fn(&TripInfo) -> bool>(
+        &self,
+        map: &Map,
+        name: String,
+        keep: F,
+    ) -> Scenario {
+        self.trips.generate_scenario_filtered(map, name, keep)
+    }
 
     pub fn get_cap_counter(&self, l: LaneID) -> usize {
         self.cap.get_cap_counter(l)
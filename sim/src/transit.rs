@@ -355,6 +355,52 @@ impl TransitSimState {
         None
     }
 
+    /// Somebody starts their trip already riding this bus. Returns the stop the bus is at or
+    /// headed to next, which is where they count as boarding. Returns None if the bus isn't running
+    /// anymore or won't reach their stop.
+    pub fn add_passenger(
+        &mut self,
+        bus: CarID,
+        person: PersonID,
+        maybe_stop2: Option<BusStopID>,
+    ) -> Option<BusStopID> {
+        let bus = self.buses.get_mut(&bus)?;
+        let route = &self.routes[&bus.route];
+        let (boarded_at, remaining) = match bus.state {
+            BusState::DrivingToStop(idx) => (route.stops[idx].id, &route.stops[idx..]),
+            BusState::AtStop(idx) => (route.stops[idx].id, &route.stops[idx + 1..]),
+            // Past the last stop
+            BusState::DrivingOffMap => (route.stops.last().unwrap().id, &route.stops[0..0]),
+            BusState::Done => {
+                return None;
+            }
+        };
+        if let Some(stop2) = maybe_stop2 {
+            if !remaining.iter().any(|s| s.id == stop2) {
+                return None;
+            }
+        } else if route.end_at_border.is_none() {
+            // The bus will vanish at its last stop, and they'd never get off
+            return None;
+        }
+        bus.passengers.push((person, maybe_stop2));
+        Some(boarded_at)
+    }
+
+    /// Forget about somebody waiting at a stop, because their trip ended some other way.
+    pub fn ped_stopped_waiting(&mut self, ped: PedestrianID) {
+        for waiting in self.peds_waiting.values_mut() {
+            waiting.retain(|(p, _, _, _)| *p != ped);
+        }
+    }
+
+    /// Forget about somebody riding a bus, because their trip ended some other way.
+    pub fn remove_passenger(&mut self, bus: CarID, person: PersonID) {
+        if let Some(bus) = self.buses.get_mut(&bus) {
+            bus.passengers.retain(|(p, _)| *p != person);
+        }
+    }
+
     pub fn collect_events(&mut self) -> Vec<Event> {
         self.events.drain(..).collect()
     }
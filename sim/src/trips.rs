@@ -1,9 +1,12 @@
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
 
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_btreemap, serialize_btreemap, Counter};
-use geom::{Duration, Speed, Time};
+use geom::{Distance, Duration, Speed, Time};
 use map_model::{
     BuildingID, BusRouteID, BusStopID, IntersectionID, Map, Path, PathConstraints, PathRequest,
     Position,
@@ -11,13 +14,17 @@ use map_model::{
 
 use crate::sim::Ctx;
 use crate::{
-    AgentID, AgentType, AlertLocation, CarID, Command, CreateCar, CreatePedestrian, DrivingGoal,
-    Event, IndividTrip, OffMapLocation, OrigPersonID, ParkedCar, ParkingSim, ParkingSpot,
-    PedestrianID, PersonID, PersonSpec, Scenario, Scheduler, SidewalkPOI, SidewalkSpot, SpawnTrip,
+    AgentID, AgentType, AlertLocation, BikeDocks, CarID, Command, CommandType, CreateCar,
+    CreatePedestrian, DrivingGoal, DrivingSimState, Event, IndividTrip, OffMapLocation,
+    OrigPersonID, ParkedCar, ParkingSim, ParkingSimState, ParkingSpot, PedestrianID, PersonID,
+    PersonSpec, Router, Scenario, Scheduler, SidewalkPOI, SidewalkSpot, SimOptions, SpawnTrip,
     TransitSimState, TripID, TripPhaseType, TripPurpose, TripSpec, Vehicle, VehicleSpec,
     VehicleType, WalkingSimState,
 };
 
+/// Added to estimated driving trips, to cover walking to the car, unparking, and parking
+const ESTIMATED_PARKING_OVERHEAD: Duration = Duration::const_seconds(120.0);
+
 /// Manages people, each of which executes some trips through the day. Each trip is further broken
 /// down into legs -- for example, a driving trip might start with somebody walking to their car,
 /// driving somewhere, parking, and then walking to their final destination.
@@ -36,22 +43,62 @@ pub struct TripManager {
     active_trip_mode: BTreeMap<AgentID, TripID>,
     unfinished_trips: usize,
     pub pathfinding_upfront: bool,
+    /// When a car heading to park near a building starts driving, reserve a spot near the end of
+    /// its route, so that other cars don't take it first.
+    pub reserve_parking_on_spawn: bool,
+    /// Trips still going this long after their departure time get cancelled
+    max_trip_duration: Option<Duration>,
 
     car_id_counter: usize,
 
+    /// Ride-hailing vehicles owned by nobody, waiting to be dispatched
+    ride_hail_fleet: Vec<Vehicle>,
+    /// Ride-hailing vehicles currently carrying somebody
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    ride_hail_busy: BTreeMap<CarID, Vehicle>,
+    bike_docks: BikeDocks,
+    /// People who just started a trip already on a bus. The bus hasn't been told about them yet.
+    new_bus_riders: Vec<(PersonID, CarID, Option<BusStopID>)>,
+    /// Total time agents on a trip waited before each intersection, measured when they finally
+    /// start a turn through it
+    intersection_delay: BTreeMap<IntersectionID, Duration>,
+    track_building_occupancy: bool,
+    /// Only filled out if track_building_occupancy is set. Each entry is the number of people
+    /// inside just after that time.
+    building_occupancy: BTreeMap<BuildingID, Vec<(Time, usize)>>,
+    #[serde(skip)]
+    on_cancel: CancellationHook,
+    /// Trips that started a leg with a different agent since the last call to `start_step`
+    #[serde(skip)]
+    new_agents_this_step: BTreeSet<TripID>,
+
     events: Vec<Event>,
 }
 
 impl TripManager {
-    pub fn new(pathfinding_upfront: bool) -> TripManager {
+    pub fn new(opts: &SimOptions) -> TripManager {
         TripManager {
             trips: Vec::new(),
             people: Vec::new(),
             active_trip_mode: BTreeMap::new(),
             unfinished_trips: 0,
             car_id_counter: 0,
+            ride_hail_fleet: Vec::new(),
+            ride_hail_busy: BTreeMap::new(),
+            bike_docks: BikeDocks::new(),
+            new_bus_riders: Vec::new(),
+            intersection_delay: BTreeMap::new(),
+            track_building_occupancy: opts.track_building_occupancy,
+            building_occupancy: BTreeMap::new(),
+            on_cancel: CancellationHook::default(),
+            new_agents_this_step: BTreeSet::new(),
             events: Vec::new(),
-            pathfinding_upfront,
+            pathfinding_upfront: opts.pathfinding_upfront,
+            reserve_parking_on_spawn: opts.reserve_parking_on_spawn,
+            max_trip_duration: None,
         }
     }
 
@@ -80,6 +127,8 @@ impl TripManager {
             ped: PedestrianID(id.0),
             ped_speed,
             vehicles,
+            // new_trip infers this.
+            home: None,
             delayed_trips: Vec::new(),
             on_bus: None,
         });
@@ -96,6 +145,108 @@ impl TripManager {
         id
     }
 
+    /// Gives an existing person another vehicle. They can't be in the middle of a trip using a
+    /// vehicle of the same type.
+    pub fn add_vehicle(&mut self, person: PersonID, spec: VehicleSpec) -> CarID {
+        assert!(person.0 < self.people.len(), "{} doesn't exist", person);
+        if let PersonState::Trip(t) = self.people[person.0].state {
+            for leg in &self.trips[t.0].legs {
+                if let TripLeg::Drive(c, _) = leg {
+                    assert_ne!(
+                        c.1, spec.vehicle_type,
+                        "{} is using {} for {}, can't add another {:?} now",
+                        person, c, t, spec.vehicle_type
+                    );
+                }
+            }
+        }
+        let id = CarID(self.new_car_id(), spec.vehicle_type);
+        self.people[person.0]
+            .vehicles
+            .push(spec.make(id, Some(person)));
+        id
+    }
+
+    /// Transfers a vehicle between two people, like members of a household sharing one car. The
+    /// vehicle can't be in use, and the old owner can't have any unfinished trips planned with it.
+    pub fn move_vehicle(
+        &mut self,
+        car: CarID,
+        from: PersonID,
+        to: PersonID,
+        parking: &mut ParkingSimState,
+    ) -> Result<(), String> {
+        if from.0 >= self.people.len() || to.0 >= self.people.len() {
+            return Err(format!("{} or {} doesn't exist", from, to));
+        }
+        if from == to {
+            return Err(format!("{} already owns {}", from, car));
+        }
+        let idx = self.people[from.0]
+            .vehicles
+            .iter()
+            .position(|v| v.id == car)
+            .ok_or_else(|| format!("{} doesn't own {}", from, car))?;
+        if self.active_trip_mode.contains_key(&AgentID::Car(car)) {
+            return Err(format!("{} is in the middle of a trip", car));
+        }
+        for t in &self.people[from.0].trips {
+            let trip = &self.trips[t.0];
+            if trip.finished_at.is_some() || trip.info.cancellation_reason.is_some() {
+                continue;
+            }
+            if trip
+                .legs
+                .iter()
+                .any(|leg| matches!(leg, TripLeg::Drive(c, _) if *c == car))
+            {
+                return Err(format!("{} still needs {} for {}", from, car, t));
+            }
+        }
+
+        let mut vehicle = self.people[from.0].vehicles.remove(idx);
+        vehicle.owner = Some(to);
+        self.people[to.0].vehicles.push(vehicle);
+        parking.set_owner_of_car(car, to);
+        Ok(())
+    }
+
+    /// Adds a car owned by nobody that can be dispatched to people taking ride-hailing trips.
+    pub fn add_ride_hail_vehicle(&mut self, spec: VehicleSpec) -> CarID {
+        assert_eq!(spec.vehicle_type, VehicleType::Car);
+        let id = CarID(self.new_car_id(), VehicleType::Car);
+        self.ride_hail_fleet.push(spec.make(id, None));
+        id
+    }
+
+    /// Adds a dock for shared bikes at a building, initially holding some bikes.
+    pub fn add_bike_dock(&mut self, b: BuildingID, capacity: usize, bikes: Vec<VehicleSpec>) {
+        let bikes = bikes
+            .into_iter()
+            .map(|spec| {
+                assert_eq!(spec.vehicle_type, VehicleType::Bike);
+                let id = CarID(self.new_car_id(), VehicleType::Bike);
+                spec.make(id, None)
+            })
+            .collect();
+        self.bike_docks.add_dock(b, capacity, bikes);
+    }
+
+    /// Returns (available bikes, free slots) for a dock
+    pub fn bike_dock_status(&self, b: BuildingID) -> Option<(usize, usize)> {
+        self.bike_docks.dock_status(b)
+    }
+
+    /// The legs of a ride-hailing trip. Which vehicle picks somebody up isn't known until the trip
+    /// starts and dispatch fills it in.
+    pub(crate) fn ride_hail_legs(&self, goal: DrivingGoal, map: &Map) -> Vec<TripLeg> {
+        let mut legs = vec![TripLeg::RideHail(None, goal.clone())];
+        if let DrivingGoal::ParkNear(b) = goal {
+            legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
+        }
+        legs
+    }
+
     pub fn new_trip(
         &mut self,
         person: PersonID,
@@ -115,12 +266,16 @@ impl TripManager {
             Some(TripLeg::Walk(ref spot)) => match spot.connection {
                 SidewalkPOI::Building(b) => TripEndpoint::Bldg(b),
                 SidewalkPOI::Border(i, ref loc) => TripEndpoint::Border(i, loc.clone()),
+                SidewalkPOI::SuddenlyAppear => TripEndpoint::SuddenlyAppear(spot.sidewalk_pos),
                 _ => unreachable!(),
             },
-            Some(TripLeg::Drive(_, ref goal)) => match goal {
-                DrivingGoal::ParkNear(b) => TripEndpoint::Bldg(*b),
-                DrivingGoal::Border(i, _, loc) => TripEndpoint::Border(*i, loc.clone()),
-            },
+            Some(TripLeg::Drive(_, ref goal)) | Some(TripLeg::RideHail(_, ref goal)) => {
+                match goal {
+                    DrivingGoal::ParkNear(b) => TripEndpoint::Bldg(*b),
+                    DrivingGoal::Border(i, _, loc) => TripEndpoint::Border(*i, loc.clone()),
+                    DrivingGoal::Vanish(pos) => TripEndpoint::SuddenlyAppear(*pos),
+                }
+            }
             Some(TripLeg::Remote(ref to)) => {
                 TripEndpoint::Border(map.all_incoming_borders()[0].id, Some(to.clone()))
             }
@@ -131,6 +286,7 @@ impl TripManager {
             }
             _ => unreachable!(),
         };
+        let starts_on_bus = matches!(legs[0], TripLeg::RideBus(_, _));
         let trip = Trip {
             id,
             info: TripInfo {
@@ -142,12 +298,21 @@ impl TripManager {
                 modified,
                 capped: false,
                 cancellation_reason: None,
+                transfers: 0,
             },
             person,
-            started: false,
+            started_at: None,
             finished_at: None,
             total_blocked_time: Duration::ZERO,
+            blocked_time_breakdown: BTreeMap::new(),
+            total_distance: Distance::ZERO,
+            distance_breakdown: BTreeMap::new(),
+            spawn_attempts: 0,
+            pathfinding_upfront: self.pathfinding_upfront,
+            starts_on_bus,
+            boarded_at: None,
             legs: VecDeque::from(legs),
+            completed_legs: Vec::new(),
         };
         self.unfinished_trips += 1;
         let person = &mut self.people[trip.person.0];
@@ -165,8 +330,21 @@ impl TripManager {
                     }
                     PersonState::OffMap
                 }
+                TripEndpoint::SuddenlyAppear(_) => PersonState::OffMap,
             };
         }
+        // Guess where somebody lives: the start of their first trip, or else the first place they
+        // head home to.
+        if person.trips.is_empty() {
+            if let TripEndpoint::Bldg(b) = trip.info.start {
+                person.home = Some(b);
+            }
+        }
+        if let (None, TripPurpose::Home, TripEndpoint::Bldg(b)) =
+            (person.home, trip.info.purpose, &trip.info.end)
+        {
+            person.home = Some(*b);
+        }
         if let Some(t) = person.trips.last() {
             // TODO If it's exactly ==, what?! See the ID.
             if self.trips[t.0].info.departure > trip.info.departure {
@@ -181,11 +359,178 @@ impl TripManager {
         id
     }
 
-    pub fn agent_starting_trip_leg(&mut self, agent: AgentID, t: TripID) {
+    /// Creates one trip that visits a chain of waypoints, lingering at each one for some duration
+    /// before continuing. Every waypoint except the last must be a building; the dwell time of the
+    /// final waypoint is ignored. Driving and biking trips only start from a building, using the
+    /// person's `use_vehicle`. The trip is scheduled to start at `departure`.
+    ///
+    /// Nothing is created if some waypoint can't be used or reached.
+    pub fn new_trip_with_waypoints(
+        &mut self,
+        person: PersonID,
+        departure: Time,
+        start: TripEndpoint,
+        mode: TripMode,
+        use_vehicle: Option<CarID>,
+        purpose: TripPurpose,
+        modified: bool,
+        waypoints: Vec<(TripEndpoint, Duration)>,
+        map: &Map,
+        scheduler: &mut Scheduler,
+    ) -> Result<TripID, String> {
+        if waypoints.is_empty() {
+            return Err(format!("{} has a trip without any waypoints", person));
+        }
+        let num_waypoints = waypoints.len();
+        let mut legs = Vec::new();
+        let mut first_spec = None;
+        let mut from = start.clone();
+        for (idx, (to, dwell)) in waypoints.into_iter().enumerate() {
+            let spec = match mode {
+                TripMode::Walk => {
+                    let start = from
+                        .start_sidewalk_spot(map)
+                        .ok_or_else(|| format!("can't start walking from {:?}", from))?;
+                    let goal = to
+                        .end_sidewalk_spot(map)
+                        .ok_or_else(|| format!("can't walk to {:?}", to))?;
+                    let req = PathRequest {
+                        start: start.sidewalk_pos,
+                        end: goal.sidewalk_pos,
+                        constraints: PathConstraints::Pedestrian,
+                    };
+                    if map.pathfind(req).is_none() {
+                        return Err(format!("can't walk from {:?} to {:?}: no path", from, to));
+                    }
+                    legs.push(TripLeg::Walk(goal.clone()));
+                    TripSpec::JustWalking { start, goal }
+                }
+                // Scooters ride like bikes for now
+                TripMode::Drive | TripMode::Bike | TripMode::Scooter => {
+                    let from_bldg = match from {
+                        TripEndpoint::Bldg(b) => b,
+                        TripEndpoint::Border(_, _) | TripEndpoint::SuddenlyAppear(_) => {
+                            return Err(format!(
+                                "{:?} trips with waypoints must start at a building, not {:?}",
+                                mode, from
+                            ));
+                        }
+                    };
+                    let vehicle = use_vehicle
+                        .ok_or_else(|| format!("{:?} trip for {} has no vehicle", mode, person))?;
+                    let constraints = mode.to_constraints();
+                    let goal = to
+                        .driving_goal(constraints, map)
+                        .ok_or_else(|| format!("can't {:?} to {:?}", mode, to))?;
+                    // The vehicle is parked near the previous waypoint
+                    let reachable = DrivingGoal::ParkNear(from_bldg)
+                        .goal_pos(constraints, map)
+                        .zip(goal.goal_pos(constraints, map))
+                        .and_then(|(start, end)| {
+                            map.pathfind(PathRequest {
+                                start,
+                                end,
+                                constraints,
+                            })
+                        })
+                        .is_some();
+                    if !reachable {
+                        return Err(format!(
+                            "can't {:?} from {:?} to {:?}: no path",
+                            mode, from, to
+                        ));
+                    }
+                    let spec = if mode == TripMode::Drive {
+                        legs.push(TripLeg::Walk(SidewalkSpot::deferred_parking_spot()));
+                        TripSpec::UsingParkedCar {
+                            car: vehicle,
+                            start_bldg: from_bldg,
+                            goal: goal.clone(),
+                        }
+                    } else {
+                        legs.push(TripLeg::Walk(
+                            SidewalkSpot::bike_rack(from_bldg, map)
+                                .ok_or_else(|| format!("no place to bike from {}", from_bldg))?,
+                        ));
+                        TripSpec::UsingBike {
+                            bike: vehicle,
+                            start: from_bldg,
+                            goal: goal.clone(),
+                        }
+                    };
+                    legs.push(TripLeg::Drive(vehicle, goal.clone()));
+                    if let DrivingGoal::ParkNear(b) = goal {
+                        legs.push(TripLeg::Walk(SidewalkSpot::building(b, map)));
+                    }
+                    spec
+                }
+                // TODO Figure out which routes to take between each waypoint
+                TripMode::Transit => {
+                    return Err("Transit trips with waypoints aren't supported yet".to_string());
+                }
+            };
+            if first_spec.is_none() {
+                first_spec = Some(spec);
+            }
+            if idx != num_waypoints - 1 {
+                match to {
+                    TripEndpoint::Bldg(b) => {
+                        legs.push(TripLeg::Dwell(b, dwell));
+                    }
+                    TripEndpoint::Border(_, _) | TripEndpoint::SuddenlyAppear(_) => {
+                        return Err(format!(
+                            "only the last waypoint of a trip can be off a building, not {:?}",
+                            to
+                        ));
+                    }
+                }
+            }
+            from = to;
+        }
+
+        let id = self.new_trip(person, departure, start, mode, purpose, modified, legs, map);
+        let spec = first_spec.unwrap();
+        let maybe_req = spec.get_pathfinding_request(map);
+        let maybe_path = if self.pathfinding_upfront {
+            maybe_req.clone().and_then(|req| map.pathfind(req))
+        } else {
+            None
+        };
+        scheduler.push(
+            departure,
+            Command::StartTrip(id, spec, maybe_req, maybe_path),
+        );
+        Ok(id)
+    }
+
+    /// A vehicle for this trip couldn't spawn because there was no room. Returns the number of
+    /// failed attempts so far, including this one.
+    pub fn spawn_failed(&mut self, trip: TripID) -> usize {
+        let trip = &mut self.trips[trip.0];
+        trip.spawn_attempts += 1;
+        trip.spawn_attempts
+    }
+
+    /// The leg's path is measured here, since routers aren't kept around after the trip finishes.
+    pub fn agent_starting_trip_leg(
+        &mut self,
+        agent: AgentID,
+        t: TripID,
+        constraints: PathConstraints,
+        path: &Path,
+    ) {
         if let Some(other) = self.active_trip_mode.get(&agent) {
             panic!("{} is doing both {} and {}?", agent, t, other);
         }
         self.active_trip_mode.insert(agent, t);
+        let trip = &mut self.trips[t.0];
+        let dist = path.total_length();
+        trip.total_distance += dist;
+        *trip
+            .distance_breakdown
+            .entry(constraints)
+            .or_insert(Distance::ZERO) += dist;
+        self.new_agents_this_step.insert(t);
     }
 
     pub fn car_reached_parking_spot(
@@ -193,13 +538,13 @@ impl TripManager {
         now: Time,
         car: CarID,
         spot: ParkingSpot,
-        blocked_time: Duration,
+        blocked_time: &BTreeMap<BlockedCause, Duration>,
         ctx: &mut Ctx,
     ) {
         let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
-        trip.total_blocked_time += blocked_time;
+        trip.add_blocked_time_breakdown(blocked_time);
 
-        match trip.legs.pop_front() {
+        match trip.finish_leg(now) {
             Some(TripLeg::Drive(c, DrivingGoal::ParkNear(_))) => {
                 assert_eq!(car, c);
             }
@@ -209,6 +554,13 @@ impl TripManager {
         match &trip.legs[0] {
             TripLeg::Walk(to) => match (spot, &to.connection) {
                 (ParkingSpot::Offstreet(b1, _), SidewalkPOI::Building(b2)) if b1 == *b2 => {
+                    if trip.legs.len() > 1 {
+                        // Parked right at a waypoint
+                        trip.finish_leg(now);
+                        let id = trip.id;
+                        self.start_dwell(now, id, b1, ctx);
+                        return;
+                    }
                     // Do the relevant parts of ped_reached_parking_spot.
                     assert_eq!(trip.legs.len(), 1);
                     assert!(!trip.finished_at.is_some());
@@ -257,9 +609,9 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
-        trip.total_blocked_time += blocked_time;
+        trip.add_blocked_time(BlockedCause::Intersection, blocked_time);
 
-        trip.assert_walking_leg(SidewalkSpot::deferred_parking_spot());
+        trip.assert_walking_leg(now, SidewalkSpot::deferred_parking_spot());
         let parked_car = ctx.parking.get_car_at_spot(spot).unwrap().clone();
         let drive_to = match trip.legs[0] {
             TripLeg::Drive(c, ref to) => {
@@ -297,6 +649,7 @@ impl TripManager {
                 path,
                 now,
                 parked_car.vehicle.id,
+                trip.id,
                 &mut trip.info.capped,
                 ctx.map,
             )
@@ -321,15 +674,10 @@ impl TripManager {
             None => {
                 // Move the car to the destination...
                 ctx.parking.remove_parked_car(parked_car.clone());
+                let msg = format!("no path to drive from {} to {}", req.start, req.end);
+                let reason = CancellationReason::path_failed(req, trip.info.capped, msg);
                 let trip = trip.id;
-                // TODO The reason might be exceeding the cap
-                self.cancel_trip(
-                    now,
-                    trip,
-                    format!("no path to drive from {} to {}", start, end),
-                    Some(parked_car.vehicle),
-                    ctx,
-                );
+                self.cancel_trip(now, trip, reason, Some(parked_car.vehicle), ctx);
             }
         }
     }
@@ -347,11 +695,21 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
-        trip.total_blocked_time += blocked_time;
+        trip.add_blocked_time(BlockedCause::Intersection, blocked_time);
 
-        trip.assert_walking_leg(spot.clone());
+        trip.assert_walking_leg(now, spot.clone());
         let (bike, drive_to) = match trip.legs[0] {
-            TripLeg::Drive(bike, ref to) => (bike, to.clone()),
+            TripLeg::Drive(bike, ref to) => {
+                (self.people[trip.person.0].get_vehicle(bike), to.clone())
+            }
+            TripLeg::BikeShare(from, to) => match self.bike_docks.check_out(trip.id, from, to) {
+                Ok(bike) => (bike, DrivingGoal::ParkNear(to)),
+                Err(err) => {
+                    let trip = trip.id;
+                    self.cancel_trip(now, trip, CancellationReason::Other(err), None, ctx);
+                    return;
+                }
+            },
             _ => unreachable!(),
         };
         let driving_pos = match spot.connection {
@@ -366,7 +724,7 @@ impl TripManager {
             self.cancel_trip(
                 now,
                 trip,
-                format!("no bike connection at {:?}", drive_to),
+                CancellationReason::Other(format!("no bike connection at {:?}", drive_to)),
                 None,
                 ctx,
             );
@@ -380,19 +738,12 @@ impl TripManager {
         if let Some(router) = ctx
             .map
             .pathfind(req.clone())
-            .map(|path| drive_to.make_router(bike, path, ctx.map))
+            .map(|path| drive_to.make_router(bike.id, path, ctx.map))
         {
             ctx.scheduler.push(
                 now,
                 Command::SpawnCar(
-                    CreateCar::for_appearing(
-                        self.people[trip.person.0].get_vehicle(bike),
-                        driving_pos,
-                        router,
-                        req,
-                        trip.id,
-                        trip.person,
-                    ),
+                    CreateCar::for_appearing(bike, driving_pos, router, req, trip.id, trip.person),
                     true,
                 ),
             );
@@ -401,10 +752,10 @@ impl TripManager {
             self.cancel_trip(
                 now,
                 trip,
-                format!(
+                CancellationReason::Other(format!(
                     "no path for the bike portion (or sidewalk connection at end), from {} to {}",
                     driving_pos, end
-                ),
+                )),
                 None,
                 ctx,
             );
@@ -416,7 +767,7 @@ impl TripManager {
         now: Time,
         bike: CarID,
         bike_rack: SidewalkSpot,
-        blocked_time: Duration,
+        blocked_time: &BTreeMap<BlockedCause, Duration>,
         map: &Map,
         scheduler: &mut Scheduler,
     ) {
@@ -425,12 +776,16 @@ impl TripManager {
             bike_rack.sidewalk_pos.lane(),
         ));
         let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(bike)).unwrap().0];
-        trip.total_blocked_time += blocked_time;
+        trip.add_blocked_time_breakdown(blocked_time);
 
-        match trip.legs.pop_front() {
+        match trip.finish_leg(now) {
             Some(TripLeg::Drive(c, DrivingGoal::ParkNear(_))) => {
                 assert_eq!(c, bike);
             }
+            Some(TripLeg::BikeShare(_, _)) => {
+                assert_eq!(self.bike_docks.bike_for_trip(trip.id), Some(bike));
+                self.bike_docks.check_in(trip.id);
+            }
             _ => unreachable!(),
         };
 
@@ -446,6 +801,39 @@ impl TripManager {
         }
     }
 
+    pub fn person_dropped_off(
+        &mut self,
+        now: Time,
+        car: CarID,
+        spot: SidewalkSpot,
+        blocked_time: &BTreeMap<BlockedCause, Duration>,
+        ctx: &mut Ctx,
+    ) {
+        let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        trip.add_blocked_time_breakdown(blocked_time);
+
+        match trip.finish_leg(now) {
+            Some(TripLeg::RideHail(c, DrivingGoal::ParkNear(_))) => {
+                assert_eq!(c, Some(car));
+            }
+            _ => unreachable!(),
+        };
+        // The vehicle vanishes after the drop-off and is ready to be dispatched again.
+        let vehicle = self.ride_hail_busy.remove(&car).unwrap();
+        self.ride_hail_fleet.push(vehicle);
+
+        if !trip.spawn_ped(
+            now,
+            spot,
+            &self.people[trip.person.0],
+            ctx.map,
+            ctx.scheduler,
+            &mut self.events,
+        ) {
+            self.unfinished_trips -= 1;
+        }
+    }
+
     pub fn ped_reached_building(
         &mut self,
         now: Time,
@@ -459,10 +847,14 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
-        trip.total_blocked_time += blocked_time;
+        trip.add_blocked_time(BlockedCause::Intersection, blocked_time);
 
-        trip.assert_walking_leg(SidewalkSpot::building(bldg, ctx.map));
-        assert!(trip.legs.is_empty());
+        trip.assert_walking_leg(now, SidewalkSpot::building(bldg, ctx.map));
+        if !trip.legs.is_empty() {
+            let id = trip.id;
+            self.start_dwell(now, id, bldg, ctx);
+            return;
+        }
         assert!(!trip.finished_at.is_some());
         trip.finished_at = Some(now);
         self.unfinished_trips -= 1;
@@ -489,7 +881,7 @@ impl TripManager {
         transit: &mut TransitSimState,
     ) -> Option<BusRouteID> {
         let trip = &mut self.trips[self.active_trip_mode[&AgentID::Pedestrian(ped)].0];
-        trip.total_blocked_time += blocked_time;
+        trip.add_blocked_time(BlockedCause::Intersection, blocked_time);
 
         match trip.legs[0] {
             TripLeg::Walk(ref spot) => {
@@ -515,13 +907,13 @@ impl TripManager {
                     maybe_stop2,
                     ctx.map,
                 ) {
-                    trip.legs.pop_front();
+                    trip.finish_leg(now);
+                    trip.boarded_at = Some(stop);
+                    let (id, person) = (trip.id, trip.person);
                     self.active_trip_mode
                         .remove(&AgentID::Pedestrian(ped))
                         .unwrap();
-                    self.active_trip_mode
-                        .insert(AgentID::BusPassenger(trip.person, bus), trip.id);
-                    self.people[trip.person.0].on_bus = Some(bus);
+                    self.start_riding_bus(id, person, bus);
                     None
                 } else {
                     Some(route)
@@ -544,14 +936,62 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
-        trip.total_blocked_time += blocked_time;
+        trip.add_blocked_time(BlockedCause::Transit, blocked_time);
 
-        trip.legs.pop_front();
+        if let Some(TripLeg::Walk(spot)) = trip.finish_leg(now) {
+            if let SidewalkPOI::BusStop(stop) = spot.connection {
+                trip.boarded_at = Some(stop);
+            }
+        }
         walking.ped_boarded_bus(now, ped);
+        let (id, person) = (trip.id, trip.person);
+        self.start_riding_bus(id, person, bus);
+        (id, person)
+    }
+
+    /// Somebody is now riding a bus, either by boarding it or by starting their trip on it.
+    fn start_riding_bus(&mut self, trip: TripID, person: PersonID, bus: CarID) {
         self.active_trip_mode
-            .insert(AgentID::BusPassenger(trip.person, bus), trip.id);
-        self.people[trip.person.0].on_bus = Some(bus);
-        (trip.id, trip.person)
+            .insert(AgentID::BusPassenger(person, bus), trip);
+        self.people[person.0].on_bus = Some(bus);
+        self.new_agents_this_step.insert(trip);
+    }
+
+    /// People who started a trip on a bus since the last call, along with where they're going.
+    pub(crate) fn collect_new_bus_riders(&mut self) -> Vec<(PersonID, CarID, Option<BusStopID>)> {
+        std::mem::replace(&mut self.new_bus_riders, Vec::new())
+    }
+
+    /// Somebody started a trip already riding a bus, which is at or headed to this stop.
+    pub fn bus_rider_boarded(&mut self, person: PersonID, bus: CarID, stop: BusStopID) {
+        let trip = &mut self.trips[self.active_trip_mode[&AgentID::BusPassenger(person, bus)].0];
+        trip.boarded_at = Some(stop);
+        let route = match trip.legs[0] {
+            TripLeg::RideBus(route, _) => route,
+            _ => unreachable!(),
+        };
+        self.events.push(Event::TripPhaseStarting(
+            trip.id,
+            person,
+            None,
+            TripPhaseType::RidingBus(route, stop, bus),
+        ));
+    }
+
+    /// Somebody started a trip on a bus, but it turned out to not be running anymore.
+    pub fn bus_rider_cant_board(&mut self, now: Time, person: PersonID, bus: CarID, ctx: &mut Ctx) {
+        let trip = self
+            .active_trip_mode
+            .remove(&AgentID::BusPassenger(person, bus))
+            .unwrap();
+        self.people[person.0].on_bus = None;
+        self.cancel_trip(
+            now,
+            trip,
+            CancellationReason::Other(format!("{} isn't running", bus)),
+            None,
+            ctx,
+        );
     }
 
     // TODO Need to characterize delay the bus experienced
@@ -561,7 +1001,7 @@ impl TripManager {
             .remove(&AgentID::BusPassenger(person, bus))
             .unwrap()
             .0];
-        let start = match trip.legs.pop_front().unwrap() {
+        let start = match trip.finish_leg(now).unwrap() {
             TripLeg::RideBus(_, maybe_stop2) => SidewalkSpot::bus_stop(
                 maybe_stop2.expect("someone left a bus, even though they should've ridden off-map"),
                 ctx.map,
@@ -569,6 +1009,14 @@ impl TripManager {
             _ => unreachable!(),
         };
         self.people[person.0].on_bus.take().unwrap();
+        // Riding off-map never gets here, so only completed transfers are counted.
+        if trip
+            .legs
+            .iter()
+            .any(|leg| matches!(leg, TripLeg::RideBus(_, _)))
+        {
+            trip.info.transfers += 1;
+        }
 
         if !trip.spawn_ped(
             now,
@@ -595,9 +1043,9 @@ impl TripManager {
             .remove(&AgentID::Pedestrian(ped))
             .unwrap()
             .0];
-        trip.total_blocked_time += blocked_time;
+        trip.add_blocked_time(BlockedCause::Intersection, blocked_time);
 
-        match trip.legs.pop_front() {
+        match trip.finish_leg(now) {
             Some(TripLeg::Walk(spot)) => match spot.connection {
                 SidewalkPOI::Border(i2, _) => assert_eq!(i, i2),
                 _ => unreachable!(),
@@ -627,6 +1075,41 @@ impl TripManager {
         self.person_finished_trip(now, person, ctx);
     }
 
+    /// A pedestrian reached a `TripEndpoint::SuddenlyAppear` and vanishes.
+    pub fn ped_vanished(
+        &mut self,
+        now: Time,
+        ped: PedestrianID,
+        blocked_time: Duration,
+        ctx: &mut Ctx,
+    ) {
+        let trip = &mut self.trips[self
+            .active_trip_mode
+            .remove(&AgentID::Pedestrian(ped))
+            .unwrap()
+            .0];
+        trip.add_blocked_time(BlockedCause::Intersection, blocked_time);
+
+        match trip.finish_leg(now) {
+            Some(TripLeg::Walk(spot)) => assert_eq!(spot.connection, SidewalkPOI::SuddenlyAppear),
+            _ => unreachable!(),
+        }
+        assert!(trip.legs.is_empty());
+        assert!(!trip.finished_at.is_some());
+        trip.finished_at = Some(now);
+        self.unfinished_trips -= 1;
+        self.events.push(Event::TripFinished {
+            trip: trip.id,
+            mode: trip.info.mode,
+            total_time: now - trip.info.departure,
+            blocked_time: trip.total_blocked_time,
+        });
+        let person = trip.person;
+        // TODO There's no state for somebody standing around on a sidewalk
+        self.people[person.0].state = PersonState::OffMap;
+        self.person_finished_trip(now, person, ctx);
+    }
+
     pub fn transit_rider_reached_border(
         &mut self,
         now: Time,
@@ -637,7 +1120,7 @@ impl TripManager {
         let agent = AgentID::BusPassenger(person, bus);
         let trip = &mut self.trips[self.active_trip_mode.remove(&agent).unwrap().0];
 
-        match trip.legs.pop_front() {
+        match trip.finish_leg(now) {
             Some(TripLeg::RideBus(_, maybe_spot2)) => assert!(maybe_spot2.is_none()),
             _ => unreachable!(),
         }
@@ -667,17 +1150,23 @@ impl TripManager {
         now: Time,
         car: CarID,
         i: IntersectionID,
-        blocked_time: Duration,
+        blocked_time: &BTreeMap<BlockedCause, Duration>,
         ctx: &mut Ctx,
     ) {
         let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
-        trip.total_blocked_time += blocked_time;
+        trip.add_blocked_time_breakdown(blocked_time);
 
-        match trip.legs.pop_front().unwrap() {
+        match trip.finish_leg(now).unwrap() {
             TripLeg::Drive(c, DrivingGoal::Border(int, _, _)) => {
                 assert_eq!(car, c);
                 assert_eq!(i, int);
             }
+            TripLeg::RideHail(c, DrivingGoal::Border(int, _, _)) => {
+                assert_eq!(Some(car), c);
+                assert_eq!(i, int);
+                let vehicle = self.ride_hail_busy.remove(&car).unwrap();
+                self.ride_hail_fleet.push(vehicle);
+            }
             _ => unreachable!(),
         };
         assert!(trip.legs.is_empty());
@@ -703,11 +1192,26 @@ impl TripManager {
         self.person_finished_trip(now, person, ctx);
     }
 
-    pub fn remote_trip_finished(&mut self, now: Time, id: TripID, ctx: &mut Ctx) {
-        let trip = &mut self.trips[id.0];
+    /// A car or bike reached a `TripEndpoint::SuddenlyAppear` and vanishes.
+    pub fn car_or_bike_vanished(
+        &mut self,
+        now: Time,
+        car: CarID,
+        blocked_time: &BTreeMap<BlockedCause, Duration>,
+        ctx: &mut Ctx,
+    ) {
+        let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        trip.add_blocked_time_breakdown(blocked_time);
 
-        let to = match trip.legs.pop_front() {
-            Some(TripLeg::Remote(to)) => to,
+        match trip.finish_leg(now).unwrap() {
+            TripLeg::Drive(c, DrivingGoal::Vanish(_)) => {
+                assert_eq!(car, c);
+            }
+            TripLeg::RideHail(c, DrivingGoal::Vanish(_)) => {
+                assert_eq!(Some(car), c);
+                let vehicle = self.ride_hail_busy.remove(&car).unwrap();
+                self.ride_hail_fleet.push(vehicle);
+            }
             _ => unreachable!(),
         };
         assert!(trip.legs.is_empty());
@@ -721,67 +1225,380 @@ impl TripManager {
             blocked_time: trip.total_blocked_time,
         });
         let person = trip.person;
-        self.events
-            .push(Event::PersonEntersRemoteBuilding(person, to));
         self.people[person.0].state = PersonState::OffMap;
         self.person_finished_trip(now, person, ctx);
     }
 
-    /// Cancel a trip before it's started. The person will stay where they are.
-    pub fn cancel_unstarted_trip(&mut self, id: TripID, reason: String) {
-        let trip = &mut self.trips[id.0];
-        self.unfinished_trips -= 1;
-        trip.info.cancellation_reason = Some(reason);
-        self.events.push(Event::TripCancelled(trip.id));
+    /// The person reached an intermediate waypoint of their trip and lingers there for a bit.
+    fn start_dwell(&mut self, now: Time, id: TripID, bldg: BuildingID, ctx: &mut Ctx) {
+        let trip = &self.trips[id.0];
+        let dwell = match trip.legs[0] {
+            TripLeg::Dwell(b, dwell) => {
+                assert_eq!(b, bldg);
+                dwell
+            }
+            _ => unreachable!(),
+        };
+        self.events
+            .push(Event::PersonEntersBuilding(trip.person, bldg));
+        self.events.push(Event::TripPhaseStarting(
+            id,
+            trip.person,
+            None,
+            TripPhaseType::Dwelling,
+        ));
+        ctx.scheduler.push(now + dwell, Command::FinishDwell(id));
     }
 
-    /// Cancel a trip after it's started. The person will be magically warped to their destination,
-    /// along with their car, as if the trip had completed normally.
-    pub fn cancel_trip(
-        &mut self,
-        now: Time,
-        id: TripID,
-        reason: String,
-        abandoned_vehicle: Option<Vehicle>,
-        ctx: &mut Ctx,
-    ) {
+    pub fn finish_dwell(&mut self, now: Time, id: TripID, ctx: &mut Ctx) {
         let trip = &mut self.trips[id.0];
-        self.unfinished_trips -= 1;
-        trip.info.cancellation_reason = Some(reason);
-        self.events.push(Event::TripCancelled(trip.id));
-        let person = trip.person;
-
-        // Maintain consistentency for anyone listening to events
-        if let PersonState::Inside(b) = self.people[person.0].state {
-            self.events.push(Event::PersonLeavesBuilding(person, b));
+        if trip.info.cancellation_reason.is_some() {
+            return;
         }
-        match trip.info.end {
-            TripEndpoint::Bldg(b) => {
-                self.events.push(Event::PersonEntersBuilding(person, b));
-            }
-            TripEndpoint::Border(i, ref loc) => {
-                self.events
-                    .push(Event::PersonLeavesMap(person, None, i, loc.clone()));
+        let bldg = match trip.finish_leg(now) {
+            Some(TripLeg::Dwell(b, _)) => b,
+            _ => unreachable!(),
+        };
+        self.events
+            .push(Event::PersonLeavesBuilding(trip.person, bldg));
+        let start = SidewalkSpot::building(bldg, ctx.map);
+
+        // Continuing to drive means walking back to wherever the car was parked
+        let use_parked_car = match (&trip.legs[0], trip.legs.get(1)) {
+            (TripLeg::Walk(spot), Some(TripLeg::Drive(car, _)))
+                if spot.connection == SidewalkPOI::DeferredParkingSpot =>
+            {
+                Some(*car)
             }
-        }
-
-        // Warp to the destination
-        self.people[person.0].state = match trip.info.end {
-            TripEndpoint::Bldg(b) => PersonState::Inside(b),
-            TripEndpoint::Border(_, _) => PersonState::OffMap,
+            _ => None,
         };
-        // Don't forget the car!
-        if let Some(vehicle) = abandoned_vehicle {
-            if vehicle.vehicle_type == VehicleType::Car {
-                if let TripEndpoint::Bldg(b) = trip.info.end {
+        let car = if let Some(car) = use_parked_car {
+            car
+        } else {
+            if !trip.spawn_ped(
+                now,
+                start,
+                &self.people[trip.person.0],
+                ctx.map,
+                ctx.scheduler,
+                &mut self.events,
+            ) {
+                self.unfinished_trips -= 1;
+            }
+            return;
+        };
+
+        if let Some(parked_car) = ctx.parking.lookup_parked_car(car).cloned() {
+            let walking_goal = SidewalkSpot::parking_spot(parked_car.spot, ctx.map, ctx.parking);
+            let req = PathRequest {
+                start: start.sidewalk_pos,
+                end: walking_goal.sidewalk_pos,
+                constraints: PathConstraints::Pedestrian,
+            };
+            if let Some(path) = ctx.map.pathfind(req.clone()) {
+                let person = &self.people[trip.person.0];
+                ctx.scheduler.push(
+                    now,
+                    Command::SpawnPed(CreatePedestrian {
+                        id: person.ped,
+                        speed: person.ped_speed,
+                        start,
+                        goal: walking_goal,
+                        path,
+                        req,
+                        trip: id,
+                        person: person.id,
+                    }),
+                );
+            } else {
+                ctx.parking.remove_parked_car(parked_car.clone());
+                let msg = format!("couldn't find the walking path back to {}: {}", car, req);
+                self.cancel_trip(
+                    now,
+                    id,
+                    CancellationReason::NoPath(req, msg),
+                    Some(parked_car.vehicle),
+                    ctx,
+                );
+            }
+        } else {
+            self.cancel_trip(
+                now,
+                id,
+                CancellationReason::Other(format!(
+                    "should have {} parked near {}, but it's unavailable",
+                    car, bldg
+                )),
+                None,
+                ctx,
+            );
+        }
+    }
+
+    pub fn remote_trip_finished(&mut self, now: Time, id: TripID, ctx: &mut Ctx) {
+        let trip = &mut self.trips[id.0];
+
+        let to = match trip.finish_leg(now) {
+            Some(TripLeg::Remote(to)) => to,
+            _ => unreachable!(),
+        };
+        assert!(trip.legs.is_empty());
+        assert!(!trip.finished_at.is_some());
+        trip.finished_at = Some(now);
+        self.unfinished_trips -= 1;
+        self.events.push(Event::TripFinished {
+            trip: trip.id,
+            mode: trip.info.mode,
+            total_time: now - trip.info.departure,
+            blocked_time: trip.total_blocked_time,
+        });
+        let person = trip.person;
+        self.events
+            .push(Event::PersonEntersRemoteBuilding(person, to));
+        self.people[person.0].state = PersonState::OffMap;
+        self.person_finished_trip(now, person, ctx);
+    }
+
+    /// Send a car partway through a trip somewhere else, re-pathfinding from where it is now. The
+    /// new goal has to be the same kind as the old one; when parking near a different building,
+    /// the person still walks to the original destination afterwards. If the car can't be
+    /// rerouted, its original route is left alone.
+    pub fn reroute_active_car(
+        &mut self,
+        now: Time,
+        car: CarID,
+        new_goal: DrivingGoal,
+        driving: &mut DrivingSimState,
+        parking: &mut ParkingSimState,
+        map: &Map,
+    ) -> Result<(), String> {
+        let id = *self
+            .active_trip_mode
+            .get(&AgentID::Car(car))
+            .ok_or_else(|| format!("{} isn't doing a trip", car))?;
+        let trip = &mut self.trips[id.0];
+        match trip.legs[0] {
+            TripLeg::Drive(c, ref old_goal) if c == car => match (old_goal, &new_goal) {
+                (DrivingGoal::ParkNear(_), DrivingGoal::ParkNear(_))
+                | (DrivingGoal::Border(_, _, _), DrivingGoal::Border(_, _, _))
+                | (DrivingGoal::Vanish(_), DrivingGoal::Vanish(_)) => {}
+                _ => {
+                    return Err(format!(
+                        "{} can't switch between parking and leaving the map",
+                        car
+                    ));
+                }
+            },
+            _ => {
+                return Err(format!("{} isn't driving for {}", car, id));
+            }
+        }
+
+        driving.reroute_car(now, car, &new_goal, parking, map)?;
+        match new_goal {
+            DrivingGoal::ParkNear(_) => {}
+            DrivingGoal::Border(i, _, ref loc) => {
+                trip.info.end = TripEndpoint::Border(i, loc.clone());
+            }
+            DrivingGoal::Vanish(pos) => {
+                trip.info.end = TripEndpoint::SuddenlyAppear(pos);
+            }
+        }
+        trip.legs[0] = TripLeg::Drive(car, new_goal);
+        Ok(())
+    }
+
+    /// Decide if paths are calculated when a trip is scheduled or when it starts. Only trips
+    /// scheduled after this call are affected. Trips that were already scheduled or are waiting
+    /// for the person's previous trip keep following the old setting: if they were scheduled
+    /// without a path, they find one when they start, and if their upfront pathfinding failed,
+    /// they're cancelled when they start.
+    pub fn set_pathfinding_upfront(&mut self, upfront: bool) {
+        self.pathfinding_upfront = upfront;
+    }
+
+    /// Called before every step of the simulation, to forget which trips switched agents.
+    pub fn start_step(&mut self) {
+        self.new_agents_this_step.clear();
+    }
+
+    pub fn intersection_delay_measured(&mut self, i: IntersectionID, delay: Duration) {
+        *self.intersection_delay.entry(i).or_insert(Duration::ZERO) += delay;
+    }
+
+    /// Called for every PersonEntersBuilding and PersonLeavesBuilding event.
+    pub fn building_occupancy_changed(&mut self, now: Time, b: BuildingID, entered: bool) {
+        if !self.track_building_occupancy {
+            return;
+        }
+        let series = self.building_occupancy.entry(b).or_insert_with(Vec::new);
+        let prev = series.last().map(|(_, cnt)| *cnt).unwrap_or(0);
+        let cnt = if entered {
+            prev + 1
+        } else {
+            prev.saturating_sub(1)
+        };
+        // Several people moving at the same time only need one entry
+        match series.last_mut() {
+            Some(last) if last.0 == now => {
+                last.1 = cnt;
+            }
+            _ => {
+                series.push((now, cnt));
+            }
+        }
+    }
+
+    /// Cancel a trip before it's started. The person will stay where they are.
+    pub fn cancel_unstarted_trip(&mut self, id: TripID, reason: CancellationReason) {
+        let trip = &mut self.trips[id.0];
+        self.unfinished_trips -= 1;
+        if let Some(ref mut cb) = self.on_cancel.0 {
+            cb(id, &reason);
+        }
+        trip.info.cancellation_reason = Some(reason);
+        self.events.push(Event::TripCancelled(trip.id));
+    }
+
+    /// Run something every time a trip is cancelled, before or after it starts. Only one callback
+    /// can be registered; this replaces any previous one. The callback isn't saved with the
+    /// simulation and is dropped when this is cloned, so it has to be registered again after
+    /// loading a savestate.
+    pub fn set_on_cancel(&mut self, cb: Box<dyn FnMut(TripID, &CancellationReason)>) {
+        self.on_cancel = CancellationHook(Some(cb));
+    }
+
+    /// Cancel a trip after it's started. The person will be magically warped to their destination,
+    /// along with their car, as if the trip had completed normally.
+    pub fn cancel_trip(
+        &mut self,
+        now: Time,
+        id: TripID,
+        reason: CancellationReason,
+        abandoned_vehicle: Option<Vehicle>,
+        ctx: &mut Ctx,
+    ) {
+        if let Some(ref mut cb) = self.on_cancel.0 {
+            cb(id, &reason);
+        }
+        let trip = &mut self.trips[id.0];
+        trip.info.cancellation_reason = Some(reason);
+        self.events.push(Event::TripCancelled(trip.id));
+        self.end_trip_early(now, id, abandoned_vehicle, ctx);
+    }
+
+    /// Instantly finish an ongoing trip, as if the person just arrived at their destination. Unlike
+    /// cancel_trip, this counts as a normal TripFinished. The caller must first remove whatever
+    /// agent is doing the trip from the simulation, passing along the vehicle it was using.
+    pub fn fast_forward_trip(
+        &mut self,
+        now: Time,
+        id: TripID,
+        abandoned_vehicle: Option<Vehicle>,
+        ctx: &mut Ctx,
+    ) -> Result<(), String> {
+        match self.trip_to_agent(id) {
+            TripResult::Ok(agent) => {
+                self.active_trip_mode.remove(&agent);
+            }
+            TripResult::ModeChange if matches!(self.trips[id.0].legs[0], TripLeg::Dwell(_, _)) => {
+                ctx.scheduler.cancel(Command::FinishDwell(id));
+            }
+            TripResult::ModeChange => {
+                return Err(format!("{} is between legs", id));
+            }
+            _ => {
+                return Err(format!("{} isn't ongoing", id));
+            }
+        }
+
+        let trip = &mut self.trips[id.0];
+        trip.finished_at = Some(now);
+        self.events.push(Event::TripFinished {
+            trip: trip.id,
+            mode: trip.info.mode,
+            total_time: now - trip.info.departure,
+            blocked_time: trip.total_blocked_time,
+        });
+        self.people[trip.person.0].on_bus = None;
+        self.end_trip_early(now, id, abandoned_vehicle, ctx);
+        self.trips[id.0].legs.clear();
+        Ok(())
+    }
+
+    /// Warp the person (and any vehicle they were using) to the end of a trip that didn't
+    /// finish normally.
+    fn end_trip_early(
+        &mut self,
+        now: Time,
+        id: TripID,
+        abandoned_vehicle: Option<Vehicle>,
+        ctx: &mut Ctx,
+    ) {
+        let to = self.trips[id.0].info.end.clone();
+        self.end_trip_at(now, id, to, abandoned_vehicle, ctx);
+    }
+
+    /// Like `end_trip_early`, but the person and their vehicle wind up at `to`.
+    fn end_trip_at(
+        &mut self,
+        now: Time,
+        id: TripID,
+        to: TripEndpoint,
+        abandoned_vehicle: Option<Vehicle>,
+        ctx: &mut Ctx,
+    ) {
+        let trip = &self.trips[id.0];
+        self.unfinished_trips -= 1;
+        let person = trip.person;
+
+        // Maintain consistentency for anyone listening to events
+        if let PersonState::Inside(b) = self.people[person.0].state {
+            self.events.push(Event::PersonLeavesBuilding(person, b));
+        }
+        match to {
+            TripEndpoint::Bldg(b) => {
+                self.events.push(Event::PersonEntersBuilding(person, b));
+            }
+            TripEndpoint::Border(i, ref loc) => {
+                self.events
+                    .push(Event::PersonLeavesMap(person, None, i, loc.clone()));
+            }
+            TripEndpoint::SuddenlyAppear(_) => {}
+        }
+
+        // Warp to the destination
+        self.people[person.0].state = match to {
+            TripEndpoint::Bldg(b) => PersonState::Inside(b),
+            TripEndpoint::Border(_, _) | TripEndpoint::SuddenlyAppear(_) => PersonState::OffMap,
+        };
+        if let Some(TripLeg::BikeShare(_, _)) = trip.legs.get(0) {
+            if let Some(bike) = self.bike_docks.bike_for_trip(id) {
+                self.active_trip_mode.remove(&AgentID::Car(bike));
+            }
+        }
+        // A shared bike is returned to the dock it was headed for
+        self.bike_docks.trip_cancelled(id);
+        // Don't forget the car!
+        if let Some(vehicle) = abandoned_vehicle {
+            if vehicle.owner.is_none() {
+                if vehicle.vehicle_type == VehicleType::Car {
+                    // A ride-hailing vehicle just goes back to the fleet
+                    self.ride_hail_busy.remove(&vehicle.id);
+                    self.ride_hail_fleet.push(vehicle);
+                }
+            } else if vehicle.vehicle_type == VehicleType::Car {
+                if let TripEndpoint::Bldg(b) = to {
                     let driving_lane = ctx.map.find_driving_lane_near_building(b);
+                    // Pick the free spot closest to the building, so warped cars don't all pile up
+                    // at one end of the lane
+                    let bldg_pt = ctx.map.get_b(b).sidewalk_pos.pt(ctx.map);
                     if let Some(spot) = ctx
                         .parking
                         .get_all_free_spots(Position::start(driving_lane), &vehicle, b, ctx.map)
-                        // TODO Could pick something closer, but meh, cancelled trips are bugs
-                        // anyway
-                        .get(0)
-                        .map(|(spot, _)| spot.clone())
+                        .into_iter()
+                        .min_by_key(|(_, pos)| pos.pt(ctx.map).dist_to(bldg_pt))
+                        .map(|(spot, _)| spot)
                         .or_else(|| {
                             ctx.parking
                                 .path_to_free_parking_spot(driving_lane, &vehicle, b, ctx.map)
@@ -791,7 +1608,7 @@ impl TripManager {
                         self.events.push(Event::Alert(
                             AlertLocation::Person(person),
                             format!(
-                                "{} had a trip cancelled, and their car was warped to {:?}",
+                                "{} had a trip end early, and their car was warped to {:?}",
                                 person, spot
                             ),
                         ));
@@ -805,7 +1622,7 @@ impl TripManager {
                         self.events.push(Event::Alert(
                             AlertLocation::Person(person),
                             format!(
-                                "{} had a trip cancelled, but nowhere to warp their car! Sucks.",
+                                "{} had a trip end early, but nowhere to warp their car! Sucks.",
                                 person
                             ),
                         ));
@@ -815,16 +1632,395 @@ impl TripManager {
         } else {
             // If the trip was cancelled because we'e totally out of parking, don't forget to clean
             // this up.
-            if let TripLeg::Drive(c, _) = &trip.legs[0] {
-                if let Some(t) = self.active_trip_mode.remove(&AgentID::Car(*c)) {
-                    assert_eq!(t, trip.id);
+            match trip.legs[0] {
+                TripLeg::Drive(c, _) | TripLeg::RideHail(Some(c), _) => {
+                    if let Some(t) = self.active_trip_mode.remove(&AgentID::Car(c)) {
+                        assert_eq!(t, trip.id);
+                    }
                 }
+                _ => {}
             }
         }
 
         self.person_finished_trip(now, person, ctx);
     }
 
+    /// Cancel a trip without warping the person to their destination, so the trip doesn't count
+    /// as an arrival anywhere. If the trip hasn't started, the person stays wherever they
+    /// currently are -- inside a building or off-map. If it's underway, the caller must first
+    /// remove whatever agent is doing the trip from the simulation and pass along the vehicle it
+    /// was using. A person dwelling at a building stays inside it; otherwise they and their
+    /// vehicle go back to where the trip started, as if they'd turned around. Either way, the
+    /// person's later trips then start from their real current location, which may not match
+    /// where those trips were planned to start.
+    pub fn cancel_trip_stay_put(
+        &mut self,
+        now: Time,
+        id: TripID,
+        reason: CancellationReason,
+        abandoned_vehicle: Option<Vehicle>,
+        ctx: &mut Ctx,
+    ) -> Result<(), String> {
+        let trip = &self.trips[id.0];
+        if trip.finished_at.is_some() || trip.info.cancellation_reason.is_some() {
+            return Err(format!("{} isn't ongoing", id));
+        }
+        let person = trip.person;
+        if trip.started_at.is_some() {
+            let to = match self.trip_to_agent(id) {
+                TripResult::Ok(agent) => {
+                    self.active_trip_mode.remove(&agent);
+                    self.trips[id.0].info.start.clone()
+                }
+                TripResult::ModeChange => match self.trips[id.0].legs[0] {
+                    TripLeg::Dwell(b, _) => {
+                        ctx.scheduler.cancel(Command::FinishDwell(id));
+                        TripEndpoint::Bldg(b)
+                    }
+                    _ => {
+                        return Err(format!("{} is between legs", id));
+                    }
+                },
+                _ => {
+                    return Err(format!("{} isn't ongoing", id));
+                }
+            };
+            if let Some(ref mut cb) = self.on_cancel.0 {
+                cb(id, &reason);
+            }
+            let trip = &mut self.trips[id.0];
+            trip.info.cancellation_reason = Some(reason);
+            self.events.push(Event::TripCancelled(trip.id));
+            self.people[person.0].on_bus = None;
+            self.end_trip_at(now, id, to, abandoned_vehicle, ctx);
+            self.trips[id.0].legs.clear();
+            return Ok(());
+        }
+        self.cancel_unstarted_trip(id, reason);
+
+        // The trip is either waiting for the person's previous trip to finish, or it's scheduled
+        // to start later.
+        let person = &mut self.people[person.0];
+        let num_delayed = person.delayed_trips.len();
+        person.delayed_trips.retain(|(t, _, _, _)| *t != id);
+        if person.delayed_trips.len() == num_delayed {
+            ctx.scheduler
+                .must_cancel_by_type(CommandType::StartTrip(id));
+        }
+        Ok(())
+    }
+
+    /// Cancel every trip of this person that hasn't finished yet. Trips that haven't started are
+    /// cancelled in place. If the person is in the middle of a trip, the caller must first remove
+    /// whatever agent is doing it from the simulation and pass along the vehicle it was using; the
+    /// person is then warped to that trip's destination, like `cancel_trip`. Returns the number of
+    /// trips cancelled.
+    pub fn cancel_all_trips_for_person(
+        &mut self,
+        now: Time,
+        p: PersonID,
+        reason: CancellationReason,
+        abandoned_vehicle: Option<Vehicle>,
+        ctx: &mut Ctx,
+    ) -> usize {
+        // Clear these first, so that finishing the current trip doesn't start one of them
+        let delayed: BTreeSet<TripID> =
+            std::mem::replace(&mut self.people[p.0].delayed_trips, Vec::new())
+                .into_iter()
+                .map(|(t, _, _, _)| t)
+                .collect();
+
+        let mut count = 0;
+        for id in self.people[p.0].trips.clone() {
+            let trip = &self.trips[id.0];
+            if trip.started_at.is_some()
+                || trip.finished_at.is_some()
+                || trip.info.cancellation_reason.is_some()
+            {
+                continue;
+            }
+            if !delayed.contains(&id) {
+                ctx.scheduler
+                    .must_cancel_by_type(CommandType::StartTrip(id));
+            }
+            self.cancel_unstarted_trip(id, reason.clone());
+            count += 1;
+        }
+
+        if let PersonState::Trip(id) = self.people[p.0].state {
+            self.abort_trip(now, id, reason, abandoned_vehicle, ctx);
+            count += 1;
+        }
+        count
+    }
+
+    /// Cancel a trip that's underway, like `cancel_trip`. Unlike `cancel_trip`, the agent doing
+    /// the trip doesn't have to be stuck; the caller must first remove it from the simulation and
+    /// pass along the vehicle it was using. This works for people riding or waiting for a bus too.
+    pub fn abort_trip(
+        &mut self,
+        now: Time,
+        id: TripID,
+        reason: CancellationReason,
+        abandoned_vehicle: Option<Vehicle>,
+        ctx: &mut Ctx,
+    ) {
+        match self.trip_to_agent(id) {
+            TripResult::Ok(agent) => {
+                self.active_trip_mode.remove(&agent);
+            }
+            TripResult::ModeChange if matches!(self.trips[id.0].legs[0], TripLeg::Dwell(_, _)) => {
+                ctx.scheduler.cancel(Command::FinishDwell(id));
+            }
+            _ => {}
+        }
+        self.people[self.trips[id.0].person.0].on_bus = None;
+        self.cancel_trip(now, id, reason, abandoned_vehicle, ctx);
+    }
+
+    /// Switch a trip that hasn't started yet to a different mode, recalculating its legs the same
+    /// way a scenario would. Fails if the new mode can't serve the trip, like when there's no
+    /// transit route or the person has no vehicle of the right type.
+    pub fn reassign_mode(
+        &mut self,
+        id: TripID,
+        new_mode: TripMode,
+        map: &Map,
+        scheduler: &mut Scheduler,
+    ) -> Result<(), String> {
+        let trip = &self.trips[id.0];
+        if trip.started_at.is_some() {
+            return Err(format!("{} already started", id));
+        }
+        if trip.info.cancellation_reason.is_some() {
+            return Err(format!("{} was cancelled", id));
+        }
+        let (start, end) = (trip.info.start.clone(), trip.info.end.clone());
+        let spawn = SpawnTrip::new(start, end, new_mode, map)
+            .ok_or_else(|| format!("{} can't be done by {}", id, new_mode.noun()))?;
+        if new_mode == TripMode::Transit && spawn.mode() != TripMode::Transit {
+            return Err(format!("no transit route for {}", id));
+        }
+        if let SpawnTrip::UsingBike(b, _) = spawn {
+            if SidewalkSpot::bike_rack(b, map).is_none() {
+                return Err(format!("{} has no place to start biking", b));
+            }
+        }
+
+        let person = &self.people[trip.person.0];
+        let use_vehicle = match spawn.mode() {
+            TripMode::Drive | TripMode::Bike => {
+                let vehicle_type = if spawn.mode() == TripMode::Drive {
+                    VehicleType::Car
+                } else {
+                    VehicleType::Bike
+                };
+                Some(
+                    person
+                        .vehicles
+                        .iter()
+                        .find(|v| v.vehicle_type == vehicle_type)
+                        .ok_or_else(|| format!("{} has no {:?}", person.id, vehicle_type))?
+                        .id,
+                )
+            }
+            _ => None,
+        };
+        // The RNG only picks a starting lane from a border, so a fixed seed is fine.
+        let spec = spawn.to_trip_spec(use_vehicle, &mut XorShiftRng::from_seed([0; 16]), map);
+        let (_, legs) = spec.legs(self, map);
+
+        let trip = &mut self.trips[id.0];
+        trip.info.mode = new_mode;
+        trip.info.modified = true;
+        trip.legs = VecDeque::from(legs);
+        trip.pathfinding_upfront = self.pathfinding_upfront;
+
+        // Replace the spec waiting to start the trip
+        let maybe_req = spec.get_pathfinding_request(map);
+        let maybe_path = if self.pathfinding_upfront {
+            maybe_req.clone().and_then(|req| map.pathfind(req))
+        } else {
+            None
+        };
+        let person = &mut self.people[trip.person.0];
+        if let Some(idx) = person
+            .delayed_trips
+            .iter()
+            .position(|(t, _, _, _)| *t == id)
+        {
+            person.delayed_trips[idx] = (id, spec, maybe_req, maybe_path);
+        } else {
+            scheduler.must_cancel_by_type(CommandType::StartTrip(id));
+            scheduler.push(
+                trip.info.departure,
+                Command::StartTrip(id, spec, maybe_req, maybe_path),
+            );
+        }
+        Ok(())
+    }
+
+    /// Change when a trip that hasn't started yet departs. The person's trips have to stay in
+    /// order, so this fails if the new time is before their previous trip's departure or after
+    /// their next one. A trip that's already waiting for the person's previous trip to finish can
+    /// be pushed later, too.
+    pub fn reschedule_departure(
+        &mut self,
+        now: Time,
+        id: TripID,
+        new_time: Time,
+        scheduler: &mut Scheduler,
+    ) -> Result<(), String> {
+        let trip = &self.trips[id.0];
+        if trip.started_at.is_some() {
+            return Err(format!("{} already started", id));
+        }
+        if trip.info.cancellation_reason.is_some() {
+            return Err(format!("{} was cancelled", id));
+        }
+        if new_time < now {
+            return Err(format!(
+                "can't move {} to {}, it's already {}",
+                id, new_time, now
+            ));
+        }
+        let person = &self.people[trip.person.0];
+        let idx = person.trips.iter().position(|t| *t == id).unwrap();
+        if idx > 0 {
+            let prev = &self.trips[person.trips[idx - 1].0];
+            if prev.info.departure > new_time {
+                return Err(format!(
+                    "{} has an earlier trip {} departing at {}",
+                    person.id, prev.id, prev.info.departure
+                ));
+            }
+        }
+        if let Some(next) = person.trips.get(idx + 1) {
+            let next = &self.trips[next.0];
+            if next.info.departure < new_time {
+                return Err(format!(
+                    "{} has a later trip {} departing at {}",
+                    person.id, next.id, next.info.departure
+                ));
+            }
+        }
+
+        let person = &mut self.people[trip.person.0];
+        if let Some(pos) = person
+            .delayed_trips
+            .iter()
+            .position(|(t, _, _, _)| *t == id)
+        {
+            // The trip already tried to start, so queue it up again
+            let (_, spec, maybe_req, maybe_path) = person.delayed_trips.remove(pos);
+            scheduler.push(
+                new_time,
+                Command::StartTrip(id, spec, maybe_req, maybe_path),
+            );
+        } else if !scheduler.reschedule(CommandType::StartTrip(id), new_time) {
+            return Err(format!("{} isn't scheduled to start", id));
+        }
+        self.trips[id.0].info.departure = new_time;
+        Ok(())
+    }
+
+    /// Append all of the people and trips from another finished simulation on the same map, like
+    /// when demand was split across several processes. IDs from `other` are shifted past the ones
+    /// used here; results like finish times, cancellations, and blocked time are kept.
+    pub fn merge_into(&mut self, other: TripManager, map: &Map) -> Result<(), String> {
+        if !self.is_done() || !other.is_done() {
+            return Err(format!(
+                "both simulations must be done, but there are {} and {} unfinished trips",
+                self.unfinished_trips, other.unfinished_trips
+            ));
+        }
+        let num_bldgs = map.all_buildings().len();
+        let num_intersections = map.all_intersections().len();
+        let num_lanes = map.all_lanes().len();
+        for t in &other.trips {
+            for endpt in &[&t.info.start, &t.info.end] {
+                let valid = match endpt {
+                    TripEndpoint::Bldg(b) => b.0 < num_bldgs,
+                    TripEndpoint::Border(i, _) => i.0 < num_intersections,
+                    TripEndpoint::SuddenlyAppear(pos) => pos.lane().0 < num_lanes,
+                };
+                if !valid {
+                    return Err(format!("{} goes somewhere not on this map", t.id));
+                }
+            }
+        }
+
+        let person_offset = self.people.len();
+        let trip_offset = self.trips.len();
+        let car_offset = self.car_id_counter;
+        let fix_person = |p: PersonID| PersonID(p.0 + person_offset);
+        let fix_trip = |t: TripID| TripID(t.0 + trip_offset);
+        let fix_car = |c: CarID| CarID(c.0 + car_offset, c.1);
+        let fix_vehicle = |mut v: Vehicle| {
+            v.id = fix_car(v.id);
+            v.owner = v.owner.map(fix_person);
+            v
+        };
+
+        for mut person in other.people {
+            person.id = fix_person(person.id);
+            person.ped = PedestrianID(person.id.0);
+            person.trips = person.trips.into_iter().map(fix_trip).collect();
+            if let PersonState::Trip(t) = person.state {
+                person.state = PersonState::Trip(fix_trip(t));
+            }
+            person.vehicles = person.vehicles.into_iter().map(fix_vehicle).collect();
+            person.on_bus = person.on_bus.map(fix_car);
+            self.people.push(person);
+        }
+        for mut trip in other.trips {
+            trip.id = fix_trip(trip.id);
+            trip.person = fix_person(trip.person);
+            // The trips are all done, so every leg is in completed_legs
+            for (leg, _) in trip.completed_legs.iter_mut() {
+                match leg {
+                    TripLeg::Drive(c, _) | TripLeg::RideHail(Some(c), _) => {
+                        *c = fix_car(*c);
+                    }
+                    _ => {}
+                }
+            }
+            self.trips.push(trip);
+        }
+        self.ride_hail_fleet
+            .extend(other.ride_hail_fleet.into_iter().map(fix_vehicle));
+        self.bike_docks
+            .merge(other.bike_docks, fix_trip, fix_vehicle);
+        self.car_id_counter += other.car_id_counter;
+        self.unfinished_trips += other.unfinished_trips;
+        for (i, delay) in other.intersection_delay {
+            *self.intersection_delay.entry(i).or_insert(Duration::ZERO) += delay;
+        }
+        for (b, theirs) in other.building_occupancy {
+            let ours = self.building_occupancy.remove(&b).unwrap_or_else(Vec::new);
+            self.building_occupancy
+                .insert(b, merge_occupancy(ours, theirs));
+        }
+        Ok(())
+    }
+
+    /// Cancel any trip still going this long after its departure time. Trips that already started
+    /// get checked too.
+    pub fn set_max_trip_duration(&mut self, now: Time, limit: Duration, scheduler: &mut Scheduler) {
+        self.max_trip_duration = Some(limit);
+        for trip in &self.trips {
+            if trip.started_at.is_some()
+                && trip.finished_at.is_none()
+                && trip.info.cancellation_reason.is_none()
+            {
+                scheduler.update(
+                    now.max(trip.info.departure + limit),
+                    Command::AbortLongTrip(trip.id),
+                );
+            }
+        }
+    }
+
     pub fn active_agents(&self) -> Vec<AgentID> {
         self.active_trip_mode.keys().cloned().collect()
     }
@@ -834,6 +2030,41 @@ impl TripManager {
     pub fn active_agents_and_trips(&self) -> &BTreeMap<AgentID, TripID> {
         &self.active_trip_mode
     }
+    /// Every active agent, along with the mode of their trip and what they're currently doing.
+    /// Drivers searching for parking are just described as driving.
+    pub fn active_agents_with_legs(
+        &self,
+        walking: &WalkingSimState,
+    ) -> Vec<(AgentID, TripMode, TripPhaseType)> {
+        let mut results = Vec::new();
+        for (agent, id) in &self.active_trip_mode {
+            let trip = &self.trips[id.0];
+            let phase = match (&trip.legs[0], *agent) {
+                (TripLeg::Walk(spot), AgentID::Pedestrian(ped))
+                    if walking.is_waiting_for_bus(ped) =>
+                {
+                    match (&spot.connection, trip.legs.get(1)) {
+                        (SidewalkPOI::BusStop(stop), Some(TripLeg::RideBus(route, _))) => {
+                            TripPhaseType::WaitingForBus(*route, *stop)
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                (TripLeg::Walk(_), _) => TripPhaseType::Walking,
+                (TripLeg::Drive(c, _), _) if c.1 == VehicleType::Bike => TripPhaseType::Biking,
+                (TripLeg::Drive(_, _), _) | (TripLeg::RideHail(_, _), _) => TripPhaseType::Driving,
+                (TripLeg::BikeShare(_, _), _) => TripPhaseType::Biking,
+                (TripLeg::RideBus(route, _), AgentID::BusPassenger(_, bus)) => {
+                    TripPhaseType::RidingBus(*route, trip.boarded_at.unwrap(), bus)
+                }
+                (TripLeg::Remote(_), _) => TripPhaseType::Remote,
+                (TripLeg::Dwell(_, _), _) => TripPhaseType::Dwelling,
+                (TripLeg::RideBus(_, _), _) => unreachable!(),
+            };
+            results.push((*agent, trip.info.mode, phase));
+        }
+        results
+    }
     pub fn num_active_agents(&self) -> usize {
         self.active_trip_mode.len()
     }
@@ -850,18 +2081,32 @@ impl TripManager {
         if trip.info.cancellation_reason.is_some() {
             return TripResult::TripCancelled;
         }
-        if !trip.started {
+        if trip.started_at.is_none() {
             return TripResult::TripNotStarted;
         }
 
         let person = &self.people[trip.person.0];
         let a = match &trip.legs[0] {
             TripLeg::Walk(_) => AgentID::Pedestrian(person.ped),
-            TripLeg::Drive(c, _) => AgentID::Car(*c),
+            TripLeg::Drive(c, _) | TripLeg::RideHail(Some(c), _) => AgentID::Car(*c),
+            // Dispatch happens as soon as the trip starts, so this shouldn't last long
+            TripLeg::RideHail(None, _) => {
+                return TripResult::ModeChange;
+            }
+            TripLeg::BikeShare(_, _) => match self.bike_docks.bike_for_trip(id) {
+                Some(bike) => AgentID::Car(bike),
+                None => {
+                    return TripResult::ModeChange;
+                }
+            },
             TripLeg::RideBus(_, _) => AgentID::BusPassenger(person.id, person.on_bus.unwrap()),
             TripLeg::Remote(_) => {
                 return TripResult::RemoteTrip;
             }
+            // Lingering at a waypoint, not represented by any agent
+            TripLeg::Dwell(_, _) => {
+                return TripResult::ModeChange;
+            }
         };
         if self.active_trip_mode.get(&a) == Some(&id) {
             TripResult::Ok(a)
@@ -871,6 +2116,16 @@ impl TripManager {
         }
     }
 
+    /// The agent currently doing a trip, and true if the trip switched to that agent during the
+    /// last step -- like somebody finishing walking to their car and starting to drive, or the
+    /// trip just starting. None when nobody represents the trip right now.
+    pub fn agent_for_trip_changed_this_step(&self, id: TripID) -> Option<(AgentID, bool)> {
+        match self.trip_to_agent(id) {
+            TripResult::Ok(a) => Some((a, self.new_agents_this_step.contains(&id))),
+            _ => None,
+        }
+    }
+
     /// This will be None for parked cars and buses. Should always work for pedestrians.
     pub fn agent_to_trip(&self, id: AgentID) -> Option<TripID> {
         self.active_trip_mode.get(&id).cloned()
@@ -901,6 +2156,16 @@ impl TripManager {
         cnt.add(AgentType::Train, trains);
         cnt
     }
+    /// Like num_agents, but also counts every parked car, so that every vehicle is accounted for
+    pub fn num_agents_detailed(
+        &self,
+        transit: &TransitSimState,
+        parking: &ParkingSimState,
+    ) -> Counter<AgentType> {
+        let mut cnt = self.num_agents(transit);
+        cnt.add(AgentType::ParkedCar, parking.num_parked_cars());
+        cnt
+    }
     pub fn num_ppl(&self) -> (usize, usize, usize) {
         let mut ppl_in_bldg = 0;
         let mut ppl_off_map = 0;
@@ -925,6 +2190,60 @@ impl TripManager {
     pub fn collect_events(&mut self) -> Vec<Event> {
         std::mem::replace(&mut self.events, Vec::new())
     }
+    /// Removes and returns only the events matching the predicate, in order. The rest are left
+    /// for `collect_events`.
+    pub fn drain_events_matching<F: Fn(&Event) -> bool>(&mut self, pred: F) -> Vec<Event> {
+        let (matching, rest): (Vec<Event>, Vec<Event>) =
+            std::mem::replace(&mut self.events, Vec::new())
+                .into_iter()
+                .partition(pred);
+        self.events = rest;
+        matching
+    }
+
+    /// All of a person's trips, sorted by departure time
+    pub fn person_schedule(&self, p: PersonID) -> Vec<ScheduledTrip> {
+        let mut schedule: Vec<ScheduledTrip> = self.people[p.0]
+            .trips
+            .iter()
+            .map(|t| {
+                let trip = &self.trips[t.0];
+                ScheduledTrip {
+                    id: trip.id,
+                    departure: trip.info.departure,
+                    mode: trip.info.mode,
+                    start: trip.info.start.clone(),
+                    end: trip.info.end.clone(),
+                    finished: trip.finished_at,
+                }
+            })
+            .collect();
+        schedule.sort_by_key(|t| t.departure);
+        schedule
+    }
+
+    /// Every trip that's finished or been cancelled so far
+    pub fn export_finished_trips(&self) -> Vec<TripRecord> {
+        self.trips
+            .iter()
+            .filter(|t| t.finished_at.is_some() || t.info.cancellation_reason.is_some())
+            .map(|t| TripRecord {
+                trip: t.id,
+                person: t.person,
+                orig_person: self.people[t.person.0].orig_id,
+                mode: t.info.mode,
+                purpose: t.info.purpose,
+                start: t.info.start.clone(),
+                end: t.info.end.clone(),
+                departure: t.info.departure,
+                finished_at: t.finished_at,
+                total_time: t.finished_at.map(|time| time - t.info.departure),
+                blocked_time: t.total_blocked_time,
+                capped: t.info.capped,
+                cancellation_reason: t.info.cancellation_reason.clone(),
+            })
+            .collect()
+    }
 
     pub fn trip_info(&self, id: TripID) -> TripInfo {
         self.trips[id.0].info.clone()
@@ -936,10 +2255,273 @@ impl TripManager {
         let t = &self.trips[id.0];
         Some((t.finished_at? - t.info.departure, t.total_blocked_time))
     }
+    /// How long the start of a trip was delayed, because the person was still doing a previous
+    /// trip. None if the trip hasn't started yet.
+    pub fn trip_start_delay(&self, id: TripID) -> Option<Duration> {
+        let t = &self.trips[id.0];
+        Some(t.started_at? - t.info.departure)
+    }
+    /// A static guess of how long a trip would take, ignoring congestion and delays at
+    /// intersections. Each step of the path is traversed at the speed limit, capped by how fast
+    /// the mode can go. None if the trip can't be routed.
+    pub fn estimate_duration(
+        &self,
+        from: TripEndpoint,
+        to: TripEndpoint,
+        mode: TripMode,
+        map: &Map,
+    ) -> Option<Duration> {
+        let path = map.pathfind(TripEndpoint::path_req(from, to, mode, map)?)?;
+        let max_speed = match mode {
+            TripMode::Walk | TripMode::Transit => Some(Scenario::max_ped_speed()),
+            TripMode::Bike | TripMode::Scooter => Some(Scenario::max_bike_speed()),
+            TripMode::Drive => None,
+        };
+        let mut total = Duration::ZERO;
+        for step in path.get_steps() {
+            let t = step.as_traversable();
+            let mut speed = t.speed_limit(map);
+            if let Some(max) = max_speed {
+                speed = speed.min(max);
+            }
+            total += t.length(map) / speed;
+        }
+        if mode == TripMode::Drive {
+            total += ESTIMATED_PARKING_OVERHEAD;
+        }
+        Some(total)
+    }
+    /// How many trips are waiting for somebody to finish their previous trip
+    pub fn num_delayed_trips(&self) -> usize {
+        self.people.iter().map(|p| p.delayed_trips.len()).sum()
+    }
+    /// The trips this person will start once they finish their current trip, in order
+    pub fn delayed_trips_for(&self, person: PersonID) -> Vec<TripID> {
+        self.people[person.0]
+            .delayed_trips
+            .iter()
+            .map(|(trip, _, _, _)| *trip)
+            .collect()
+    }
+    /// Trips between someone's home and a place they work. A work building is anywhere one of the
+    /// person's trips with the Work purpose ends.
+    pub fn commute_trips(&self, person: PersonID) -> Vec<TripID> {
+        let person = &self.people[person.0];
+        let home = if let Some(b) = person.home {
+            TripEndpoint::Bldg(b)
+        } else {
+            return Vec::new();
+        };
+        let work: BTreeSet<BuildingID> = person
+            .trips
+            .iter()
+            .filter_map(|t| {
+                let info = &self.trips[t.0].info;
+                match (&info.end, info.purpose) {
+                    (TripEndpoint::Bldg(b), TripPurpose::Work) => Some(*b),
+                    _ => None,
+                }
+            })
+            .collect();
+        let is_work = |endpt: &TripEndpoint| match endpt {
+            TripEndpoint::Bldg(b) => work.contains(b),
+            TripEndpoint::Border(_, _) | TripEndpoint::SuddenlyAppear(_) => false,
+        };
+        person
+            .trips
+            .iter()
+            .filter(|t| {
+                let info = &self.trips[t.0].info;
+                (info.start == home && is_work(&info.end))
+                    || (is_work(&info.start) && info.end == home)
+            })
+            .cloned()
+            .collect()
+    }
     pub fn trip_blocked_time(&self, id: TripID) -> Duration {
         let t = &self.trips[id.0];
         t.total_blocked_time
     }
+    /// Why the trip was blocked so far, only counting finished legs
+    pub fn blocked_time_breakdown(&self, id: TripID) -> BTreeMap<BlockedCause, Duration> {
+        self.trips[id.0].blocked_time_breakdown.clone()
+    }
+    /// The total length of every walking, biking, and driving leg's path; riding a bus doesn't
+    /// count. None if the trip hasn't finished.
+    pub fn trip_distance(&self, id: TripID) -> Option<Distance> {
+        let t = &self.trips[id.0];
+        t.finished_at?;
+        Some(t.total_distance)
+    }
+    /// Like trip_distance, but split up by the mode of each leg, so walking and driving can be
+    /// told apart
+    pub fn trip_distance_breakdown(
+        &self,
+        id: TripID,
+    ) -> Option<BTreeMap<PathConstraints, Distance>> {
+        let t = &self.trips[id.0];
+        t.finished_at?;
+        Some(t.distance_breakdown.clone())
+    }
+    pub fn num_transfers(&self, id: TripID) -> usize {
+        self.trips[id.0].info.transfers
+    }
+    /// Active trips that've been blocked for more than some threshold, most blocked first. Only
+    /// the blocked time from finished legs is counted.
+    pub fn trips_blocked_longer_than(&self, threshold: Duration) -> Vec<(TripID, Duration)> {
+        let mut results: Vec<(TripID, Duration)> = self
+            .active_trip_mode
+            .values()
+            .map(|id| (*id, self.trips[id.0].total_blocked_time))
+            .filter(|(_, dt)| *dt > threshold)
+            .collect();
+        results.sort_by(|a, b| b.1.cmp(&a.1));
+        results
+    }
+    /// Total time of every finished trip, grouped by mode. Cancelled and unfinished trips are
+    /// skipped.
+    pub fn travel_times_by_mode(&self) -> BTreeMap<TripMode, Vec<Duration>> {
+        let mut results = BTreeMap::new();
+        for t in self.finished_trips() {
+            results
+                .entry(t.info.mode)
+                .or_insert_with(Vec::new)
+                .push(t.finished_at.unwrap() - t.info.departure);
+        }
+        results
+    }
+    /// Like `travel_times_by_mode`, but only counting time spent blocked.
+    pub fn blocked_times_by_mode(&self) -> BTreeMap<TripMode, Vec<Duration>> {
+        let mut results = BTreeMap::new();
+        for t in self.finished_trips() {
+            results
+                .entry(t.info.mode)
+                .or_insert_with(Vec::new)
+                .push(t.total_blocked_time);
+        }
+        results
+    }
+    /// Total time agents have been blocked waiting to turn through each intersection, including
+    /// legs still in progress.
+    pub fn intersection_delay(&self) -> BTreeMap<IntersectionID, Duration> {
+        self.intersection_delay.clone()
+    }
+    /// Blocked time from finished legs that's blamed on traffic, but isn't tied to any
+    /// intersection, like being stuck in a queue partway along a lane. Parking and other causes
+    /// are tracked separately, so they're not included. Since `intersection_delay` counts legs in
+    /// progress, this is an underestimate while agents are still blocked.
+    pub fn unattributed_delay(&self) -> Duration {
+        let reported: Duration = self
+            .trips
+            .iter()
+            .filter_map(|t| t.blocked_time_breakdown.get(&BlockedCause::Intersection))
+            .cloned()
+            .sum();
+        let attributed: Duration = self.intersection_delay.values().cloned().sum();
+        (reported - attributed).max(Duration::ZERO)
+    }
+    /// Trips that a congestion cap rerouted or cancelled
+    pub fn capped_trips(&self) -> Vec<TripID> {
+        self.trips
+            .iter()
+            .filter(|t| t.info.capped)
+            .map(|t| t.id)
+            .collect()
+    }
+    pub fn num_capped(&self) -> usize {
+        self.trips.iter().filter(|t| t.info.capped).count()
+    }
+    /// Trips in progress at some point during [start, end): they departed before the end and hadn't
+    /// finished by the start. Trips cancelled before they started are skipped. Trips cancelled
+    /// partway never finish, so they count as overlapping any window after they departed.
+    pub fn trips_overlapping(&self, start: Time, end: Time) -> Vec<TripID> {
+        self.trips
+            .iter()
+            .filter(|t| {
+                t.info.departure < end
+                    && t.finished_at.map(|f| f >= start).unwrap_or(true)
+                    && !(t.started_at.is_none() && t.info.cancellation_reason.is_some())
+            })
+            .map(|t| t.id)
+            .collect()
+    }
+    /// The legs a trip has finished so far, with the time each one ended. Legs skipped by
+    /// cancelling or fast-forwarding the trip aren't included.
+    pub fn trip_leg_history(&self, id: TripID) -> Vec<(TripLeg, Time)> {
+        self.trips[id.0].completed_legs.clone()
+    }
+    /// Summarizes all trips cancelled so far, however that happened
+    pub fn unmet_demand(&self) -> UnmetDemand {
+        let mut result = UnmetDemand {
+            total: 0,
+            by_reason: BTreeMap::new(),
+            by_mode: BTreeMap::new(),
+            trips: Vec::new(),
+        };
+        for t in &self.trips {
+            if let Some(ref reason) = t.info.cancellation_reason {
+                result.total += 1;
+                *result.by_reason.entry(reason.kind()).or_insert(0) += 1;
+                *result.by_mode.entry(t.info.mode).or_insert(0) += 1;
+                result.trips.push((t.id, reason.clone()));
+            }
+        }
+        result
+    }
+    /// How many trips of each mode depart at or before some time, ignoring cancelled trips. This
+    /// only looks at the scheduled departure, so it also counts trips delayed by the person's
+    /// previous trip.
+    pub fn mode_share_up_to(&self, t: Time) -> BTreeMap<TripMode, usize> {
+        let mut counts = BTreeMap::new();
+        for trip in &self.trips {
+            if trip.info.departure <= t && trip.info.cancellation_reason.is_none() {
+                *counts.entry(trip.info.mode).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+    /// Every trip with this purpose, including cancelled ones
+    pub fn trips_with_purpose(&self, purpose: TripPurpose) -> Vec<TripID> {
+        self.trips
+            .iter()
+            .filter(|t| t.info.purpose == purpose)
+            .map(|t| t.id)
+            .collect()
+    }
+    /// Counts the purpose of all trips that haven't been cancelled
+    pub fn purpose_histogram(&self) -> Counter<TripPurpose> {
+        let mut cnt = Counter::new();
+        for t in &self.trips {
+            if t.info.cancellation_reason.is_none() {
+                cnt.inc(t.info.purpose);
+            }
+        }
+        cnt
+    }
+    fn finished_trips(&self) -> impl Iterator<Item = &Trip> {
+        self.trips
+            .iter()
+            .filter(|t| t.finished_at.is_some() && t.info.cancellation_reason.is_none())
+    }
+    /// From the departure of a person's first trip to the end of their last one, only counting
+    /// trips that started and weren't cancelled. A trip still going ends at its departure time
+    /// here. None if the person hasn't started any trips.
+    pub fn person_active_span(&self, p: PersonID) -> Option<(Time, Time)> {
+        let mut span: Option<(Time, Time)> = None;
+        for t in &self.people[p.0].trips {
+            let trip = &self.trips[t.0];
+            if trip.started_at.is_none() || trip.info.cancellation_reason.is_some() {
+                continue;
+            }
+            let start = trip.info.departure;
+            let end = trip.finished_at.unwrap_or(start);
+            span = Some(match span {
+                Some((a, b)) => (a.min(start), b.max(end)),
+                None => (start, end),
+            });
+        }
+        span
+    }
     pub fn bldg_to_people(&self, b: BuildingID) -> Vec<PersonID> {
         let mut people = Vec::new();
         for p in &self.people {
@@ -949,6 +2531,33 @@ impl TripManager {
         }
         people
     }
+    /// How many people were inside a building over time, starting from the first time anybody
+    /// entered. Each entry is the count just after that time. Always empty unless
+    /// `SimOptions::track_building_occupancy` is set.
+    pub fn building_occupancy_timeseries(&self, b: BuildingID) -> Vec<(Time, usize)> {
+        self.building_occupancy
+            .get(&b)
+            .cloned()
+            .unwrap_or_else(Vec::new)
+    }
+
+    /// All trips going from one endpoint to another, skipping cancelled trips.
+    pub fn trips_between(&self, from: TripEndpoint, to: TripEndpoint) -> Vec<TripID> {
+        self.uncancelled_trips(|info| info.start == from && info.end == to)
+    }
+    pub fn trips_from_building(&self, b: BuildingID) -> Vec<TripID> {
+        self.uncancelled_trips(|info| info.start == TripEndpoint::Bldg(b))
+    }
+    pub fn trips_to_building(&self, b: BuildingID) -> Vec<TripID> {
+        self.uncancelled_trips(|info| info.end == TripEndpoint::Bldg(b))
+    }
+    fn uncancelled_trips<F: Fn(&TripInfo) -> bool>(&self, filter: F) -> Vec<TripID> {
+        self.trips
+            .iter()
+            .filter(|t| t.info.cancellation_reason.is_none() && filter(&t.info))
+            .map(|t| t.id)
+            .collect()
+    }
 
     pub fn get_person(&self, p: PersonID) -> Option<&Person> {
         self.people.get(p.0)
@@ -956,6 +2565,19 @@ impl TripManager {
     pub fn get_all_people(&self) -> &Vec<Person> {
         &self.people
     }
+    /// Which of a person's vehicles are parked right now, and where
+    pub fn parked_vehicles(
+        &self,
+        p: PersonID,
+        parking: &ParkingSimState,
+    ) -> Vec<(CarID, ParkingSpot)> {
+        self.people[p.0]
+            .vehicles
+            .iter()
+            .filter(|v| !self.active_trip_mode.contains_key(&AgentID::Car(v.id)))
+            .filter_map(|v| parking.lookup_parked_car(v.id).map(|pc| (v.id, pc.spot)))
+            .collect()
+    }
 
     pub fn trip_to_person(&self, id: TripID) -> PersonID {
         self.trips[id.0].person
@@ -976,6 +2598,10 @@ impl TripManager {
                 ),
             ));
         }
+        self.events.push(Event::DelayedTripStarted(
+            trip,
+            now - self.trips[trip.0].info.departure,
+        ));
         self.start_trip(now, trip, spec, maybe_req, maybe_path, ctx);
     }
 
@@ -989,9 +2615,17 @@ impl TripManager {
         ctx: &mut Ctx,
     ) {
         assert!(self.trips[trip.0].info.cancellation_reason.is_none());
-        if !self.pathfinding_upfront && maybe_path.is_none() && maybe_req.is_some() {
+        // If paths were calculated upfront when this trip was scheduled, a missing path means
+        // pathfinding already failed. Trips scheduled before upfront pathfinding was turned on
+        // still need to find one.
+        if !self.trips[trip.0].pathfinding_upfront && maybe_path.is_none() && maybe_req.is_some() {
             maybe_path = ctx.map.pathfind(maybe_req.clone().unwrap());
         }
+        // Only give up on a trip that had nowhere to appear if there's still no way to route it.
+        let (spec, maybe_req, maybe_path) = match spec.find_room_to_spawn(ctx.map) {
+            Some((spec, req, path)) => (spec, Some(req), Some(path)),
+            None => (spec, maybe_req, maybe_path),
+        };
 
         let person = &mut self.people[self.trips[trip.0].person.0];
         if let PersonState::Trip(_) = person.state {
@@ -1016,7 +2650,13 @@ impl TripManager {
             ));
             return;
         }
-        self.trips[trip.0].started = true;
+        self.trips[trip.0].started_at = Some(now);
+        if let Some(limit) = self.max_trip_duration {
+            ctx.scheduler.push(
+                now.max(self.trips[trip.0].info.departure + limit),
+                Command::AbortLongTrip(trip),
+            );
+        }
 
         match spec {
             TripSpec::VehicleAppearing {
@@ -1045,6 +2685,7 @@ impl TripManager {
                         path,
                         now,
                         vehicle.id,
+                        trip,
                         &mut self.trips[trip.0].info.capped,
                         ctx.map,
                     )
@@ -1062,14 +2703,15 @@ impl TripManager {
                         );
                     }
                     None => {
-                        // TODO Reason might be related to cap
+                        let capped = self.trips[trip.0].info.capped;
+                        let msg = format!(
+                            "VehicleAppearing trip couldn't find the first path: {}",
+                            req
+                        );
                         self.cancel_trip(
                             now,
                             trip,
-                            format!(
-                                "VehicleAppearing trip couldn't find the first path: {}",
-                                req
-                            ),
+                            CancellationReason::path_failed(req, capped, msg),
                             Some(vehicle),
                             ctx,
                         );
@@ -1082,11 +2724,12 @@ impl TripManager {
                 error,
                 ..
             } => {
+                // find_room_to_spawn already looked for a path from the border and failed
                 let vehicle = person.get_vehicle(use_vehicle);
                 self.cancel_trip(
                     now,
                     trip,
-                    format!("couldn't spawn at border {}: {}", i, error),
+                    CancellationReason::Other(format!("couldn't spawn at border {}: {}", i, error)),
                     Some(vehicle),
                     ctx,
                 );
@@ -1126,10 +2769,12 @@ impl TripManager {
                     } else {
                         // Move the car to the destination
                         ctx.parking.remove_parked_car(parked_car.clone());
+                        let msg =
+                            format!("UsingParkedCar trip couldn't find the walking path {}", req);
                         self.cancel_trip(
                             now,
                             trip,
-                            format!("UsingParkedCar trip couldn't find the walking path {}", req),
+                            CancellationReason::NoPath(req, msg),
                             Some(parked_car.vehicle),
                             ctx,
                         );
@@ -1140,7 +2785,10 @@ impl TripManager {
                     self.cancel_trip(
                         now,
                         trip,
-                        format!("should have {} parked somewhere, but it's unavailable", car),
+                        CancellationReason::Other(format!(
+                            "should have {} parked somewhere, but it's unavailable",
+                            car
+                        )),
                         None,
                         ctx,
                     );
@@ -1192,13 +2840,8 @@ impl TripManager {
                         }),
                     );
                 } else {
-                    self.cancel_trip(
-                        now,
-                        trip,
-                        format!("JustWalking trip couldn't find the first path {}", req),
-                        None,
-                        ctx,
-                    );
+                    let msg = format!("JustWalking trip couldn't find the first path {}", req);
+                    self.cancel_trip(now, trip, CancellationReason::NoPath(req, msg), None, ctx);
                 }
             }
             TripSpec::UsingBike { start, .. } => {
@@ -1222,10 +2865,11 @@ impl TripManager {
                             }),
                         );
                     } else {
+                        let msg = format!("UsingBike trip couldn't find the first path {}", req);
                         self.cancel_trip(
                             now,
                             trip,
-                            format!("UsingBike trip couldn't find the first path {}", req),
+                            CancellationReason::NoPath(req, msg),
                             None,
                             ctx,
                         );
@@ -1234,10 +2878,10 @@ impl TripManager {
                     self.cancel_trip(
                         now,
                         trip,
-                        format!(
+                        CancellationReason::Other(format!(
                             "UsingBike trip couldn't find a way to start biking from {}",
                             start
-                        ),
+                        )),
                         None,
                         ctx,
                     );
@@ -1290,15 +2934,59 @@ impl TripManager {
                         }),
                     );
                 } else {
-                    self.cancel_trip(
+                    let msg = format!("UsingTransit trip couldn't find the first path {}", req);
+                    self.cancel_trip(now, trip, CancellationReason::NoPath(req, msg), None, ctx);
+                }
+            }
+            TripSpec::StartOnBus {
+                bus, maybe_stop2, ..
+            } => {
+                assert_eq!(person.state, PersonState::OffMap);
+                let agent = AgentID::BusPassenger(person.id, bus);
+                if let TripEndpoint::Border(i, ref loc) = self.trips[trip.0].info.start {
+                    self.events
+                        .push(Event::PersonEntersMap(person.id, agent, i, loc.clone()));
+                }
+                person.state = PersonState::Trip(trip);
+                let person = person.id;
+                self.start_riding_bus(trip, person, bus);
+                // Once the rider is attached to the bus, bus_rider_boarded records where
+                self.new_bus_riders.push((person, bus, maybe_stop2));
+            }
+            TripSpec::UsingBikeShare {
+                start, from_dock, ..
+            } => {
+                assert_eq!(person.state, PersonState::Inside(start));
+                person.state = PersonState::Trip(trip);
+
+                let req = maybe_req.unwrap();
+                if let Some(path) = maybe_path {
+                    ctx.scheduler.push(
                         now,
-                        trip,
-                        format!("UsingTransit trip couldn't find the first path {}", req),
-                        None,
-                        ctx,
+                        Command::SpawnPed(CreatePedestrian {
+                            id: person.ped,
+                            speed: person.ped_speed,
+                            start: SidewalkSpot::building(start, ctx.map),
+                            goal: SidewalkSpot::bike_rack(from_dock, ctx.map).unwrap(),
+                            path,
+                            req,
+                            trip,
+                            person: person.id,
+                        }),
                     );
+                } else {
+                    let msg = format!("UsingBikeShare trip couldn't find the first path {}", req);
+                    self.cancel_trip(now, trip, CancellationReason::NoPath(req, msg), None, ctx);
                 }
             }
+            TripSpec::UsingRideHail { start, goal } => {
+                assert_eq!(person.state, PersonState::Inside(start));
+                person.state = PersonState::Trip(trip);
+                self.events
+                    .push(Event::PersonLeavesBuilding(person.id, start));
+                let person = person.id;
+                self.dispatch_ride_hail(now, trip, person, start, goal, ctx);
+            }
             TripSpec::Remote {
                 trip_time, from, ..
             } => {
@@ -1318,6 +3006,141 @@ impl TripManager {
         }
     }
 
+    fn dispatch_ride_hail(
+        &mut self,
+        now: Time,
+        trip: TripID,
+        person: PersonID,
+        start: BuildingID,
+        goal: DrivingGoal,
+        ctx: &mut Ctx,
+    ) {
+        let pickup = if let Some((pos, _)) = ctx.map.get_b(start).driving_connection(ctx.map) {
+            pos
+        } else {
+            self.cancel_trip(
+                now,
+                trip,
+                CancellationReason::Other(format!(
+                    "nowhere for a ride-hailing vehicle to pick up at {}",
+                    start
+                )),
+                None,
+                ctx,
+            );
+            return;
+        };
+        let dropoff = match goal {
+            DrivingGoal::ParkNear(b) => ctx
+                .map
+                .get_b(b)
+                .driving_connection(ctx.map)
+                .map(|(pos, _)| pos),
+            DrivingGoal::Border(_, _, _) | DrivingGoal::Vanish(_) => {
+                goal.goal_pos(PathConstraints::Car, ctx.map)
+            }
+        };
+        let dropoff = if let Some(pos) = dropoff {
+            pos
+        } else {
+            self.cancel_trip(
+                now,
+                trip,
+                CancellationReason::Other(format!(
+                    "nowhere for a ride-hailing vehicle to drop off near {:?}",
+                    goal
+                )),
+                None,
+                ctx,
+            );
+            return;
+        };
+        if pickup == dropoff {
+            self.cancel_trip(
+                now,
+                trip,
+                CancellationReason::Other(format!(
+                    "ride-hailing trip from {} doesn't go anywhere",
+                    start
+                )),
+                None,
+                ctx,
+            );
+            return;
+        }
+
+        let vehicle = if let Some(v) = self.ride_hail_fleet.pop() {
+            v
+        } else {
+            self.cancel_trip(
+                now,
+                trip,
+                CancellationReason::Other(format!(
+                    "no ride-hailing vehicle available to pick up at {}",
+                    start
+                )),
+                None,
+                ctx,
+            );
+            return;
+        };
+        // From here on, cancelling the trip returns the vehicle to the fleet.
+        self.ride_hail_busy.insert(vehicle.id, vehicle.clone());
+        self.trips[trip.0].legs[0] = TripLeg::RideHail(Some(vehicle.id), goal.clone());
+
+        let req = PathRequest {
+            start: pickup,
+            end: dropoff,
+            constraints: PathConstraints::Car,
+        };
+        match ctx.map.pathfind(req.clone()).and_then(|path| {
+            ctx.cap.validate_path(
+                &req,
+                path,
+                now,
+                vehicle.id,
+                trip,
+                &mut self.trips[trip.0].info.capped,
+                ctx.map,
+            )
+        }) {
+            Some(path) => {
+                let router = match goal {
+                    DrivingGoal::ParkNear(b) => Router::drop_off(
+                        vehicle.id,
+                        path,
+                        dropoff.dist_along(),
+                        SidewalkSpot {
+                            connection: SidewalkPOI::SuddenlyAppear,
+                            sidewalk_pos: dropoff.equiv_pos(ctx.map.get_b(b).sidewalk(), ctx.map),
+                        },
+                    ),
+                    DrivingGoal::Border(_, _, _) | DrivingGoal::Vanish(_) => {
+                        goal.make_router(vehicle.id, path, ctx.map)
+                    }
+                };
+                ctx.scheduler.push(
+                    now,
+                    Command::SpawnCar(
+                        CreateCar::for_appearing(vehicle, pickup, router, req, trip, person),
+                        true,
+                    ),
+                );
+            }
+            None => {
+                let capped = self.trips[trip.0].info.capped;
+                let msg = format!("ride-hailing vehicle couldn't find a path {}", req);
+                self.cancel_trip(
+                    now,
+                    trip,
+                    CancellationReason::path_failed(req, capped, msg),
+                    Some(vehicle),
+                    ctx,
+                );
+            }
+        }
+    }
+
     pub fn all_arrivals_at_border(&self, at: IntersectionID) -> Vec<(Time, AgentType)> {
         let mut times = Vec::new();
         for t in &self.trips {
@@ -1326,14 +3149,10 @@ impl TripManager {
             }
             if let TripEndpoint::Border(i, _) = t.info.start {
                 if i == at {
-                    // We can make some assumptions here.
-                    let agent_type = match t.info.mode {
-                        TripMode::Walk => AgentType::Pedestrian,
-                        TripMode::Bike => AgentType::Bike,
-                        TripMode::Drive => AgentType::Car,
-                        // TODO Not true for long. People will be able to spawn at borders already
-                        // on a bus.
-                        TripMode::Transit => AgentType::Pedestrian,
+                    let agent_type = if t.starts_on_bus {
+                        AgentType::TransitRider
+                    } else {
+                        border_agent_type(t.info.mode)
                     };
                     times.push((t.info.departure, agent_type));
                 }
@@ -1343,6 +3162,39 @@ impl TripManager {
         times
     }
 
+    /// When every trip ending at this border leaves the map. Trips that haven't finished yet use
+    /// their departure plus `estimate_duration`, and are skipped if they can't be routed.
+    pub fn all_departures_at_border(
+        &self,
+        at: IntersectionID,
+        map: &Map,
+    ) -> Vec<(Time, AgentType)> {
+        let mut times = Vec::new();
+        for t in &self.trips {
+            if t.info.cancellation_reason.is_some() {
+                continue;
+            }
+            if let TripEndpoint::Border(i, _) = t.info.end {
+                if i == at {
+                    let time = t.finished_at.or_else(|| {
+                        let estimate = self.estimate_duration(
+                            t.info.start.clone(),
+                            t.info.end.clone(),
+                            t.info.mode,
+                            map,
+                        )?;
+                        Some(t.info.departure + estimate)
+                    });
+                    if let Some(time) = time {
+                        times.push((time, border_agent_type(t.info.mode)));
+                    }
+                }
+            }
+        }
+        times.sort();
+        times
+    }
+
     // TODO This could be lossy. There are a few layers in spawning trips, and things like
     // spawn_agents_around reach into one of the middle layers directly. So here in TripManager, we
     // might not have retained enough state to create a proper scenario. But this should work
@@ -1350,39 +3202,114 @@ impl TripManager {
     pub fn generate_scenario(&self, map: &Map, name: String) -> Scenario {
         let mut scenario = Scenario::empty(map, &name);
         for p in &self.people {
-            scenario.people.push(PersonSpec {
-                id: p.id,
-                orig_id: p.orig_id,
-                trips: p
-                    .trips
-                    .iter()
-                    .filter_map(|t| {
-                        let trip = &self.trips[t.0];
-                        SpawnTrip::new(
-                            trip.info.start.clone(),
-                            trip.info.end.clone(),
-                            trip.info.mode,
-                            map,
-                        )
-                        .map(|spawn| {
-                            IndividTrip::new(trip.info.departure, trip.info.purpose, spawn)
-                        })
-                    })
-                    .collect(),
-            });
+            scenario
+                .people
+                .push(self.export_person(p, p.id, map, |_| true));
+        }
+        scenario
+    }
+
+    /// Like generate_scenario, but only keeps trips matching the predicate. People left without
+    /// any trips are dropped, and the rest are renumbered, so PersonIDs might not match the current
+    /// simulation.
+    pub fn generate_scenario_filtered<F: Fn(&TripInfo) -> bool>(
+        &self,
+        map: &Map,
+        name: String,
+        keep: F,
+    ) -> Scenario {
+        let mut scenario = Scenario::empty(map, &name);
+        for p in &self.people {
+            // IDs have to stay contiguous after dropping people
+            let person = self.export_person(p, PersonID(scenario.people.len()), map, &keep);
+            if !person.trips.is_empty() {
+                scenario.people.push(person);
+            }
         }
         scenario
     }
+
+    fn export_person<F: Fn(&TripInfo) -> bool>(
+        &self,
+        p: &Person,
+        id: PersonID,
+        map: &Map,
+        keep: F,
+    ) -> PersonSpec {
+        let mut person = PersonSpec {
+            id,
+            orig_id: p.orig_id,
+            trips: p
+                .trips
+                .iter()
+                .filter(|t| keep(&self.trips[t.0].info))
+                .filter_map(|t| {
+                    let trip = &self.trips[t.0];
+                    SpawnTrip::new(
+                        trip.info.start.clone(),
+                        trip.info.end.clone(),
+                        trip.info.mode,
+                        map,
+                    )
+                    .map(|spawn| IndividTrip::new(trip.info.departure, trip.info.purpose, spawn))
+                })
+                .collect(),
+            vehicles: Vec::new(),
+        };
+        // The order matches how vehicles were originally assigned to trips, so instantiating this
+        // scenario reuses the same vehicle for the same trips. If some trips were dropped, the
+        // vehicles might not line up anymore, and random ones get used instead.
+        person.record_vehicles(p.vehicles.iter().map(|v| v.to_spec()).collect());
+        person
+    }
+}
+
+// We can make some assumptions about the agent crossing a border.
+fn border_agent_type(mode: TripMode) -> AgentType {
+    match mode {
+        TripMode::Walk => AgentType::Pedestrian,
+        // TODO Scooters should probably be their own AgentType eventually
+        TripMode::Bike | TripMode::Scooter => AgentType::Bike,
+        TripMode::Drive => AgentType::Car,
+        // Unless they start the trip already on a bus
+        TripMode::Transit => AgentType::Pedestrian,
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct Trip {
     id: TripID,
     info: TripInfo,
-    started: bool,
+    /// Might be later than the departure, if the person's previous trip wasn't done
+    started_at: Option<Time>,
     finished_at: Option<Time>,
+    /// The sum of everything in blocked_time_breakdown
     total_blocked_time: Duration,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    blocked_time_breakdown: BTreeMap<BlockedCause, Duration>,
+    /// The sum of everything in distance_breakdown
+    total_distance: Distance,
+    /// The length of each leg's path when it spawned, grouped by who followed it
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    distance_breakdown: BTreeMap<PathConstraints, Distance>,
+    /// How many times a vehicle for this trip failed to spawn because there was no room
+    spawn_attempts: usize,
+    /// Was the first path calculated when this trip was scheduled? If so and it failed, there's
+    /// no point trying again when the trip starts.
+    pathfinding_upfront: bool,
+    /// The person enters the map already riding a bus, instead of walking to a stop
+    starts_on_bus: bool,
+    /// Where the person got on the bus they're currently riding
+    boarded_at: Option<BusStopID>,
     legs: VecDeque<TripLeg>,
+    /// Legs already done, with the time each one finished
+    completed_legs: Vec<(TripLeg, Time)>,
     person: PersonID,
 }
 
@@ -1398,10 +3325,173 @@ pub struct TripInfo {
     pub modified: bool,
     /// Was this trip affected by a congestion cap?
     pub capped: bool,
-    pub cancellation_reason: Option<String>,
+    pub cancellation_reason: Option<CancellationReason>,
+    /// How many times the person got off a bus or train and later boarded another one
+    pub transfers: usize,
+}
+
+/// One entry in a person's itinerary
+#[derive(Clone, Debug)]
+pub struct ScheduledTrip {
+    pub id: TripID,
+    pub departure: Time,
+    pub mode: TripMode,
+    pub start: TripEndpoint,
+    pub end: TripEndpoint,
+    pub finished: Option<Time>,
+}
+
+/// Every trip that was cancelled, before or after starting
+#[derive(Clone, Debug)]
+pub struct UnmetDemand {
+    pub total: usize,
+    pub by_reason: BTreeMap<CancellationKind, usize>,
+    pub by_mode: BTreeMap<TripMode, usize>,
+    pub trips: Vec<(TripID, CancellationReason)>,
+}
+
+/// A flattened summary of one trip that's done, either finished or cancelled, for offline analysis.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TripRecord {
+    pub trip: TripID,
+    pub person: PersonID,
+    pub orig_person: Option<OrigPersonID>,
+    pub mode: TripMode,
+    pub purpose: TripPurpose,
+    pub start: TripEndpoint,
+    pub end: TripEndpoint,
+    pub departure: Time,
+    /// None for cancelled trips
+    pub finished_at: Option<Time>,
+    /// None for cancelled trips
+    pub total_time: Option<Duration>,
+    pub blocked_time: Duration,
+    pub capped: bool,
+    pub cancellation_reason: Option<CancellationReason>,
+}
+
+/// Add up the occupancy of the same building from two simulations.
+fn merge_occupancy(a: Vec<(Time, usize)>, b: Vec<(Time, usize)>) -> Vec<(Time, usize)> {
+    let mut times: Vec<Time> = a.iter().chain(b.iter()).map(|(t, _)| *t).collect();
+    times.sort();
+    times.dedup();
+
+    let mut result = Vec::new();
+    let (mut idx_a, mut idx_b) = (0, 0);
+    let (mut cnt_a, mut cnt_b) = (0, 0);
+    for t in times {
+        while idx_a < a.len() && a[idx_a].0 <= t {
+            cnt_a = a[idx_a].1;
+            idx_a += 1;
+        }
+        while idx_b < b.len() && b[idx_b].0 <= t {
+            cnt_b = b[idx_b].1;
+            idx_b += 1;
+        }
+        result.push((t, cnt_a + cnt_b));
+    }
+    result
+}
+
+/// Called when a trip is cancelled. Not persisted or cloned.
+#[derive(Default)]
+struct CancellationHook(Option<Box<dyn FnMut(TripID, &CancellationReason)>>);
+
+impl Clone for CancellationHook {
+    fn clone(&self) -> CancellationHook {
+        CancellationHook(None)
+    }
+}
+
+impl fmt::Debug for CancellationHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CancellationHook({})", self.0.is_some())
+    }
+}
+
+/// Why a trip was stuck waiting
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BlockedCause {
+    /// Stuck in traffic or waiting to cross an intersection, while walking, biking, or driving.
+    Intersection,
+    /// Waiting at a stop for a bus or train
+    Transit,
+    /// Stuck anywhere while circling around looking for parking or starting to park, or stuck
+    /// behind somebody else parking.
+    Parking,
+    /// Stuck behind something else that stopped in the lane, like a bus at a stop.
+    Other,
+}
+
+/// Why a trip was cancelled
+///
+/// The message kept alongside some reasons is what gets logged, so it says which part of the trip
+/// couldn't be routed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum CancellationReason {
+    NoPath(PathRequest, String),
+    NoParking(BuildingID),
+    /// A congestion cap prevented the trip from finding any path
+    OverCap(String),
+    Other(String),
+}
+
+/// The kind of problem that cancelled a trip, without any details
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CancellationKind {
+    NoPath,
+    NoParking,
+    OverCap,
+    Other,
+}
+
+impl CancellationReason {
+    pub fn kind(&self) -> CancellationKind {
+        match self {
+            CancellationReason::NoPath(_, _) => CancellationKind::NoPath,
+            CancellationReason::NoParking(_) => CancellationKind::NoParking,
+            CancellationReason::OverCap(_) => CancellationKind::OverCap,
+            CancellationReason::Other(_) => CancellationKind::Other,
+        }
+    }
+
+    /// Pathfinding for a vehicle failed, possibly because of a congestion cap.
+    fn path_failed(req: PathRequest, capped: bool, msg: String) -> CancellationReason {
+        if capped {
+            CancellationReason::OverCap(msg)
+        } else {
+            CancellationReason::NoPath(req, msg)
+        }
+    }
+}
+
+impl fmt::Display for CancellationReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CancellationReason::NoPath(_, msg) | CancellationReason::OverCap(msg) => {
+                write!(f, "{}", msg)
+            }
+            CancellationReason::NoParking(_) => write!(f, "no available parking anywhere"),
+            CancellationReason::Other(reason) => write!(f, "{}", reason),
+        }
+    }
 }
 
 impl Trip {
+    fn add_blocked_time(&mut self, cause: BlockedCause, dt: Duration) {
+        self.total_blocked_time += dt;
+        *self
+            .blocked_time_breakdown
+            .entry(cause)
+            .or_insert(Duration::ZERO) += dt;
+    }
+
+    fn add_blocked_time_breakdown(&mut self, breakdown: &BTreeMap<BlockedCause, Duration>) {
+        for (cause, dt) in breakdown {
+            self.add_blocked_time(*cause, *dt);
+        }
+    }
+
     // Returns true if this succeeds. If not, trip cancelled.
     fn spawn_ped(
         &self,
@@ -1451,8 +3541,15 @@ impl Trip {
         true
     }
 
-    fn assert_walking_leg(&mut self, goal: SidewalkSpot) {
-        match self.legs.pop_front() {
+    /// Remembers the leg in completed_legs
+    fn finish_leg(&mut self, now: Time) -> Option<TripLeg> {
+        let leg = self.legs.pop_front()?;
+        self.completed_legs.push((leg.clone(), now));
+        Some(leg)
+    }
+
+    fn assert_walking_leg(&mut self, now: Time, goal: SidewalkSpot) {
+        match self.finish_leg(now) {
             Some(TripLeg::Walk(spot)) => {
                 assert_eq!(goal, spot);
             }
@@ -1470,6 +3567,12 @@ pub enum TripLeg {
     Drive(CarID, DrivingGoal),
     /// Maybe get off at a stop, maybe ride off-map
     RideBus(BusRouteID, Option<BusStopID>),
+    /// Picked up by a vehicle owned by nobody, dispatched when the trip starts. None until then.
+    RideHail(Option<CarID>, DrivingGoal),
+    /// Linger inside an intermediate waypoint before continuing the trip
+    Dwell(BuildingID, Duration),
+    /// Take a shared bike from the first dock and return it at the second
+    BikeShare(BuildingID, BuildingID),
     Remote(OffMapLocation),
 }
 
@@ -1479,6 +3582,7 @@ pub enum TripMode {
     Bike,
     Transit,
     Drive,
+    Scooter,
 }
 
 impl TripMode {
@@ -1488,6 +3592,7 @@ impl TripMode {
             TripMode::Bike,
             TripMode::Transit,
             TripMode::Drive,
+            TripMode::Scooter,
         ]
     }
 
@@ -1497,6 +3602,7 @@ impl TripMode {
             TripMode::Bike => "bike",
             TripMode::Transit => "use transit",
             TripMode::Drive => "drive",
+            TripMode::Scooter => "ride a scooter",
         }
     }
 
@@ -1507,6 +3613,7 @@ impl TripMode {
             TripMode::Bike => "biking",
             TripMode::Transit => "using transit",
             TripMode::Drive => "driving",
+            TripMode::Scooter => "riding a scooter",
         }
     }
 
@@ -1516,6 +3623,7 @@ impl TripMode {
             TripMode::Bike => "Bike",
             TripMode::Transit => "Bus",
             TripMode::Drive => "Car",
+            TripMode::Scooter => "Scooter",
         }
     }
 
@@ -1526,12 +3634,15 @@ impl TripMode {
             // TODO WRONG
             TripMode::Transit => PathConstraints::Bus,
             TripMode::Drive => PathConstraints::Car,
+            // TODO Scooters use bike lanes for now, but they might get their own constraints
+            TripMode::Scooter => PathConstraints::Bike,
         }
     }
 
     pub fn from_constraints(c: PathConstraints) -> TripMode {
         match c {
             PathConstraints::Pedestrian => TripMode::Walk,
+            // Scooters also use this, but there's no way to tell
             PathConstraints::Bike => TripMode::Bike,
             // TODO The bijection breaks down... transit rider vs train vs bus...
             PathConstraints::Bus | PathConstraints::Train => TripMode::Transit,
@@ -1544,6 +3655,9 @@ impl TripMode {
 pub enum TripEndpoint {
     Bldg(BuildingID),
     Border(IntersectionID, Option<OffMapLocation>),
+    /// Anywhere along a lane. People walk from or to the equivalent spot on the nearest sidewalk,
+    /// and vehicles start or stop on the nearest lane they can use.
+    SuddenlyAppear(Position),
 }
 
 impl TripEndpoint {
@@ -1559,7 +3673,7 @@ impl TripEndpoint {
             constraints: match mode {
                 TripMode::Walk | TripMode::Transit => PathConstraints::Pedestrian,
                 TripMode::Drive => PathConstraints::Car,
-                TripMode::Bike => PathConstraints::Bike,
+                TripMode::Bike | TripMode::Scooter => PathConstraints::Bike,
             },
         })
     }
@@ -1569,7 +3683,9 @@ fn pos(endpt: TripEndpoint, mode: TripMode, from: bool, map: &Map) -> Option<Pos
     match endpt {
         TripEndpoint::Bldg(b) => match mode {
             TripMode::Walk | TripMode::Transit => Some(map.get_b(b).sidewalk_pos),
-            TripMode::Bike => Some(DrivingGoal::ParkNear(b).goal_pos(PathConstraints::Bike, map)?),
+            TripMode::Bike | TripMode::Scooter => {
+                Some(DrivingGoal::ParkNear(b).goal_pos(PathConstraints::Bike, map)?)
+            }
             TripMode::Drive => Some(
                 DrivingGoal::ParkNear(b)
                     .goal_pos(PathConstraints::Car, map)
@@ -1583,25 +3699,41 @@ fn pos(endpt: TripEndpoint, mode: TripMode, from: bool, map: &Map) -> Option<Pos
                 SidewalkSpot::end_at_border(i, None, map)
             }
             .map(|spot| spot.sidewalk_pos),
-            TripMode::Bike | TripMode::Drive => (if from {
+            TripMode::Bike | TripMode::Scooter | TripMode::Drive => (if from {
                 map.get_i(i).some_outgoing_road(map)
             } else {
                 map.get_i(i).some_incoming_road(map)
             })
             .and_then(|dr| {
-                dr.lanes(
-                    if mode == TripMode::Bike {
-                        PathConstraints::Bike
-                    } else {
-                        PathConstraints::Car
-                    },
-                    map,
-                )
-                .get(0)
-                .map(|l| Position::start(*l))
+                dr.lanes(mode.to_constraints(), map)
+                    .get(0)
+                    .map(|l| Position::start(*l))
             }),
         },
+        TripEndpoint::SuddenlyAppear(pos) => match mode {
+            TripMode::Walk | TripMode::Transit => equiv_pos(pos, PathConstraints::Pedestrian, map),
+            TripMode::Bike | TripMode::Scooter | TripMode::Drive => {
+                equiv_pos(pos, mode.to_constraints(), map)
+            }
+        },
+    }
+}
+
+/// The same spot as `pos`, but on the closest lane of the same road usable by `constraints`.
+pub(crate) fn equiv_pos(
+    pos: Position,
+    constraints: PathConstraints,
+    map: &Map,
+) -> Option<Position> {
+    if constraints.can_use(map.get_l(pos.lane()), map) {
+        return Some(pos);
     }
+    let l = map.get_parent(pos.lane()).find_closest_lane(
+        pos.lane(),
+        |l| constraints.can_use(l, map),
+        map,
+    )?;
+    Some(pos.equiv_pos(l, map))
 }
 
 pub enum TripResult<T> {
@@ -1640,13 +3772,15 @@ pub struct Person {
     pub id: PersonID,
     pub orig_id: Option<OrigPersonID>,
     pub trips: Vec<TripID>,
-    // TODO home
     pub state: PersonState,
 
     pub ped: PedestrianID,
     pub ped_speed: Speed,
     /// Both cars and bikes
     pub vehicles: Vec<Vehicle>,
+    /// Inferred from the person's trips, so it's None for people who never start or stop at a
+    /// building on the map.
+    pub home: Option<BuildingID>,
 
     delayed_trips: Vec<(TripID, TripSpec, Option<PathRequest>, Option<Path>)>,
     on_bus: Option<CarID>,
@@ -1672,6 +3806,14 @@ impl TripEndpoint {
             TripEndpoint::Border(i, origin) => {
                 SidewalkSpot::start_at_border(*i, origin.clone(), map)
             }
+            TripEndpoint::SuddenlyAppear(pos) => {
+                let pos = equiv_pos(*pos, PathConstraints::Pedestrian, map)?;
+                Some(SidewalkSpot::suddenly_appear(
+                    pos.lane(),
+                    pos.dist_along(),
+                    map,
+                ))
+            }
         }
     }
 
@@ -1681,6 +3823,7 @@ impl TripEndpoint {
             TripEndpoint::Border(i, destination) => {
                 SidewalkSpot::end_at_border(*i, destination.clone(), map)
             }
+            TripEndpoint::SuddenlyAppear(_) => self.start_sidewalk_spot(map),
         }
     }
 
@@ -1697,6 +3840,9 @@ impl TripEndpoint {
                 destination.clone(),
                 map,
             ),
+            TripEndpoint::SuddenlyAppear(pos) => {
+                Some(DrivingGoal::Vanish(equiv_pos(*pos, constraints, map)?))
+            }
         }
     }
 }
@@ -55,6 +55,11 @@ impl<'a> GfxCtxInnards<'a> {
                 .unwrap();
             self.gl
                 .uniform_3_f32_slice(Some(&window_loc), &uniforms.window);
+            let alpha_loc = self
+                .gl
+                .get_uniform_location(*self.program, "alpha")
+                .unwrap();
+            self.gl.uniform_1_f32(Some(&alpha_loc), uniforms.alpha);
 
             self.gl.bind_vertex_array(Some(obj.vert_array.id));
             self.gl
@@ -90,6 +95,42 @@ impl<'a> GfxCtxInnards<'a> {
         }
     }
 
+    /// Clip to the intersection of this rectangle and any current clip. Returns the previous clip,
+    /// to be passed to pop_clip.
+    pub fn push_clip(
+        &mut self,
+        rect: ScreenRectangle,
+        scale_factor: f64,
+        canvas: &Canvas,
+    ) -> Option<[i32; 4]> {
+        let prev = self.current_clip;
+        let mut left = (rect.x1 * scale_factor) as i32;
+        // Y-inversion
+        let mut bottom = ((canvas.window_height - rect.y2) * scale_factor) as i32;
+        let mut right = (rect.x2 * scale_factor) as i32;
+        let mut top = ((canvas.window_height - rect.y1) * scale_factor) as i32;
+        if let Some(c) = prev {
+            left = left.max(c[0]);
+            bottom = bottom.max(c[1]);
+            right = right.min(c[0] + c[2]);
+            top = top.min(c[1] + c[3]);
+        }
+        let clip = [left, bottom, (right - left).max(0), (top - bottom).max(0)];
+        unsafe {
+            self.gl.scissor(clip[0], clip[1], clip[2], clip[3]);
+        }
+        self.current_clip = Some(clip);
+        prev
+    }
+
+    pub fn pop_clip(&mut self, prev: Option<[i32; 4]>, scale_factor: f64, canvas: &Canvas) {
+        if prev.is_some() {
+            self.restore_clip(prev);
+        } else {
+            self.disable_clipping(scale_factor, canvas);
+        }
+    }
+
     pub fn take_clip(&mut self, scale_factor: f64, canvas: &Canvas) -> Option<[i32; 4]> {
         let clip = self.current_clip?;
         self.disable_clipping(scale_factor, canvas);
@@ -24,6 +24,8 @@ pub struct Uniforms {
     pub transform: [f32; 3],
     // (window_width, window_height, Z values)
     pub window: [f32; 3],
+    // Multiplies the alpha of everything drawn, from 0 to 1
+    pub alpha: f32,
 }
 
 impl Uniforms {
@@ -39,6 +41,7 @@ impl Uniforms {
                 canvas.window_height as f32,
                 MAPSPACE_Z,
             ],
+            alpha: 1.0,
         }
     }
 }
@@ -114,7 +117,10 @@ impl<'a> GfxCtx<'a> {
     }
 
     pub fn unfork(&mut self) {
+        // Transparency isn't part of the transformation, so keep whatever's in effect
+        let alpha = self.uniforms.alpha;
         self.uniforms = Uniforms::new(&self.canvas);
+        self.uniforms.alpha = alpha;
         self.num_forks += 1;
     }
 
@@ -153,6 +159,30 @@ impl<'a> GfxCtx<'a> {
         self.inner.disable_clipping(scale_factor, self.canvas);
     }
 
+    /// Like enable_clipping, but nests inside any clipping already in effect. Pass the result to
+    /// pop_clipping when done.
+    pub(crate) fn push_clipping(&mut self, rect: ScreenRectangle) -> Option<[i32; 4]> {
+        let scale_factor = self.prerender.get_scale_factor();
+        self.inner.push_clip(rect, scale_factor, self.canvas)
+    }
+
+    pub(crate) fn pop_clipping(&mut self, prev: Option<[i32; 4]>) {
+        let scale_factor = self.prerender.get_scale_factor();
+        self.inner.pop_clip(prev, scale_factor, self.canvas);
+    }
+
+    /// Multiplies the alpha of everything drawn until pop_alpha by this, on top of any alpha
+    /// already in effect. Pass the result to pop_alpha when done.
+    pub(crate) fn push_alpha(&mut self, alpha: f32) -> f32 {
+        let prev = self.uniforms.alpha;
+        self.uniforms.alpha *= alpha;
+        prev
+    }
+
+    pub(crate) fn pop_alpha(&mut self, prev: f32) {
+        self.uniforms.alpha = prev;
+    }
+
     // Canvas stuff.
 
     pub fn draw_mouse_tooltip(&mut self, txt: Text) {
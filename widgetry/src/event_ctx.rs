@@ -102,6 +102,17 @@ impl<'a> EventCtx<'a> {
         false
     }
 
+    pub fn normal_right_click(&mut self) -> bool {
+        if self.input.has_been_consumed() {
+            return false;
+        }
+        if self.input.right_mouse_button_released() {
+            self.input.consume_event();
+            return true;
+        }
+        false
+    }
+
     fn is_dragging(&self) -> bool {
         self.canvas.drag_canvas_from.is_some() || self.canvas.drag_just_ended
     }
@@ -1,4 +1,4 @@
-use geom::{Angle, Bounds, Polygon, Pt2D};
+use geom::{Angle, Bounds, Line, Polygon, Pt2D};
 
 use crate::widgets::button::BtnBuilder;
 use crate::{
@@ -163,8 +163,13 @@ impl GeomBatch {
 
     /// Translates the batch by some offset.
     pub fn translate(mut self, dx: f64, dy: f64) -> GeomBatch {
-        for (_, poly, _) in &mut self.list {
+        for (fill, poly, _) in &mut self.list {
             *poly = poly.translate(dx, dy);
+            // Gradients are defined in the same coordinate space as the polygon
+            if let Fill::LinearGradient(ref mut lg) = fill {
+                lg.line =
+                    Line::must_new(lg.line.pt1().offset(dx, dy), lg.line.pt2().offset(dx, dy));
+            }
         }
         self
     }
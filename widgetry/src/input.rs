@@ -77,6 +77,9 @@ impl UserInput {
     pub fn left_mouse_button_released(&mut self) -> bool {
         self.event == Event::LeftMouseButtonUp
     }
+    pub fn right_mouse_button_released(&mut self) -> bool {
+        self.event == Event::RightMouseButtonUp
+    }
 
     pub fn window_lost_cursor(&self) -> bool {
         self.event == Event::WindowLostCursor
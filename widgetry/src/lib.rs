@@ -8,19 +8,25 @@
 //! * [`Autocomplete`] - select predefined value by combining text entry with menus
 //! * [`Button`] - clickable buttons with keybindings and tooltips
 //! * [`Checkbox`] - toggle between two buttons
+//! * [`ColorPicker`] - pick from a grid of preset colors
 //! * [`CompareTimes`] - a scatter plot specialized for comparing times
+//! * [`Divider`] - a line separating sections, stretching across its row or column
 //! * [`DrawWithTooltips`] - draw static geometry, with mouse tooltips in certain regions
 //! * [`Dropdown`] - a button that expands into a menu
 //! * [`FanChart`] - visualize a range of values over time
 //! * [`Filler`] - just carve out space in the layout for something else
-//! * [`JustDraw`] (argh private) - just draw text, `GeomBatch`es, SVGs
+//! * [`Histogram`] - group durations into buckets, and click a bucket to select it
+//! * [`JustDraw`] (argh private) - just draw text, `GeomBatch`es, SVGs, PNGs
 //! * [`LinePlot`] - visualize 2 variables with a line plot
+//! * [`LoadingSpinner`] - an animated arc, for when something's happening in the background
 //! * [`Menu`] - select something from a menu, with keybindings
 //! * [`MultiButton`] - clickable regions in one batch of geometry
 //! * [`PersistentSplit`] - a button with a dropdown to change its state
+//! * [`RadioGroup`] - pick exactly one of several options
 //! * [`ScatterPlot`] - visualize 2 variables with a scatter plot
 //! * [`Slider`] - horizontal and vertical sliders
 //! * [`Spinner`] - numeric input with up/down buttons
+//! * [`TabController`] - show one of several tabs, keeping the state of hidden ones
 //! * [`TexBox`] - single line text entry
 
 //#![warn(missing_docs)]
@@ -45,18 +51,24 @@ pub use crate::widgets::autocomplete::Autocomplete;
 pub(crate) use crate::widgets::button::Button;
 pub use crate::widgets::button::{Btn, MultiButton};
 pub use crate::widgets::checkbox::Checkbox;
+pub use crate::widgets::color_picker::ColorPicker;
 pub use crate::widgets::compare_times::CompareTimes;
+pub use crate::widgets::divider::Divider;
 pub(crate) use crate::widgets::dropdown::Dropdown;
 pub use crate::widgets::fan_chart::FanChart;
 pub use crate::widgets::filler::Filler;
-pub use crate::widgets::just_draw::DrawWithTooltips;
+pub use crate::widgets::histogram::Histogram;
 pub(crate) use crate::widgets::just_draw::{DeferDraw, JustDraw};
+pub use crate::widgets::just_draw::{DrawWithTooltips, ImageSource};
 pub use crate::widgets::line_plot::{LinePlot, PlotOptions, Series};
+pub use crate::widgets::loading_spinner::LoadingSpinner;
 pub use crate::widgets::menu::Menu;
 pub use crate::widgets::persistent_split::PersistentSplit;
+pub use crate::widgets::radio::RadioGroup;
 pub use crate::widgets::scatter_plot::ScatterPlot;
 pub use crate::widgets::slider::{AreaSlider, Slider};
 pub use crate::widgets::spinner::Spinner;
+pub use crate::widgets::tabs::TabController;
 pub(crate) use crate::widgets::text_box::TextBox;
 pub use crate::widgets::{EdgeInsets, Outcome, Panel, Widget, WidgetImpl, WidgetOutput};
 
@@ -30,7 +30,7 @@ impl From<winit::dpi::LogicalPosition<f64>> for ScreenPt {
 }
 
 /// ScreenRectangle is in units of logical pixels, as opposed to physical pixels.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ScreenRectangle {
     pub x1: f64,
     pub y1: f64,
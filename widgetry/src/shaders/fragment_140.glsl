@@ -6,6 +6,8 @@ uniform vec3 transform;
 uniform vec3 window;
 // textures grid
 uniform sampler2DArray textures;
+// multiplies the alpha of everything drawn
+uniform float alpha;
 
 in vec4 fs_color;
 in vec3 fs_texture_coord;
@@ -14,4 +16,5 @@ out vec4 out_color;
 
 void main() {
     out_color = fs_color * texture(textures, fs_texture_coord);
+    out_color.a *= alpha;
 }
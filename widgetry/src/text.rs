@@ -334,6 +334,15 @@ impl Text {
         batch.autocrop()
     }
 
+    /// Just the words, with lines separated by newlines
+    pub(crate) fn to_plaintext(&self) -> String {
+        self.lines
+            .iter()
+            .map(|(_, spans)| spans.iter().map(|s| s.text.as_str()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     fn hash_key(&self) -> String {
         let mut hasher = DefaultHasher::new();
         hasher.write(format!("{:?}", self).as_ref());
@@ -354,6 +363,12 @@ impl Text {
         )
     }
 
+    /// Break lines so none are wider than this many pixels. Single words that are too long still
+    /// stick out.
+    pub fn wrap_to_pixels(self, ctx: &EventCtx, max_width: f64) -> Text {
+        self.inner_wrap_to_pct(max_width, &ctx.prerender.assets)
+    }
+
     pub(crate) fn inner_wrap_to_pct(mut self, limit: f64, assets: &Assets) -> Text {
         let mut lines = Vec::new();
         for (bg, spans) in self.lines.drain(..) {
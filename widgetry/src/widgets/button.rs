@@ -1,10 +1,17 @@
+use instant::Instant;
+
+use abstutil::elapsed_seconds;
 use geom::{Distance, Polygon};
 
 use crate::{
-    svg, Color, Drawable, EdgeInsets, EventCtx, GeomBatch, GfxCtx, Line, MultiKey, Outcome,
-    RewriteColor, ScreenDims, ScreenPt, ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
+    svg, Choice, Color, Drawable, EdgeInsets, EventCtx, GeomBatch, GfxCtx, Line, Menu, MultiKey,
+    Outcome, RewriteColor, ScreenDims, ScreenPt, ScreenRectangle, Text, Widget, WidgetImpl,
+    WidgetOutput,
 };
 
+/// Two clicks on a double-clickable button within this many seconds count as a double click.
+const DOUBLE_CLICK_THRESHOLD_S: f64 = 0.5;
+
 pub struct Button {
     pub action: String,
 
@@ -12,6 +19,7 @@ pub struct Button {
     // 0, 0. Transformation happens later.
     draw_normal: Drawable,
     draw_hovered: Drawable,
+    draw_disabled: Drawable,
 
     pub(crate) hotkey: Option<MultiKey>,
     tooltip: Text,
@@ -19,6 +27,13 @@ pub struct Button {
     hitbox: Polygon,
 
     pub(crate) hovering: bool,
+    /// Disabled buttons are drawn greyed out and can't be hovered on or clicked.
+    pub(crate) disabled: bool,
+    pub(crate) double_clickable: bool,
+    last_click: Option<Instant>,
+    /// Right-clicking opens a menu with these entries
+    pub(crate) context_menu: Vec<String>,
+    open_context_menu: Option<Menu<()>>,
 
     pub(crate) top_left: ScreenPt,
     pub(crate) dims: ScreenDims,
@@ -41,6 +56,7 @@ impl Button {
         Widget::new(Box::new(Button {
             action: tooltip.to_string(),
 
+            draw_disabled: ctx.upload(normal.clone().color(RewriteColor::ChangeAlpha(0.3))),
             draw_normal: ctx.upload(normal),
             draw_hovered: ctx.upload(hovered),
             tooltip: if let Some(t) = maybe_tooltip {
@@ -52,12 +68,44 @@ impl Button {
             hitbox,
 
             hovering: false,
+            disabled: false,
+            double_clickable: false,
+            last_click: None,
+            context_menu: Vec::new(),
+            open_context_menu: None,
 
             top_left: ScreenPt::new(0.0, 0.0),
             dims,
         }))
         .named(tooltip)
     }
+
+    pub(crate) fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+        if disabled {
+            self.hovering = false;
+        }
+    }
+
+    fn open_context_menu(&mut self, ctx: &mut EventCtx) {
+        let mut menu = Menu::new(
+            ctx,
+            self.context_menu
+                .iter()
+                .map(|entry| Choice::new(entry.clone(), ()))
+                .collect(),
+        )
+        .take_menu();
+        // Open at the cursor, but don't leak off the screen
+        let cursor = ctx.canvas.get_cursor();
+        let dims = menu.get_dims();
+        menu.set_pos(ScreenPt::new(
+            cursor.x.min(ctx.canvas.window_width - dims.width),
+            cursor.y.min(ctx.canvas.window_height - dims.height),
+        ));
+        self.open_context_menu = Some(menu);
+        self.hovering = false;
+    }
 }
 
 impl WidgetImpl for Button {
@@ -70,6 +118,33 @@ impl WidgetImpl for Button {
     }
 
     fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if self.disabled {
+            return;
+        }
+
+        if let Some(ref mut menu) = self.open_context_menu {
+            let mut tmp_output = WidgetOutput::new();
+            menu.event(ctx, &mut tmp_output);
+            if let Outcome::Clicked(entry) = tmp_output.outcome {
+                self.open_context_menu = None;
+                output.outcome = Outcome::ContextAction(self.action.clone(), entry);
+            } else if ctx.normal_left_click() || ctx.normal_right_click() {
+                // Clicking anywhere outside the menu closes it
+                let rect = ScreenRectangle::top_left(menu.top_left, menu.get_dims());
+                let inside = ctx
+                    .canvas
+                    .get_cursor_in_screen_space()
+                    .map(|pt| rect.contains(pt))
+                    .unwrap_or(false);
+                if inside {
+                    ctx.input.unconsume_event();
+                } else {
+                    self.open_context_menu = None;
+                }
+            }
+            return;
+        }
+
         if ctx.redo_mouseover() {
             if let Some(pt) = ctx.canvas.get_cursor_in_screen_space() {
                 self.hovering = self
@@ -83,6 +158,24 @@ impl WidgetImpl for Button {
         if self.hovering && ctx.normal_left_click() {
             self.hovering = false;
             output.outcome = Outcome::Clicked(self.action.clone());
+            if self.double_clickable {
+                if self
+                    .last_click
+                    .map(|t| elapsed_seconds(t) <= DOUBLE_CLICK_THRESHOLD_S)
+                    .unwrap_or(false)
+                {
+                    output.outcome = Outcome::DoubleClicked(self.action.clone());
+                    // A third click starts over
+                    self.last_click = None;
+                } else {
+                    self.last_click = Some(Instant::now());
+                }
+            }
+            return;
+        }
+
+        if self.hovering && !self.context_menu.is_empty() && ctx.normal_right_click() {
+            self.open_context_menu(ctx);
             return;
         }
 
@@ -98,7 +191,9 @@ impl WidgetImpl for Button {
     }
 
     fn draw(&self, g: &mut GfxCtx) {
-        if self.hovering {
+        if self.disabled {
+            g.redraw_at(self.top_left, &self.draw_disabled);
+        } else if self.hovering {
             g.redraw_at(self.top_left, &self.draw_hovered);
             if !self.tooltip.is_empty() {
                 g.draw_mouse_tooltip(self.tooltip.clone());
@@ -106,6 +201,9 @@ impl WidgetImpl for Button {
         } else {
             g.redraw_at(self.top_left, &self.draw_normal);
         }
+        if let Some(ref menu) = self.open_context_menu {
+            menu.draw_popup(g);
+        }
     }
 }
 
@@ -193,6 +193,15 @@ impl Checkbox {
     }
 }
 
+impl Checkbox {
+    /// The caller should recompute layout afterwards.
+    pub(crate) fn toggle(&mut self) {
+        std::mem::swap(&mut self.btn, &mut self.other_btn);
+        self.btn.set_pos(self.other_btn.top_left);
+        self.enabled = !self.enabled;
+    }
+}
+
 impl WidgetImpl for Checkbox {
     fn get_dims(&self) -> ScreenDims {
         self.btn.get_dims()
@@ -206,9 +215,7 @@ impl WidgetImpl for Checkbox {
         self.btn.event(ctx, output);
         if let Outcome::Clicked(_) = output.outcome {
             output.outcome = Outcome::Changed;
-            std::mem::swap(&mut self.btn, &mut self.other_btn);
-            self.btn.set_pos(self.other_btn.top_left);
-            self.enabled = !self.enabled;
+            self.toggle();
             output.redo_layout = true;
         }
     }
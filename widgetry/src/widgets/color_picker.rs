@@ -0,0 +1,145 @@
+use geom::{Distance, Polygon};
+
+use crate::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, Outcome, ScreenDims, ScreenPt, ScreenRectangle,
+    Widget, WidgetImpl, WidgetOutput,
+};
+
+const SWATCH_SIZE: f64 = 30.0;
+const SWATCH_PADDING: f64 = 4.0;
+const SWATCHES_PER_ROW: usize = 6;
+const SELECTED_THICKNESS: f64 = 3.0;
+
+/// A grid of preset colors. Exactly one is always selected; picking another returns
+/// Outcome::Changed.
+pub struct ColorPicker {
+    swatches: Vec<Color>,
+    current: usize,
+    draw: Drawable,
+    hovering: Option<usize>,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+impl ColorPicker {
+    /// If the default color isn't one of the presets, it's added as the first swatch.
+    pub fn new<I: Into<String>>(ctx: &EventCtx, label: I, default: Color) -> Widget {
+        let mut swatches = vec![
+            Color::BLACK,
+            Color::grey(0.5),
+            Color::WHITE,
+            Color::RED,
+            Color::ORANGE,
+            Color::YELLOW,
+            Color::GREEN,
+            Color::CYAN,
+            Color::BLUE,
+            Color::PURPLE,
+            Color::PINK,
+            Color::hex("#8B4513"),
+        ];
+        let current = if let Some(idx) = swatches.iter().position(|c| *c == default) {
+            idx
+        } else {
+            swatches.insert(0, default);
+            0
+        };
+
+        let cols = swatches.len().min(SWATCHES_PER_ROW) as f64;
+        let rows = ((swatches.len() + SWATCHES_PER_ROW - 1) / SWATCHES_PER_ROW) as f64;
+        let dims = ScreenDims::new(
+            cols * SWATCH_SIZE + (cols - 1.0) * SWATCH_PADDING,
+            rows * SWATCH_SIZE + (rows - 1.0) * SWATCH_PADDING,
+        );
+
+        Widget::new(Box::new(ColorPicker {
+            draw: render(ctx, &swatches, current),
+            swatches,
+            current,
+            hovering: None,
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims,
+        }))
+        .named(label)
+    }
+
+    pub fn current_color(&self) -> Color {
+        self.swatches[self.current]
+    }
+}
+
+/// Relative to the top-left of the grid
+fn swatch_geom(idx: usize) -> Polygon {
+    let row = (idx / SWATCHES_PER_ROW) as f64;
+    let col = (idx % SWATCHES_PER_ROW) as f64;
+    Polygon::rectangle(SWATCH_SIZE, SWATCH_SIZE).translate(
+        col * (SWATCH_SIZE + SWATCH_PADDING),
+        row * (SWATCH_SIZE + SWATCH_PADDING),
+    )
+}
+
+fn render(ctx: &EventCtx, swatches: &[Color], current: usize) -> Drawable {
+    let mut batch = GeomBatch::new();
+    for (idx, color) in swatches.iter().enumerate() {
+        batch.push(*color, swatch_geom(idx));
+    }
+    // Keep the border inside the swatch
+    let inset = SELECTED_THICKNESS / 2.0;
+    let bounds = swatch_geom(current).get_bounds();
+    batch.push(
+        ctx.style().outline_color,
+        Polygon::rectangle(
+            SWATCH_SIZE - SELECTED_THICKNESS,
+            SWATCH_SIZE - SELECTED_THICKNESS,
+        )
+        .translate(bounds.min_x + inset, bounds.min_y + inset)
+        .to_outline(Distance::meters(SELECTED_THICKNESS))
+        .unwrap(),
+    );
+    ctx.upload(batch)
+}
+
+impl WidgetImpl for ColorPicker {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if ctx.redo_mouseover() {
+            self.hovering = None;
+            if let Some(cursor) = ctx.canvas.get_cursor_in_screen_space() {
+                if ScreenRectangle::top_left(self.top_left, self.dims).contains(cursor) {
+                    let pt = ScreenPt::new(cursor.x - self.top_left.x, cursor.y - self.top_left.y);
+                    self.hovering = (0..self.swatches.len())
+                        .find(|idx| swatch_geom(*idx).contains_pt(pt.to_pt()));
+                }
+            }
+        }
+
+        if let Some(idx) = self.hovering {
+            ctx.cursor_clickable();
+            if ctx.normal_left_click() && idx != self.current {
+                self.current = idx;
+                self.draw = render(ctx, &self.swatches, self.current);
+                output.outcome = Outcome::Changed;
+            }
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        g.redraw_at(self.top_left, &self.draw);
+        if let Some(idx) = self.hovering {
+            let draw = g.upload(GeomBatch::from(vec![(
+                Color::WHITE.alpha(0.3),
+                swatch_geom(idx),
+            )]));
+            g.redraw_at(self.top_left, &draw);
+        }
+    }
+}
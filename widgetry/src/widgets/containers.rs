@@ -1,4 +1,9 @@
-use crate::{EventCtx, GfxCtx, Outcome, ScreenDims, ScreenPt, Widget, WidgetImpl, WidgetOutput};
+use geom::Angle;
+
+use crate::{
+    Btn, Checkbox, EventCtx, GeomBatch, GfxCtx, Outcome, RewriteColor, ScreenDims, ScreenPt, Text,
+    Widget, WidgetImpl, WidgetOutput,
+};
 
 pub struct Nothing {}
 
@@ -23,13 +28,75 @@ pub struct Container {
     // false means column
     pub is_row: bool,
     pub members: Vec<Widget>,
+    /// The first member is a Checkbox header, and the rest are only shown while it's checked
+    pub(crate) collapsible: bool,
 }
 
 impl Container {
     pub fn new(is_row: bool, mut members: Vec<Widget>) -> Container {
         members.retain(|w| !w.widget.is::<Nothing>());
-        Container { is_row, members }
+        Container {
+            is_row,
+            members,
+            collapsible: false,
+        }
     }
+
+    /// The header's text is used as the name of the section.
+    pub(crate) fn collapsible(
+        ctx: &EventCtx,
+        header: Text,
+        body: Widget,
+        expanded: bool,
+    ) -> Container {
+        let label = header.to_plaintext();
+        let header = Checkbox::new(
+            expanded,
+            collapsible_header(ctx, &label, header.clone(), false),
+            collapsible_header(ctx, &label, header, true),
+        )
+        .named(label)
+        .margin_below(10);
+        let mut container = Container::new(false, vec![header, body]);
+        container.collapsible = true;
+        container
+    }
+
+    /// Only these members take part in layout, events, and drawing. The rest belong to a collapsed
+    /// section.
+    pub(crate) fn num_visible(&self) -> usize {
+        if self.collapsible
+            && !self.members[0]
+                .widget
+                .downcast_ref::<Checkbox>()
+                .unwrap()
+                .enabled
+        {
+            1
+        } else {
+            self.members.len()
+        }
+    }
+}
+
+fn collapsible_header(ctx: &EventCtx, label: &str, header: Text, expanded: bool) -> Widget {
+    let chevron = GeomBatch::load_svg(ctx.prerender, "system/assets/widgetry/arrow_drop_down.svg")
+        .color(RewriteColor::ChangeAll(ctx.style().outline_color))
+        .autocrop();
+    let chevron = if expanded {
+        chevron
+    } else {
+        chevron.rotate_around_batch_center(Angle::new_degs(-90.0))
+    };
+    let (normal, hitbox) = Widget::row(vec![
+        chevron.batch().centered_vert(),
+        header.render(ctx).batch(),
+    ])
+    .to_geom(ctx, None);
+    let hovered = normal
+        .clone()
+        .color(RewriteColor::ChangeAll(ctx.style().hovering_color));
+    Btn::custom(normal, hovered, hitbox, None).build(ctx, label, None)
 }
 
 impl WidgetImpl for Container {
@@ -47,16 +114,25 @@ impl WidgetImpl for Container {
     }
 
     fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
-        for w in &mut self.members {
+        let num_visible = self.num_visible();
+        for (idx, w) in self.members[..num_visible].iter_mut().enumerate() {
             w.widget.event(ctx, output);
             if output.outcome != Outcome::Nothing {
+                // Expanding or collapsing a section is handled here; the Checkbox already asked
+                // for a new layout.
+                if self.collapsible && idx == 0 {
+                    output.outcome = Outcome::Nothing;
+                }
                 return;
             }
         }
     }
 
     fn draw(&self, g: &mut GfxCtx) {
-        for w in &self.members {
+        let mut members: Vec<&Widget> = self.members[..self.num_visible()].iter().collect();
+        // The sort is stable, so ties keep their original order
+        members.sort_by_key(|w| w.layout.z_index);
+        for w in members {
             w.draw(g);
         }
     }
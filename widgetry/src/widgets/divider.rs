@@ -0,0 +1,97 @@
+use geom::Polygon;
+
+use crate::{
+    Color, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenPt, Widget, WidgetImpl, WidgetOutput,
+};
+
+/// A thin line separating sections of a panel. A horizontal divider stretches to the width of the
+/// column containing it, and a vertical divider to the height of its row.
+pub struct Divider {
+    horizontal: bool,
+    thickness: f64,
+    color: Option<Color>,
+    /// Filled in by the layout
+    length: f64,
+
+    top_left: ScreenPt,
+}
+
+impl Divider {
+    pub fn horizontal() -> Divider {
+        Divider::new(true)
+    }
+
+    pub fn vertical() -> Divider {
+        Divider::new(false)
+    }
+
+    fn new(horizontal: bool) -> Divider {
+        Divider {
+            horizontal,
+            thickness: 2.0,
+            color: None,
+            length: 0.0,
+
+            top_left: ScreenPt::new(0.0, 0.0),
+        }
+    }
+
+    /// In pixels. Defaults to 2.
+    pub fn thickness(mut self, thickness: f64) -> Divider {
+        self.thickness = thickness;
+        self
+    }
+
+    /// Defaults to the style's outline color.
+    pub fn color(mut self, color: Color) -> Divider {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn build(mut self, ctx: &EventCtx) -> Widget {
+        if self.color.is_none() {
+            self.color = Some(ctx.style().outline_color);
+        }
+        Widget::new(Box::new(self))
+    }
+
+    pub(crate) fn is_horizontal(&self) -> bool {
+        self.horizontal
+    }
+
+    pub(crate) fn thickness_px(&self) -> f64 {
+        self.thickness
+    }
+
+    pub(crate) fn set_length(&mut self, length: f64) {
+        self.length = length;
+    }
+}
+
+impl WidgetImpl for Divider {
+    fn get_dims(&self) -> ScreenDims {
+        if self.horizontal {
+            ScreenDims::new(self.length, self.thickness)
+        } else {
+            ScreenDims::new(self.thickness, self.length)
+        }
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, _: &mut EventCtx, _: &mut WidgetOutput) {}
+
+    fn draw(&self, g: &mut GfxCtx) {
+        let dims = self.get_dims();
+        if dims.width == 0.0 || dims.height == 0.0 {
+            return;
+        }
+        let draw = g.upload(GeomBatch::from(vec![(
+            self.color.unwrap(),
+            Polygon::rectangle(dims.width, dims.height),
+        )]));
+        g.redraw_at(self.top_left, &draw);
+    }
+}
@@ -1,8 +1,6 @@
-use geom::{Distance, Polygon, Pt2D};
-
 use crate::{
-    Btn, Button, Choice, Color, EventCtx, GeomBatch, GfxCtx, Menu, Outcome, ScreenDims, ScreenPt,
-    ScreenRectangle, WidgetImpl, WidgetOutput,
+    Btn, Button, Choice, EventCtx, GfxCtx, Menu, Outcome, ScreenDims, ScreenPt, ScreenRectangle,
+    WidgetImpl, WidgetOutput,
 };
 
 pub struct Dropdown<T: Clone> {
@@ -56,7 +54,6 @@ impl<T: 'static + PartialEq + Clone> Dropdown<T> {
 
 impl<T: 'static + Clone> Dropdown<T> {
     fn open_menu(&mut self, ctx: &mut EventCtx) {
-        // TODO set current idx in menu
         let mut menu = Menu::new(
             ctx,
             self.choices
@@ -66,6 +63,8 @@ impl<T: 'static + Clone> Dropdown<T> {
                 .collect(),
         )
         .take_menu();
+        // Start with the current choice highlighted
+        menu.current_idx = self.current_idx;
         let y1_below = self.btn.top_left.y + self.btn.dims.height + 15.0;
 
         menu.set_pos(ScreenPt::new(
@@ -130,33 +129,7 @@ impl<T: 'static + Clone> WidgetImpl for Dropdown<T> {
     fn draw(&self, g: &mut GfxCtx) {
         self.btn.draw(g);
         if let Some(ref m) = self.menu {
-            // We need a background too! Add some padding and an outline.
-            // TODO Little embedded Panel could make more sense?
-            let pad = 5.0;
-            let width = m.get_dims().width + 2.0 * pad;
-            let height = m.get_dims().height + 2.0 * pad;
-            let rect = Polygon::rounded_rectangle(width, height, Some(5.0));
-            let draw_bg = g.upload(GeomBatch::from(vec![
-                (Color::grey(0.3), rect.clone()),
-                (
-                    Color::WHITE,
-                    rect.to_outline(Distance::meters(3.0)).unwrap(),
-                ),
-            ]));
-            g.fork(
-                Pt2D::new(0.0, 0.0),
-                ScreenPt::new(m.top_left.x - pad, m.top_left.y - pad),
-                1.0,
-                Some(crate::drawing::MENU_Z),
-            );
-            g.redraw(&draw_bg);
-            g.unfork();
-
-            m.draw(g);
-
-            // Dropdown menus often leak out of their Panel
-            g.canvas
-                .mark_covered_area(ScreenRectangle::top_left(m.top_left, m.get_dims()));
+            m.draw_popup(g);
         }
     }
 
@@ -1,4 +1,8 @@
-use crate::{EventCtx, GfxCtx, ScreenDims, ScreenPt, Widget, WidgetImpl, WidgetOutput};
+use geom::{Distance, Polygon};
+
+use crate::{
+    Color, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenPt, Widget, WidgetImpl, WidgetOutput,
+};
 
 // Doesn't do anything by itself, just used for widgetsing. Something else reaches in, asks for the
 // ScreenRectangle to use.
@@ -7,6 +11,7 @@ pub struct Filler {
     dims: ScreenDims,
 
     square_width_pct: f64,
+    debug_border: Option<Color>,
 }
 
 impl Filler {
@@ -19,8 +24,16 @@ impl Filler {
             ),
             top_left: ScreenPt::new(0.0, 0.0),
             square_width_pct: pct_width,
+            debug_border: None,
         }))
     }
+
+    /// Outlines the filler, to see where it lands in the layout. Only meant for development. Panics
+    /// if the widget isn't a Filler.
+    pub fn with_debug_border(mut widget: Widget, color: Color) -> Widget {
+        widget.widget.downcast_mut::<Filler>().unwrap().debug_border = Some(color);
+        widget
+    }
 }
 
 impl WidgetImpl for Filler {
@@ -40,5 +53,14 @@ impl WidgetImpl for Filler {
             );
         }
     }
-    fn draw(&self, _g: &mut GfxCtx) {}
+    fn draw(&self, g: &mut GfxCtx) {
+        if let Some(color) = self.debug_border {
+            if let Ok(outline) = Polygon::rectangle(self.dims.width, self.dims.height)
+                .to_outline(Distance::meters(2.0))
+            {
+                let draw = g.upload(GeomBatch::from(vec![(color, outline)]));
+                g.redraw_at(self.top_left, &draw);
+            }
+        }
+    }
 }
@@ -0,0 +1,141 @@
+use abstutil::prettyprint_usize;
+use geom::{Duration, Polygon};
+
+use crate::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, Outcome, ScreenDims, ScreenPt,
+    ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
+};
+
+/// Groups durations into evenly sized buckets and draws a bar for each. Clicking a bar selects
+/// that bucket, so callers can filter to the range.
+pub struct Histogram {
+    draw: Drawable,
+
+    /// (start, end, count) of each bucket
+    buckets: Vec<(Duration, Duration, usize)>,
+    bars: Vec<Polygon>,
+    hovering: Option<usize>,
+    clicked: Option<usize>,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+impl Histogram {
+    pub fn new(ctx: &EventCtx, color: Color, data: Vec<Duration>, num_buckets: usize) -> Widget {
+        assert!(num_buckets > 0);
+        if data.is_empty() {
+            return Widget::nothing();
+        }
+
+        let min = *data.iter().min().unwrap();
+        let max = *data.iter().max().unwrap();
+        // If every value is the same, everything winds up in the first bucket.
+        let bucket_size = (max - min) / (num_buckets as f64);
+        let mut buckets: Vec<(Duration, Duration, usize)> = (0..num_buckets)
+            .map(|idx| {
+                (
+                    min + bucket_size * (idx as f64),
+                    min + bucket_size * ((idx + 1) as f64),
+                    0,
+                )
+            })
+            .collect();
+        for x in data {
+            let idx = if bucket_size == Duration::ZERO {
+                0
+            } else {
+                (((x - min) / bucket_size) as usize).min(num_buckets - 1)
+            };
+            buckets[idx].2 += 1;
+        }
+        let max_count = buckets.iter().map(|(_, _, cnt)| *cnt).max().unwrap();
+
+        let width = 0.3 * ctx.canvas.window_width;
+        let height = 0.15 * ctx.canvas.window_height;
+        let bar_width = width / (num_buckets as f64);
+
+        let mut batch = GeomBatch::new();
+        batch.autocrop_dims = false;
+        batch.push(Color::grey(0.3), Polygon::rectangle(width, height));
+        let mut bars = Vec::new();
+        for (idx, (_, _, cnt)) in buckets.iter().enumerate() {
+            let bar_height = (*cnt as f64) / (max_count as f64) * height;
+            // Always make the bar hoverable, even if it's empty
+            let bar = Polygon::rectangle(bar_width, bar_height.max(1.0))
+                .translate((idx as f64) * bar_width, height - bar_height.max(1.0));
+            if *cnt > 0 {
+                batch.push(color, bar.clone());
+            }
+            bars.push(bar);
+        }
+
+        Widget::new(Box::new(Histogram {
+            draw: ctx.upload(batch),
+            buckets,
+            bars,
+            hovering: None,
+            clicked: None,
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims: ScreenDims::new(width, height),
+        }))
+    }
+
+    /// The (start, end) range of the bucket most recently clicked. An Outcome::Changed is returned
+    /// when this happens.
+    pub fn clicked_bucket(&self) -> Option<(Duration, Duration)> {
+        let (start, end, _) = self.buckets[self.clicked?];
+        Some((start, end))
+    }
+}
+
+impl WidgetImpl for Histogram {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if ctx.redo_mouseover() {
+            self.hovering = None;
+            if let Some(cursor) = ctx.canvas.get_cursor_in_screen_space() {
+                let rect = ScreenRectangle::top_left(self.top_left, self.dims);
+                if let Some((pct_x, _)) = rect.pt_to_percent(cursor) {
+                    let n = self.buckets.len();
+                    self.hovering = Some(((pct_x * (n as f64)) as usize).min(n - 1));
+                }
+            }
+        }
+
+        if let Some(idx) = self.hovering {
+            if ctx.normal_left_click() {
+                self.clicked = Some(idx);
+                output.outcome = Outcome::Changed;
+            }
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        g.redraw_at(self.top_left, &self.draw);
+
+        if let Some(idx) = self.hovering {
+            let draw = g.upload(GeomBatch::from(vec![(
+                Color::WHITE.alpha(0.5),
+                self.bars[idx].clone(),
+            )]));
+            g.redraw_at(self.top_left, &draw);
+
+            let (start, end, cnt) = self.buckets[idx];
+            g.draw_mouse_tooltip(Text::from(Line(format!(
+                "{} - {}: {}",
+                start,
+                end,
+                prettyprint_usize(cnt)
+            ))));
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use geom::Polygon;
 
 use crate::{
-    svg, Drawable, EventCtx, GeomBatch, GfxCtx, RewriteColor, ScreenDims, ScreenPt,
+    svg, Color, Drawable, EventCtx, GeomBatch, GfxCtx, RewriteColor, ScreenDims, ScreenPt,
     ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
 };
 
@@ -43,6 +43,82 @@ impl JustDraw {
     }
 }
 
+/// Where to find a raster image, like a PNG
+pub enum ImageSource {
+    /// Relative to the data directory, like SVG filenames
+    Path(String),
+    Bytes(Vec<u8>),
+}
+
+impl JustDraw {
+    /// Stretches the image to fill dims.
+    pub fn image(
+        ctx: &EventCtx,
+        source: ImageSource,
+        dims: ScreenDims,
+        rewrite: RewriteColor,
+    ) -> Widget {
+        let raw = match source {
+            ImageSource::Path(filename) => {
+                let path = abstutil::path(filename);
+                if let Ok(raw) = abstutil::slurp_file(&path) {
+                    raw
+                } else {
+                    panic!("Can't read {}", path);
+                }
+            }
+            ImageSource::Bytes(raw) => raw,
+        };
+        let batch = match rasterize(&raw, dims) {
+            Ok(batch) => batch.color(rewrite),
+            Err(err) => panic!("Can't load image: {}", err),
+        };
+        Widget::new(Box::new(JustDraw {
+            dims,
+            draw: ctx.upload(batch),
+            top_left: ScreenPt::new(0.0, 0.0),
+        }))
+    }
+}
+
+/// The renderer only knows about the built-in texture spritesheet, so images are turned into plain
+/// geometry: one rectangle per run of identical pixels in each row, skipping fully transparent
+/// ones. That's fine for icons and small screenshots, but large photos produce lots of polygons.
+fn rasterize(raw: &[u8], dims: ScreenDims) -> Result<GeomBatch, String> {
+    let img = image::load_from_memory(raw)
+        .map_err(|err| err.to_string())?
+        .into_rgba();
+    let (width, height) = img.dimensions();
+    let px_width = dims.width / (width as f64);
+    let px_height = dims.height / (height as f64);
+
+    let mut batch = GeomBatch::new();
+    for y in 0..height {
+        let mut x = 0;
+        while x < width {
+            let rgba = img.get_pixel(x, y).0;
+            let start = x;
+            while x < width && img.get_pixel(x, y).0 == rgba {
+                x += 1;
+            }
+            if rgba[3] == 0 {
+                continue;
+            }
+            batch.push(
+                Color::rgba(
+                    rgba[0] as usize,
+                    rgba[1] as usize,
+                    rgba[2] as usize,
+                    (rgba[3] as f32) / 255.0,
+                ),
+                Polygon::rectangle(((x - start) as f64) * px_width, px_height)
+                    .translate((start as f64) * px_width, (y as f64) * px_height),
+            );
+        }
+    }
+    Ok(batch)
+}
+
 impl WidgetImpl for JustDraw {
     fn get_dims(&self) -> ScreenDims {
         self.dims
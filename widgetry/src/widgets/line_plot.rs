@@ -10,6 +10,8 @@ use crate::{
     ScreenRectangle, Text, TextExt, Widget, WidgetImpl, WidgetOutput,
 };
 
+const THRESHOLD_COLORS: [Color; 4] = [Color::RED, Color::ORANGE, Color::CYAN, Color::PINK];
+
 // The X is always time
 pub struct LinePlot<T: Yvalue<T>> {
     draw: Drawable,
@@ -18,6 +20,9 @@ pub struct LinePlot<T: Yvalue<T>> {
     max_x: Time,
     max_y: T,
     closest: FindClosest<String>,
+    y_axis: YAxis,
+    // The series label and the point on it closest to the cursor
+    hovering: Option<(String, Pt2D)>,
 
     top_left: ScreenPt,
     dims: ScreenDims,
@@ -28,6 +33,10 @@ pub struct PlotOptions<T: Yvalue<T>> {
     pub max_x: Option<Time>,
     pub max_y: Option<T>,
     pub disabled: HashSet<String>,
+    /// Use a logarithmic Y axis, starting at 1. Only LinePlot supports this.
+    pub log_y: bool,
+    /// Labeled horizontal reference lines. Only LinePlot supports this.
+    pub thresholds: Vec<(String, T)>,
 }
 
 impl<T: Yvalue<T>> PlotOptions<T> {
@@ -37,6 +46,8 @@ impl<T: Yvalue<T>> PlotOptions<T> {
             max_x: None,
             max_y: None,
             disabled: HashSet::new(),
+            log_y: false,
+            thresholds: Vec::new(),
         }
     }
 
@@ -46,8 +57,23 @@ impl<T: Yvalue<T>> PlotOptions<T> {
             max_x: None,
             max_y: None,
             disabled: HashSet::new(),
+            log_y: false,
+            thresholds: Vec::new(),
         }
     }
+
+    /// Values spanning a few orders of magnitude are easier to see on a logarithmic Y axis.
+    pub fn log_y(mut self, log_y: bool) -> PlotOptions<T> {
+        self.log_y = log_y;
+        self
+    }
+
+    /// Draw a dashed horizontal line at this value, like a target to stay under. Each threshold
+    /// gets its own color. Values beyond the top of the plot aren't drawn.
+    pub fn threshold<I: Into<String>>(mut self, label: I, value: T) -> PlotOptions<T> {
+        self.thresholds.push((label.into(), value));
+        self
+    }
 }
 
 impl<T: Yvalue<T>> LinePlot<T> {
@@ -87,13 +113,32 @@ impl<T: Yvalue<T>> LinePlot<T> {
         // container.
         let width = 0.23 * ctx.canvas.window_width;
         let height = 0.2 * ctx.canvas.window_height;
+        let y_axis = if opts.log_y {
+            YAxis::Log {
+                max_exponent: max_y.to_f64().max(10.0).log10().ceil(),
+            }
+        } else {
+            YAxis::Linear
+        };
 
         let mut batch = GeomBatch::new();
-        // Grid lines for the Y scale. Draw up to 10 lines max to cover the order of magnitude of
-        // the range.
+        // Grid lines for the Y scale. On a logarithmic axis, there's one per power of ten.
+        // Otherwise, draw up to 10 lines max to cover the order of magnitude of the range.
         // TODO This caps correctly, but if the max is 105, then suddenly we just have 2 grid
         // lines.
-        {
+        if let YAxis::Log { max_exponent } = y_axis {
+            for i in 0..=(max_exponent as usize) {
+                let pct = (i as f64) / max_exponent;
+                batch.push(
+                    Color::hex("#7C7C7C"),
+                    PolyLine::must_new(vec![
+                        Pt2D::new(0.0, (1.0 - pct) * height),
+                        Pt2D::new(width, (1.0 - pct) * height),
+                    ])
+                    .make_polygons(Distance::meters(1.0)),
+                );
+            }
+        } else {
             let order_of_mag = 10.0_f64.powf(max_y.to_f64().log10().ceil());
             for i in 0..10 {
                 let y = max_y.from_f64(order_of_mag / 10.0 * (i as f64));
@@ -143,7 +188,7 @@ impl<T: Yvalue<T>> LinePlot<T> {
             let mut pts = Vec::new();
             for (t, y) in s.pts {
                 let percent_x = t.to_percent(max_x);
-                let percent_y = y.to_percent(max_y);
+                let percent_y = y_axis.to_percent(y, max_y);
                 pts.push(Pt2D::new(
                     percent_x * width,
                     // Y inversion! :D
@@ -157,11 +202,40 @@ impl<T: Yvalue<T>> LinePlot<T> {
             }
         }
 
+        for (idx, (label, value)) in opts.thresholds.iter().enumerate() {
+            let pct = y_axis.to_percent(*value, max_y);
+            if pct > 1.0 {
+                continue;
+            }
+            let color = THRESHOLD_COLORS[idx % THRESHOLD_COLORS.len()];
+            let y = (1.0 - pct) * height;
+            let line = PolyLine::must_new(vec![Pt2D::new(0.0, y), Pt2D::new(width, y)]);
+            batch.extend(
+                color,
+                line.dashed_lines(
+                    Distance::meters(2.0),
+                    Distance::meters(8.0),
+                    Distance::meters(4.0),
+                ),
+            );
+            let txt = Text::from(Line(label).small().fg(color)).render(ctx);
+            // Above the line, unless that'd leave the plot
+            let txt_height = txt.get_dims().height;
+            let txt_y = if y - txt_height >= 0.0 {
+                y - txt_height
+            } else {
+                y
+            };
+            batch.append(txt.translate(4.0, txt_y));
+        }
+
         let plot = LinePlot {
             draw: ctx.upload(batch),
             closest,
+            hovering: None,
             max_x,
             max_y,
+            y_axis,
 
             top_left: ScreenPt::new(0.0, 0.0),
             dims: ScreenDims::new(width, height),
@@ -182,11 +256,20 @@ impl<T: Yvalue<T>> LinePlot<T> {
         }
         let x_axis = Widget::custom_row(row).padding(10).evenly_spaced();
 
-        let num_y_labels = 4;
+        let num_y_labels = match y_axis {
+            YAxis::Linear => 4,
+            // Label each power of ten
+            YAxis::Log { max_exponent } => (max_exponent as usize) + 1,
+        };
         let mut col = Vec::new();
         for i in 0..num_y_labels {
             let percent_y = (i as f64) / ((num_y_labels - 1) as f64);
-            col.push(max_y.from_percent(percent_y).prettyprint().draw_text(ctx));
+            col.push(
+                y_axis
+                    .from_percent(percent_y, max_y)
+                    .prettyprint()
+                    .draw_text(ctx),
+            );
         }
         col.reverse();
         let y_axis = Widget::custom_col(col).padding(10).evenly_spaced();
@@ -210,37 +293,76 @@ impl<T: Yvalue<T>> WidgetImpl for LinePlot<T> {
         self.top_left = top_left;
     }
 
-    fn event(&mut self, _: &mut EventCtx, _: &mut WidgetOutput) {}
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut WidgetOutput) {
+        if ctx.redo_mouseover() {
+            self.hovering = None;
+            if let Some(cursor) = ctx.canvas.get_cursor_in_screen_space() {
+                if ScreenRectangle::top_left(self.top_left, self.dims).contains(cursor) {
+                    // Pick the closest point across all series
+                    self.hovering = self.closest.closest_pt(
+                        Pt2D::new(cursor.x - self.top_left.x, cursor.y - self.top_left.y),
+                        Distance::meters(15.0),
+                    );
+                }
+            }
+        }
+    }
 
     fn draw(&self, g: &mut GfxCtx) {
         g.redraw_at(self.top_left, &self.draw);
 
-        if let Some(cursor) = g.canvas.get_cursor_in_screen_space() {
-            if ScreenRectangle::top_left(self.top_left, self.dims).contains(cursor) {
-                let radius = Distance::meters(15.0);
-                let mut txt = Text::new();
-                for (label, pt, _) in self.closest.all_close_pts(
-                    Pt2D::new(cursor.x - self.top_left.x, cursor.y - self.top_left.y),
-                    radius,
-                ) {
-                    // TODO If some/all of the matches have the same t, write it once?
-                    let t = self.max_x.percent_of(pt.x() / self.dims.width);
-                    let y_percent = 1.0 - (pt.y() / self.dims.height);
-
-                    // TODO Draw this info in the ColorLegend
-                    txt.add(Line(format!(
-                        "{}: at {}, {}",
-                        label,
-                        t.ampm_tostring(),
-                        self.max_y.from_percent(y_percent).prettyprint()
-                    )));
-                }
-                if !txt.is_empty() {
-                    g.fork_screenspace();
-                    g.draw_polygon(Color::RED, Circle::new(cursor.to_pt(), radius).to_polygon());
-                    g.draw_mouse_tooltip(txt);
-                    g.unfork();
-                }
+        if let Some((ref label, pt)) = self.hovering {
+            let t = self.max_x.percent_of(pt.x() / self.dims.width);
+            let y_percent = 1.0 - (pt.y() / self.dims.height);
+
+            // TODO Draw this info in the ColorLegend
+            let txt = Text::from(Line(format!(
+                "{}: at {}, {}",
+                label,
+                t.ampm_tostring(),
+                self.y_axis
+                    .from_percent(y_percent, self.max_y)
+                    .prettyprint()
+            )));
+            g.fork_screenspace();
+            g.draw_polygon(
+                Color::RED,
+                Circle::new(
+                    Pt2D::new(self.top_left.x + pt.x(), self.top_left.y + pt.y()),
+                    Distance::meters(5.0),
+                )
+                .to_polygon(),
+            );
+            g.draw_mouse_tooltip(txt);
+            g.unfork();
+        }
+    }
+}
+
+/// How values map onto the Y axis
+#[derive(Clone, Copy)]
+enum YAxis {
+    Linear,
+    /// From 1 to 10^max_exponent. Anything below 1, including zero, is drawn at the bottom.
+    Log {
+        max_exponent: f64,
+    },
+}
+
+impl YAxis {
+    fn to_percent<T: Yvalue<T>>(self, y: T, max_y: T) -> f64 {
+        match self {
+            YAxis::Linear => y.to_percent(max_y),
+            YAxis::Log { max_exponent } => y.to_f64().max(1.0).log10() / max_exponent,
+        }
+    }
+
+    fn from_percent<T: Yvalue<T>>(self, percent: f64, max_y: T) -> T {
+        match self {
+            YAxis::Linear => max_y.from_percent(percent),
+            // Round, so powers of ten come out exactly
+            YAxis::Log { max_exponent } => {
+                max_y.from_f64(10.0_f64.powf(percent * max_exponent).round())
             }
         }
     }
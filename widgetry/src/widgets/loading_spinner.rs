@@ -0,0 +1,81 @@
+use geom::{Angle, Distance, PolyLine, Pt2D};
+
+use crate::{
+    Drawable, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenPt, UpdateType, Widget, WidgetImpl,
+    WidgetOutput,
+};
+
+/// How fast the arc spins
+const DEGREES_PER_SECOND: f64 = 360.0;
+/// How much of the circle the arc covers
+const ARC_DEGREES: f64 = 270.0;
+const ARC_STEPS: usize = 30;
+
+/// An arc that spins forever, to show that something is happening in the background.
+///
+/// The angle only advances on `Event::Update`, so the owning screen has to keep calling
+/// `Panel::event` on every event, including updates. The spinner requests `UpdateType::Game`
+/// itself to keep those events and redraws coming; if the screen stops passing events to the
+/// panel, the spinner freezes.
+pub struct LoadingSpinner {
+    diameter: f64,
+    angle: Angle,
+    draw: Drawable,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+impl LoadingSpinner {
+    pub fn new(ctx: &EventCtx, diameter: f64) -> Widget {
+        let angle = Angle::ZERO;
+        Widget::new(Box::new(LoadingSpinner {
+            diameter,
+            angle,
+            draw: render(ctx, diameter, angle),
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims: ScreenDims::new(diameter, diameter),
+        }))
+    }
+}
+
+fn render(ctx: &EventCtx, diameter: f64, angle: Angle) -> Drawable {
+    let thickness = (diameter / 8.0).max(1.0);
+    let radius = Distance::meters((diameter - thickness) / 2.0);
+    let center = Pt2D::new(diameter / 2.0, diameter / 2.0);
+    let pts = (0..=ARC_STEPS)
+        .map(|i| {
+            let theta = angle.rotate_degs(ARC_DEGREES * (i as f64) / (ARC_STEPS as f64));
+            center.project_away(radius, theta)
+        })
+        .collect();
+    ctx.upload(GeomBatch::from(vec![(
+        ctx.style().outline_color,
+        PolyLine::unchecked_new(pts).make_polygons(Distance::meters(thickness)),
+    )]))
+}
+
+impl WidgetImpl for LoadingSpinner {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut WidgetOutput) {
+        if let Some(dt) = ctx.input.nonblocking_is_update_event() {
+            self.angle = self
+                .angle
+                .rotate_degs(DEGREES_PER_SECOND * dt.inner_seconds());
+            self.draw = render(ctx, self.diameter, self.angle);
+        }
+        ctx.request_update(UpdateType::Game);
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        g.redraw_at(self.top_left, &self.draw);
+    }
+}
@@ -1,13 +1,22 @@
-use geom::Pt2D;
+use std::collections::BTreeSet;
+
+use geom::{Distance, Polygon, Pt2D};
 
 use crate::{
-    text, Choice, EventCtx, GfxCtx, Key, Line, Outcome, ScreenDims, ScreenPt, ScreenRectangle,
-    Style, Text, Widget, WidgetImpl, WidgetOutput,
+    text, Choice, Color, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, ScreenDims, ScreenPt,
+    ScreenRectangle, Style, Text, Widget, WidgetImpl, WidgetOutput,
 };
 
+/// A list of choices. Typing filters the choices by label.
 pub struct Menu<T> {
     choices: Vec<Choice<T>>,
-    current_idx: usize,
+    pub(crate) current_idx: usize,
+    /// Only for menus where several choices can be picked at once
+    selected: Option<BTreeSet<usize>>,
+    /// Typed so far. Only choices with labels containing this are shown.
+    query: String,
+    /// Indices into choices, in order, matching the query
+    visible: Vec<usize>,
 
     pub(crate) top_left: ScreenPt,
     dims: ScreenDims,
@@ -15,9 +24,24 @@ pub struct Menu<T> {
 
 impl<T: 'static> Menu<T> {
     pub fn new(ctx: &EventCtx, choices: Vec<Choice<T>>) -> Widget {
+        Menu::make(ctx, choices, None)
+    }
+
+    /// Several choices can be picked at once. Picking a choice with the mouse, Enter, or its
+    /// hotkey toggles a checkmark next to it and produces Outcome::Changed, instead of finishing
+    /// the menu. Nothing starts picked. Clicking "Done" at the bottom or pressing Escape produces
+    /// `Outcome::Clicked("Done")`; read the result with `Panel::multi_menu_selection`.
+    pub fn multi(ctx: &EventCtx, choices: Vec<Choice<T>>) -> Widget {
+        Menu::make(ctx, choices, Some(BTreeSet::new()))
+    }
+
+    fn make(ctx: &EventCtx, choices: Vec<Choice<T>>, selected: Option<BTreeSet<usize>>) -> Widget {
         let mut m = Menu {
+            visible: (0..choices.len()).collect(),
             choices,
             current_idx: 0,
+            selected,
+            query: String::new(),
 
             top_left: ScreenPt::new(0.0, 0.0),
             dims: ScreenDims::new(0.0, 0.0),
@@ -26,34 +50,143 @@ impl<T: 'static> Menu<T> {
         Widget::new(Box::new(m))
     }
 
+    /// Draw a menu floating above everything else, with a background. Used when the menu isn't
+    /// laid out in a Panel, like for dropdowns and context menus.
+    pub(crate) fn draw_popup(&self, g: &mut GfxCtx) {
+        // We need a background too! Add some padding and an outline.
+        // TODO Little embedded Panel could make more sense?
+        let pad = 5.0;
+        let width = self.dims.width + 2.0 * pad;
+        let height = self.dims.height + 2.0 * pad;
+        let rect = Polygon::rounded_rectangle(width, height, Some(5.0));
+        let draw_bg = g.upload(GeomBatch::from(vec![
+            (Color::grey(0.3), rect.clone()),
+            (
+                Color::WHITE,
+                rect.to_outline(Distance::meters(3.0)).unwrap(),
+            ),
+        ]));
+        g.fork(
+            Pt2D::new(0.0, 0.0),
+            ScreenPt::new(self.top_left.x - pad, self.top_left.y - pad),
+            1.0,
+            Some(crate::drawing::MENU_Z),
+        );
+        g.redraw(&draw_bg);
+        g.unfork();
+
+        self.draw(g);
+
+        // These often leak out of their Panel
+        g.canvas
+            .mark_covered_area(ScreenRectangle::top_left(self.top_left, self.dims));
+    }
+
     pub fn take_current_choice(&mut self) -> T {
         // TODO Make sure it's marked invalid, like button
-        self.choices.remove(self.current_idx).data
+        let data = self.choices.remove(self.current_idx).data;
+        self.update_visible();
+        data
+    }
+
+    /// A single-select menu is done once the current choice is picked; a multi-select menu toggles
+    /// it.
+    fn pick_current(&mut self, ctx: &EventCtx, output: &mut WidgetOutput) {
+        if let Some(ref mut selected) = self.selected {
+            if !selected.remove(&self.current_idx) {
+                selected.insert(self.current_idx);
+            }
+            self.dims = self.calculate_txt(ctx.style()).dims(&ctx.prerender.assets);
+            output.outcome = Outcome::Changed;
+            output.redo_layout = true;
+        } else {
+            output.outcome = Outcome::Clicked(self.choices[self.current_idx].label.clone());
+        }
+    }
+
+    /// The row of the "Done" entry in a multi-select menu. "No matches" takes up a row too.
+    fn done_row(&self) -> usize {
+        self.visible.len().max(1)
+    }
+
+    /// Recalculate which choices match the query, keeping the current choice if it still matches.
+    fn update_visible(&mut self) {
+        let query = self.query.to_lowercase();
+        self.visible = (0..self.choices.len())
+            .filter(|idx| self.choices[*idx].label.to_lowercase().contains(&query))
+            .collect();
+        if !self.visible.contains(&self.current_idx) {
+            // Prefer the first match that can be picked
+            if let Some(idx) = self
+                .visible
+                .iter()
+                .find(|idx| self.choices[**idx].active)
+                .or_else(|| self.visible.first())
+            {
+                self.current_idx = *idx;
+            }
+        }
+    }
+
+    /// Returns the screen-space hitbox of one visible row.
+    fn row_rect(&self, row: usize, line_height: f64) -> ScreenRectangle {
+        // When filtering, the query is shown on the first line
+        let skip = if self.query.is_empty() { 0 } else { 1 };
+        let y1 = self.top_left.y + line_height * ((row + skip) as f64);
+        ScreenRectangle {
+            x1: self.top_left.x,
+            y1,
+            x2: self.top_left.x + self.dims.width,
+            y2: y1 + line_height,
+        }
     }
 
     fn calculate_txt(&self, style: &Style) -> Text {
         let mut txt = Text::new();
 
-        for (idx, choice) in self.choices.iter().enumerate() {
-            if choice.active {
-                if let Some(ref key) = choice.hotkey {
-                    txt.add_appended(vec![
-                        Line(key.describe()).fg(style.hotkey_color),
-                        Line(format!(" - {}", choice.label)).maybe_fg(choice.fg),
-                    ]);
-                } else {
-                    txt.add(Line(&choice.label).maybe_fg(choice.fg));
-                }
+        if !self.query.is_empty() {
+            txt.add(Line(format!("Filter: {}", self.query)).fg(style.hotkey_color));
+            if self.visible.is_empty() {
+                txt.add(Line("No matches").fg(text::INACTIVE_CHOICE_COLOR));
+            }
+        }
+
+        for idx in self.visible.iter().cloned() {
+            let choice = &self.choices[idx];
+            let mut prefix = match self.selected {
+                Some(ref selected) if selected.contains(&idx) => "[X] ".to_string(),
+                Some(_) => "[ ] ".to_string(),
+                None => String::new(),
+            };
+            let fg = if choice.active {
+                choice.fg
             } else {
-                if let Some(ref key) = choice.hotkey {
-                    txt.add(
-                        Line(format!("{} - {}", key.describe(), choice.label))
-                            .fg(text::INACTIVE_CHOICE_COLOR),
-                    );
+                Some(text::INACTIVE_CHOICE_COLOR)
+            };
+
+            // Pieces of the line, and a different color for the matched part of the label
+            let mut pieces: Vec<(String, Option<Color>)> = Vec::new();
+            if let Some(ref key) = choice.hotkey {
+                if choice.active {
+                    pieces.push((key.describe(), Some(style.hotkey_color)));
+                    prefix = format!(" - {}", prefix);
                 } else {
-                    txt.add(Line(&choice.label).fg(text::INACTIVE_CHOICE_COLOR));
+                    prefix = format!("{} - {}", key.describe(), prefix);
                 }
             }
+            if let Some((before, matched, after)) = split_match(&choice.label, &self.query) {
+                pieces.push((format!("{}{}", prefix, before), fg));
+                pieces.push((matched.to_string(), Some(style.hotkey_color)));
+                pieces.push((after.to_string(), fg));
+            } else {
+                pieces.push((format!("{}{}", prefix, choice.label), fg));
+            }
+            txt.add_appended(
+                pieces
+                    .into_iter()
+                    .map(|(piece, color)| Line(piece).maybe_fg(color))
+                    .collect(),
+            );
             if choice.tooltip.is_some() {
                 // TODO Ideally unicode info symbol, but the fonts don't seem to have it
                 txt.append(Line(" (!)"));
@@ -64,10 +197,27 @@ impl<T: 'static> Menu<T> {
                 txt.highlight_last_line(text::SELECTED_COLOR);
             }
         }
+        if self.selected.is_some() {
+            txt.add(Line("Done").fg(style.hotkey_color));
+        }
         txt
     }
 }
 
+impl<T: 'static + Clone> Menu<T> {
+    /// The data of every picked choice, in the original order. Always empty for a single-select
+    /// menu.
+    pub fn selected_choices(&self) -> Vec<T> {
+        match self.selected {
+            Some(ref selected) => selected
+                .iter()
+                .map(|idx| self.choices[*idx].data.clone())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
 impl<T: 'static> WidgetImpl for Menu<T> {
     fn get_dims(&self) -> ScreenDims {
         self.dims
@@ -85,73 +235,89 @@ impl<T: 'static> WidgetImpl for Menu<T> {
         // Handle the mouse
         if ctx.redo_mouseover() {
             if let Some(cursor) = ctx.canvas.get_cursor_in_screen_space() {
-                let mut top_left = self.top_left;
-                for idx in 0..self.choices.len() {
-                    let rect = ScreenRectangle {
-                        x1: top_left.x,
-                        y1: top_left.y,
-                        x2: top_left.x + self.dims.width,
-                        y2: top_left.y + ctx.default_line_height(),
-                    };
-                    if rect.contains(cursor) && self.choices[idx].active {
-                        self.current_idx = idx;
+                for (row, idx) in self.visible.iter().enumerate() {
+                    if self
+                        .row_rect(row, ctx.default_line_height())
+                        .contains(cursor)
+                        && self.choices[*idx].active
+                    {
+                        self.current_idx = *idx;
                         break;
                     }
-                    top_left.y += ctx.default_line_height();
                 }
             }
         }
-        {
-            let choice = &self.choices[self.current_idx];
-            if ctx.normal_left_click() {
-                // Did we actually click the entry?
-                let mut top_left = self.top_left;
-                top_left.y += ctx.default_line_height() * (self.current_idx as f64);
-                let rect = ScreenRectangle {
-                    x1: top_left.x,
-                    y1: top_left.y,
-                    x2: top_left.x + self.dims.width,
-                    y2: top_left.y + ctx.default_line_height(),
-                };
-                if let Some(pt) = ctx.canvas.get_cursor_in_screen_space() {
-                    if rect.contains(pt) && choice.active {
-                        output.outcome = Outcome::Clicked(choice.label.clone());
-                        return;
-                    }
+        let current_row = self.visible.iter().position(|idx| *idx == self.current_idx);
+        if ctx.normal_left_click() {
+            // Did we actually click the entry?
+            if let (Some(row), Some(pt)) = (current_row, ctx.canvas.get_cursor_in_screen_space()) {
+                if self.row_rect(row, ctx.default_line_height()).contains(pt)
+                    && self.choices[self.current_idx].active
+                {
+                    self.pick_current(ctx, output);
+                    return;
                 }
-                ctx.input.unconsume_event();
             }
+            if let (Some(_), Some(pt)) = (&self.selected, ctx.canvas.get_cursor_in_screen_space()) {
+                if self
+                    .row_rect(self.done_row(), ctx.default_line_height())
+                    .contains(pt)
+                {
+                    output.outcome = Outcome::Clicked("Done".to_string());
+                    return;
+                }
+            }
+            ctx.input.unconsume_event();
         }
 
         // Handle hotkeys
-        for (idx, choice) in self.choices.iter().enumerate() {
-            if !choice.active {
+        for idx in 0..self.choices.len() {
+            if !self.choices[idx].active {
                 continue;
             }
-            if ctx.input.pressed(choice.hotkey.clone()) {
+            if ctx.input.pressed(self.choices[idx].hotkey.clone()) {
                 self.current_idx = idx;
-                output.outcome = Outcome::Clicked(choice.label.clone());
+                self.pick_current(ctx, output);
                 return;
             }
         }
 
+        if self.selected.is_some() && ctx.input.pressed(Key::Escape) {
+            output.outcome = Outcome::Clicked("Done".to_string());
+            return;
+        }
+
         // Handle nav keys
         if ctx.input.pressed(Key::Enter) {
-            let choice = &self.choices[self.current_idx];
-            if choice.active {
-                output.outcome = Outcome::Clicked(choice.label.clone());
-                return;
-            } else {
-                return;
+            if current_row.is_some() && self.choices[self.current_idx].active {
+                self.pick_current(ctx, output);
             }
+            return;
         } else if ctx.input.pressed(Key::UpArrow) {
-            if self.current_idx > 0 {
-                self.current_idx -= 1;
+            if let Some(row) = current_row {
+                if row > 0 {
+                    self.current_idx = self.visible[row - 1];
+                }
             }
         } else if ctx.input.pressed(Key::DownArrow) {
-            if self.current_idx < self.choices.len() - 1 {
-                self.current_idx += 1;
+            if let Some(row) = current_row {
+                if row < self.visible.len() - 1 {
+                    self.current_idx = self.visible[row + 1];
+                }
             }
+        } else if let Some(key) = ctx.input.any_pressed() {
+            // Typing filters the choices
+            if key == Key::Backspace {
+                self.query.pop();
+            } else if let Some(c) = key.to_char(ctx.canvas.lshift_held) {
+                self.query.push(c);
+            } else {
+                ctx.input.unconsume_event();
+                return;
+            }
+            self.update_visible();
+            self.dims = self.calculate_txt(ctx.style()).dims(&ctx.prerender.assets);
+            output.redo_layout = true;
         }
     }
 
@@ -170,16 +336,11 @@ impl<T: 'static> WidgetImpl for Menu<T> {
         g.redraw(&draw);
         g.unfork();
 
-        if let Some(ref info) = self.choices[self.current_idx].tooltip {
+        let current_row = self.visible.iter().position(|idx| *idx == self.current_idx);
+        let tooltip = &self.choices[self.current_idx].tooltip;
+        if let (Some(row), Some(info)) = (current_row, tooltip) {
             // Hold on, are we actually hovering on that entry right now?
-            let mut top_left = self.top_left;
-            top_left.y += g.default_line_height() * (self.current_idx as f64);
-            let rect = ScreenRectangle {
-                x1: top_left.x,
-                y1: top_left.y,
-                x2: top_left.x + self.dims.width,
-                y2: top_left.y + g.default_line_height(),
-            };
+            let rect = self.row_rect(row, g.default_line_height());
             if let Some(pt) = g.canvas.get_cursor_in_screen_space() {
                 if rect.contains(pt) {
                     g.draw_mouse_tooltip(
@@ -191,3 +352,43 @@ impl<T: 'static> WidgetImpl for Menu<T> {
         }
     }
 }
+
+/// Splits a label into the parts before, matching, and after the first case-insensitive match of
+/// the query. Returns None for an empty query.
+fn split_match<'a>(label: &'a str, query: &str) -> Option<(&'a str, &'a str, &'a str)> {
+    if query.is_empty() {
+        return None;
+    }
+    let lower = label.to_lowercase();
+    // Lowercasing some characters changes their length, so the match can't be found in the
+    // original label
+    if lower.len() != label.len() {
+        return None;
+    }
+    let query = query.to_lowercase();
+    let start = lower.find(&query)?;
+    let end = start + query.len();
+    if !label.is_char_boundary(start) || !label.is_char_boundary(end) {
+        return None;
+    }
+    Some((&label[..start], &label[start..end], &label[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_match() {
+        assert_eq!(
+            split_match("Jump to time", "TO"),
+            Some(("Jump ", "to", " time"))
+        );
+        assert_eq!(
+            split_match("Jump to time", "jump"),
+            Some(("", "Jump", " to time"))
+        );
+        assert_eq!(split_match("Jump to time", "delay"), None);
+        assert_eq!(split_match("Jump to time", ""), None);
+    }
+}
@@ -1,37 +1,45 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use stretch::geometry::{Rect, Size};
 use stretch::node::{Node, Stretch};
 use stretch::number::Number;
 use stretch::style::{
-    AlignItems, Dimension, FlexDirection, FlexWrap, JustifyContent, PositionType, Style,
+    AlignItems, AlignSelf, Dimension, FlexDirection, FlexWrap, JustifyContent, PositionType, Style,
 };
 
-use geom::{Distance, Percent, Polygon};
+use geom::{Angle, Distance, Line, Percent, Polygon, Pt2D, Triangle};
 
 use crate::widgets::containers::{Container, Nothing};
 pub use crate::widgets::panel::Panel;
 use crate::{
-    Button, Choice, Color, DeferDraw, DrawWithTooltips, Drawable, Dropdown, EventCtx, GeomBatch,
-    GfxCtx, JustDraw, Menu, RewriteColor, ScreenDims, ScreenPt, ScreenRectangle, Text, TextBox,
+    Button, Checkbox, Choice, Color, ColorPicker, DeferDraw, Divider, DrawWithTooltips, Drawable,
+    Dropdown, EventCtx, Fill, GeomBatch, GfxCtx, ImageSource, JustDraw, LinearGradient,
+    LoadingSpinner, Menu, MultiKey, Panel, RadioGroup, RewriteColor, ScreenDims, ScreenPt,
+    ScreenRectangle, Text, TextBox, TextExt,
 };
 
 pub mod autocomplete;
 pub mod button;
 pub mod checkbox;
+pub mod color_picker;
 pub mod compare_times;
 pub mod containers;
+pub mod divider;
 pub mod dropdown;
 pub mod fan_chart;
 pub mod filler;
+pub mod histogram;
 pub mod just_draw;
 pub mod line_plot;
+pub mod loading_spinner;
 pub mod menu;
 mod panel;
 pub mod persistent_split;
+pub mod radio;
 pub mod scatter_plot;
 pub mod slider;
 pub mod spinner;
+pub mod tabs;
 pub mod text_box;
 
 /// Create a new widget by implementing this trait. You can instantiate your widget by calling
@@ -63,9 +71,16 @@ pub trait WidgetImpl: downcast_rs::Downcast {
 pub enum Outcome {
     /// An action was done
     Clicked(String),
+    /// A button made with `double_clickable` was clicked twice quickly. The first click still
+    /// produces Clicked.
+    DoubleClicked(String),
     /// A dropdown, checkbox, spinner, etc changed values. Usually this triggers a refresh of
     /// everything, so not useful to plumb along what changed.
     Changed,
+    /// The backdrop of a modal, dismissable Panel was clicked
+    Dismissed,
+    /// An entry was picked from a button's context menu. The button's action, then the entry.
+    ContextAction(String, String),
     /// Nothing happened
     Nothing,
 }
@@ -98,20 +113,32 @@ pub struct Widget {
     // to_geom forces this one to happen
     bg_batch: Option<GeomBatch>,
     id: Option<String>,
+    /// Shown when the mouse lingers over this widget
+    tooltip: Option<Text>,
+    /// Don't draw anything outside of rect
+    clip: bool,
+    /// Came from another Panel, so its names can't be used anywhere else in this one
+    embedded: bool,
+    /// Multiplies the alpha of everything this widget (and its children) draws
+    alpha: f32,
 }
 
 struct LayoutStyle {
     bg_color: Option<Color>,
+    // (from, to, vertical). Takes precedence over bg_color.
+    bg_gradient: Option<(Color, Color, bool)>,
     // (thickness, color)
     outline: Option<(f64, Color)>,
     // If None, as round as possible
     rounded_radius: Option<f64>,
+    // Siblings are drawn in increasing order of this
+    z_index: i32,
     style: Style,
 }
 
 // Layouting
-// TODO Maybe I just want margin, not padding. And maybe more granular controls per side. And to
-// apply margin to everything in a row or column.
+// TODO Maybe I just want margin, not padding. And to apply margin to everything in a row or
+// column.
 // TODO Row and columns feel backwards when using them.
 impl Widget {
     pub fn centered(mut self) -> Widget {
@@ -138,6 +165,26 @@ impl Widget {
         self
     }
 
+    // Only for rows. Leaf widgets don't know where their text baseline is, so this lines up their
+    // bottom edges instead, which works for single lines of text with different font sizes.
+    pub fn align_baseline(mut self) -> Widget {
+        self.layout.style.align_items = AlignItems::Baseline;
+        self
+    }
+
+    // Only for rows. Splits the row's width equally between the direct children, no matter how
+    // wide their content is. The row needs room to divide, from something like fill_width or
+    // force_width.
+    pub fn equal_widths(mut self) -> Widget {
+        if let Some(container) = self.widget.downcast_mut::<Container>() {
+            for w in &mut container.members {
+                w.layout.style.flex_basis = Dimension::Points(0.0);
+                w.layout.style.flex_grow = 1.0;
+            }
+        }
+        self
+    }
+
     pub fn fill_width(mut self) -> Widget {
         self.layout.style.size.width = Dimension::Percent(1.0);
         self
@@ -159,6 +206,24 @@ impl Widget {
         self.layout.style.justify_content = JustifyContent::SpaceAround;
         self
     }
+    // Like flex_wrap, but each new line goes before the previous one.
+    pub fn flex_wrap_reverse(self, ctx: &EventCtx, width: Percent) -> Widget {
+        let mut w = self.flex_wrap(ctx, width);
+        w.layout.style.flex_wrap = FlexWrap::WrapReverse;
+        w
+    }
+    // How much of the leftover space in a row/column this takes up, relative to its siblings. Use
+    // this to push things to the far end, like buttons after a title.
+    pub fn flex_grow(mut self, factor: f32) -> Widget {
+        self.layout.style.flex_grow = factor;
+        self
+    }
+    // How much this shrinks relative to its siblings, when a row/column doesn't have enough
+    // space.
+    pub fn flex_shrink(mut self, factor: f32) -> Widget {
+        self.layout.style.flex_shrink = factor;
+        self
+    }
     // Only for rows/columns. Used to force table columns to line up.
     pub fn force_width(mut self, width: f64) -> Widget {
         self.layout.style.size.width = Dimension::Points(width as f32);
@@ -170,6 +235,24 @@ impl Widget {
         self
     }
 
+    // Only for rows/columns. Flexbox still grows or shrinks these within the bounds.
+    pub fn min_width(mut self, width: f64) -> Widget {
+        self.layout.style.min_size.width = Dimension::Points(width as f32);
+        self
+    }
+    pub fn max_width(mut self, width: f64) -> Widget {
+        self.layout.style.max_size.width = Dimension::Points(width as f32);
+        self
+    }
+    pub fn min_height(mut self, height: f64) -> Widget {
+        self.layout.style.min_size.height = Dimension::Points(height as f32);
+        self
+    }
+    pub fn max_height(mut self, height: f64) -> Widget {
+        self.layout.style.max_size.height = Dimension::Points(height as f32);
+        self
+    }
+
     // Needed for force_width.
     pub fn get_width_for_forcing(&self) -> f64 {
         self.widget.get_dims().width
@@ -179,6 +262,12 @@ impl Widget {
         self.layout.bg_color = Some(color);
         self
     }
+    /// Fill the background with a gradient, from the top to the bottom if `vertical`, otherwise
+    /// from the left to the right.
+    pub fn bg_gradient(mut self, from: Color, to: Color, vertical: bool) -> Widget {
+        self.layout.bg_gradient = Some((from, to, vertical));
+        self
+    }
 
     // Callers have to adjust padding too, probably
     pub fn outline(mut self, thickness: f64, color: Color) -> Widget {
@@ -189,6 +278,12 @@ impl Widget {
         self.layout.rounded_radius = None;
         self
     }
+    /// Set the radius of the background and outline's corners. 0 makes square corners; the
+    /// default is 5.
+    pub fn corner_radius(mut self, radius: f64) -> Widget {
+        self.layout.rounded_radius = Some(radius);
+        self
+    }
 
     // Things like padding don't work on many widgets, so just make a convenient way to wrap in a
     // row/column first
@@ -304,6 +399,68 @@ impl Widget {
         self.id = Some(id.into());
         self
     }
+
+    /// Don't draw anything from this widget (or its children) outside of its rectangle. Useful
+    /// when the contents are forced to be smaller than they'd like, without scrolling.
+    pub fn clip(mut self) -> Widget {
+        self.clip = true;
+        self
+    }
+
+    /// Fade out everything this widget draws, including its background and children. 0 is
+    /// invisible and 1 is fully opaque; anything else is clamped to that range. The widget still
+    /// takes up space and responds to events.
+    pub fn alpha(mut self, alpha: f32) -> Widget {
+        self.alpha = alpha.max(0.0).min(1.0);
+        self
+    }
+
+    /// Widgets in the same row or column are drawn in increasing order of this, then in the order
+    /// they were added. Useful when siblings overlap. The default is 0.
+    pub fn z_index(mut self, z: i32) -> Widget {
+        self.layout.z_index = z;
+        self
+    }
+
+    /// Show some text near the cursor when the mouse lingers over this widget. Works on anything
+    /// inside a Panel.
+    pub fn tooltip(mut self, txt: Text) -> Widget {
+        self.tooltip = Some(txt);
+        self
+    }
+
+    /// Only works on buttons. Makes the button return Outcome::DoubleClicked for the second of two
+    /// quick clicks.
+    pub fn double_clickable(mut self) -> Widget {
+        if let Some(btn) = self.widget.downcast_mut::<Button>() {
+            btn.double_clickable = true;
+        } else {
+            panic!("double_clickable() called on something that isn't a button");
+        }
+        self
+    }
+
+    /// Only works on buttons. Right-clicking the button opens a menu with these entries at the
+    /// cursor; picking one returns Outcome::ContextAction.
+    pub fn context_menu(mut self, entries: Vec<String>) -> Widget {
+        if let Some(btn) = self.widget.downcast_mut::<Button>() {
+            btn.context_menu = entries;
+        } else {
+            panic!("context_menu() called on something that isn't a button");
+        }
+        self
+    }
+
+    /// Only works on buttons. A disabled button is greyed out and ignores clicks and hotkeys. Use
+    /// `Panel::set_disabled` to toggle it later.
+    pub fn disabled(mut self, disabled: bool) -> Widget {
+        if let Some(btn) = self.widget.downcast_mut::<Button>() {
+            btn.set_disabled(disabled);
+        } else {
+            panic!("disabled() called on something that isn't a button");
+        }
+        self
+    }
 }
 
 // Convenient?? constructors
@@ -313,8 +470,10 @@ impl Widget {
             widget,
             layout: LayoutStyle {
                 bg_color: None,
+                bg_gradient: None,
                 outline: None,
                 rounded_radius: Some(5.0),
+                z_index: 0,
                 style: Style {
                     ..Default::default()
                 },
@@ -323,6 +482,10 @@ impl Widget {
             bg: None,
             bg_batch: None,
             id: None,
+            tooltip: None,
+            clip: false,
+            embedded: false,
+            alpha: 1.0,
         }
     }
 
@@ -331,12 +494,30 @@ impl Widget {
     pub fn draw_batch(ctx: &EventCtx, batch: GeomBatch) -> Widget {
         JustDraw::wrap(ctx, batch)
     }
+    /// Wraps the text first, so the widget is at most `max_width` pixels wide and tall enough for
+    /// all of the lines. See `Text::wrap_to_pixels`.
+    pub fn draw_text_wrapped(ctx: &EventCtx, txt: Text, max_width: f64) -> Widget {
+        JustDraw::wrap(ctx, txt.wrap_to_pixels(ctx, max_width).render(ctx))
+    }
     pub fn draw_svg<I: Into<String>>(ctx: &EventCtx, filename: I) -> Widget {
         JustDraw::svg(ctx, filename.into())
     }
     pub fn draw_svg_transform(ctx: &EventCtx, filename: &str, rewrite: RewriteColor) -> Widget {
         JustDraw::svg_transform(ctx, filename, rewrite)
     }
+    /// Draw a PNG or other raster image, stretched to fill dims. Use `image_transform` to tint
+    /// monochrome icons.
+    pub fn image(ctx: &EventCtx, source: ImageSource, dims: ScreenDims) -> Widget {
+        JustDraw::image(ctx, source, dims, RewriteColor::NoOp)
+    }
+    pub fn image_transform(
+        ctx: &EventCtx,
+        source: ImageSource,
+        dims: ScreenDims,
+        rewrite: RewriteColor,
+    ) -> Widget {
+        JustDraw::image(ctx, source, dims, rewrite)
+    }
     pub fn draw_svg_with_tooltip<I: Into<String>>(
         ctx: &EventCtx,
         filename: I,
@@ -380,6 +561,34 @@ impl Widget {
         .outline(ctx.style().outline_thickness, ctx.style().outline_color)
     }
 
+    pub fn radio_group<I: Into<String>>(
+        ctx: &EventCtx,
+        label: I,
+        options: Vec<String>,
+        default_index: usize,
+    ) -> Widget {
+        RadioGroup::new(ctx, label, options, default_index)
+    }
+
+    pub fn color_picker<I: Into<String>>(ctx: &EventCtx, label: I, default: Color) -> Widget {
+        ColorPicker::new(ctx, label, default)
+    }
+
+    /// See `LoadingSpinner` for how to keep it animating.
+    pub fn loading_spinner(ctx: &EventCtx, diameter: f64) -> Widget {
+        LoadingSpinner::new(ctx, diameter)
+    }
+
+    /// A clickable header with a chevron. Clicking it shows or hides the body; while hidden, the
+    /// body takes no space. This doesn't produce any Outcome. The header's text names the section,
+    /// so `Panel::is_checked` with that text says if it's expanded, and it can't be used by any
+    /// other widget in the panel.
+    pub fn collapsible(ctx: &EventCtx, header: Text, body: Widget, expanded: bool) -> Widget {
+        Widget::new(Box::new(Container::collapsible(
+            ctx, header, body, expanded,
+        )))
+    }
+
     pub fn custom_row(widgets: Vec<Widget>) -> Widget {
         Widget::new(Box::new(Container::new(true, widgets)))
     }
@@ -414,10 +623,51 @@ impl Widget {
         Widget::new(Box::new(Container::new(false, new)))
     }
 
+    /// Lay out cells in rows of `cols`, all the same width, so the columns line up. The last row
+    /// may be partly empty; its cells still line up with the rows above.
+    pub fn grid(cols: usize, mut cells: Vec<Widget>) -> Widget {
+        assert!(cols > 0);
+        cells.retain(|w| !w.widget.is::<Nothing>());
+        let cell_width = cells
+            .iter()
+            .map(|w| w.get_width_for_forcing())
+            .fold(0.0, f64::max);
+        // Every cell starts from the same size and grows equally to fill its row
+        let make_cell = |w: Widget| {
+            let mut cell = Widget::custom_row(vec![w])
+                .min_width(cell_width)
+                .flex_grow(1.0);
+            cell.layout.style.flex_basis = Dimension::Points(0.0);
+            cell
+        };
+
+        let mut rows = Vec::new();
+        let mut cells = cells.into_iter().peekable();
+        while cells.peek().is_some() {
+            let mut row: Vec<Widget> = cells.by_ref().take(cols).map(make_cell).collect();
+            while row.len() < cols {
+                row.push(make_cell(Widget::custom_row(Vec::new())));
+            }
+            rows.push(Widget::row(row));
+        }
+        Widget::col(rows)
+    }
+
     pub fn nothing() -> Widget {
         Widget::new(Box::new(Nothing {}))
     }
 
+    /// Reuse the contents of an already built Panel inside another. Names of widgets inside the
+    /// embedded panel must not be used anywhere else in the new panel; building it panics
+    /// otherwise. Settings of the old panel, like alignment and scrolling, are dropped.
+    pub fn embed(panel: Panel) -> Widget {
+        let mut widget = panel.into_widget();
+        widget.embedded = true;
+        // The layout will probably change, so don't keep backgrounds sized for the old panel
+        widget.clear_bgs();
+        widget
+    }
+
     // Also returns the hitbox of the entire widget
     pub fn to_geom(mut self, ctx: &EventCtx, exact_pct_width: Option<f64>) -> (GeomBatch, Polygon) {
         if let Some(w) = exact_pct_width {
@@ -480,6 +730,49 @@ impl Widget {
             GeomBatch::from(vec![(Color::WHITE, Polygon::rectangle(2.0, height_px))]),
         )
     }
+
+    /// A line stretching across the width of the column containing it. Use `Divider` to change
+    /// the thickness or color.
+    pub fn divider(ctx: &EventCtx) -> Widget {
+        Divider::horizontal().build(ctx)
+    }
+
+    /// A line stretching across the height of the row containing it.
+    pub fn vertical_divider(ctx: &EventCtx) -> Widget {
+        Divider::vertical().build(ctx)
+    }
+
+    /// A label, a value, and an arrow showing how the value compares to some baseline: a green
+    /// up arrow if `delta` is positive, a red down arrow if it's negative, and a dash otherwise.
+    pub fn stat<I: Into<String>>(
+        ctx: &EventCtx,
+        label: I,
+        value: Text,
+        delta: Option<f64>,
+    ) -> Widget {
+        let size = 12.0;
+        let up = Polygon::from_triangle(&Triangle {
+            pt1: Pt2D::new(0.0, size),
+            pt2: Pt2D::new(size / 2.0, 0.0),
+            pt3: Pt2D::new(size, size),
+        });
+        let trend = match delta {
+            Some(x) if x > 0.0 => GeomBatch::from(vec![(Color::GREEN, up)]),
+            Some(x) if x < 0.0 => {
+                GeomBatch::from(vec![(Color::RED, up.rotate(Angle::new_degs(180.0)))])
+            }
+            _ => GeomBatch::from(vec![(
+                Color::grey(0.5),
+                Polygon::rectangle(size, 2.0).translate(0.0, (size - 2.0) / 2.0),
+            )]),
+        };
+        Widget::row(vec![
+            label.into().draw_text(ctx),
+            value.draw(ctx),
+            Widget::draw_batch(ctx, trend),
+        ])
+        .centered_cross()
+    }
 }
 
 // Internals
@@ -492,11 +785,19 @@ impl Widget {
             return;
         }
 
+        let prev_alpha = g.push_alpha(self.alpha);
         if let Some(ref bg) = self.bg {
             g.redraw_at(ScreenPt::new(self.rect.x1, self.rect.y1), bg);
         }
 
-        self.widget.draw(g);
+        if self.clip {
+            let prev = g.push_clipping(self.rect.clone());
+            self.widget.draw(g);
+            g.pop_clipping(prev);
+        } else {
+            self.widget.draw(g);
+        }
+        g.pop_alpha(prev_alpha);
     }
 
     // Populate a flattened list of Nodes, matching the traversal order
@@ -510,11 +811,30 @@ impl Widget {
             };
             let node = stretch.new_node(style, Vec::new()).unwrap();
             nodes.push(node);
-            for widget in &container.members {
+            for widget in &container.members[..container.num_visible()] {
                 widget.get_flexbox(node, stretch, nodes);
             }
             stretch.add_child(parent, node).unwrap();
             return;
+        } else if let Some(divider) = self.widget.downcast_ref::<Divider>() {
+            // Stretch along the parent's cross axis, however long that turns out to be
+            let mut style = self.layout.style.clone();
+            let thickness = Dimension::Points(divider.thickness_px() as f32);
+            style.size = if divider.is_horizontal() {
+                Size {
+                    width: Dimension::Auto,
+                    height: thickness,
+                }
+            } else {
+                Size {
+                    width: thickness,
+                    height: Dimension::Auto,
+                }
+            };
+            style.align_self = AlignSelf::Stretch;
+            let node = stretch.new_node(style, Vec::new()).unwrap();
+            stretch.add_child(parent, node).unwrap();
+            nodes.push(node);
         } else {
             let mut style = self.layout.style.clone();
             style.size = Size {
@@ -556,21 +876,36 @@ impl Widget {
 
         // Assume widgets don't dynamically change, so we just upload the background once.
         if (self.bg.is_none() || recompute_layout)
-            && (self.layout.bg_color.is_some() || self.layout.outline.is_some())
+            && (self.layout.bg_color.is_some()
+                || self.layout.bg_gradient.is_some()
+                || self.layout.outline.is_some())
         {
             let mut batch = GeomBatch::new();
-            if let Some(c) = self.layout.bg_color {
+            let bg_polygon = if self.layout.rounded_radius == Some(0.0) {
+                Polygon::rectangle(width, height)
+            } else {
+                Polygon::rounded_rectangle(width, height, self.layout.rounded_radius)
+            };
+            if let Some((from, to, vertical)) = self.layout.bg_gradient {
+                let end = if vertical {
+                    Pt2D::new(0.0, height)
+                } else {
+                    Pt2D::new(width, 0.0)
+                };
                 batch.push(
-                    c,
-                    Polygon::rounded_rectangle(width, height, self.layout.rounded_radius),
+                    Fill::LinearGradient(LinearGradient {
+                        line: Line::must_new(Pt2D::new(0.0, 0.0), end),
+                        stops: vec![(0.0, from), (1.0, to)],
+                    }),
+                    bg_polygon.clone(),
                 );
+            } else if let Some(c) = self.layout.bg_color {
+                batch.push(c, bg_polygon.clone());
             }
             if let Some((thickness, color)) = self.layout.outline {
                 batch.push(
                     color,
-                    Polygon::rounded_rectangle(width, height, self.layout.rounded_radius)
-                        .to_outline(Distance::meters(thickness))
-                        .unwrap(),
+                    bg_polygon.to_outline(Distance::meters(thickness)).unwrap(),
                 );
             }
             if defer_draw {
@@ -582,7 +917,8 @@ impl Widget {
 
         if let Some(container) = self.widget.downcast_mut::<Container>() {
             // layout() doesn't return absolute position; it's relative to the container.
-            for widget in &mut container.members {
+            let num_visible = container.num_visible();
+            for widget in &mut container.members[..num_visible] {
                 widget.apply_flexbox(
                     stretch,
                     nodes,
@@ -595,10 +931,65 @@ impl Widget {
                 );
             }
         } else {
+            if let Some(divider) = self.widget.downcast_mut::<Divider>() {
+                divider.set_length(if divider.is_horizontal() {
+                    width
+                } else {
+                    height
+                });
+            }
             self.widget.set_pos(top_left);
         }
     }
 
+    fn clear_bgs(&mut self) {
+        self.bg = None;
+        if let Some(container) = self.widget.downcast_mut::<Container>() {
+            for w in &mut container.members {
+                w.clear_bgs();
+            }
+        }
+    }
+
+    fn check_embedded_names(&self) {
+        let mut names = Vec::new();
+        self.collect_names(None, &mut 0, &mut names);
+        let mut seen: HashMap<&String, Option<usize>> = HashMap::new();
+        for (name, group) in &names {
+            if let Some(other) = seen.insert(name, *group) {
+                if other != *group {
+                    panic!(
+                        "Widget name {} is used by an embedded Panel and somewhere else",
+                        name
+                    );
+                }
+            }
+        }
+    }
+
+    /// Each embedded panel gets its own group
+    fn collect_names<'a>(
+        &'a self,
+        group: Option<usize>,
+        next_group: &mut usize,
+        names: &mut Vec<(&'a String, Option<usize>)>,
+    ) {
+        let group = if self.embedded {
+            *next_group += 1;
+            Some(*next_group)
+        } else {
+            group
+        };
+        if let Some(ref id) = self.id {
+            names.push((id, group));
+        }
+        if let Some(container) = self.widget.downcast_ref::<Container>() {
+            for w in &container.members {
+                w.collect_names(group, next_group, names);
+            }
+        }
+    }
+
     fn get_all_click_actions(&self, actions: &mut HashSet<String>) {
         if let Some(btn) = self.widget.downcast_ref::<Button>() {
             if actions.contains(&btn.action) {
@@ -612,13 +1003,32 @@ impl Widget {
         }
     }
 
+    fn check_hotkeys(&self, hotkeys: &mut HashMap<MultiKey, String>) {
+        if let Some(btn) = self.widget.downcast_ref::<Button>() {
+            if let Some(ref key) = btn.hotkey {
+                if let Some(other) = hotkeys.insert(key.clone(), btn.action.clone()) {
+                    panic!(
+                        "Two buttons in one Panel both use hotkey {}: {} and {}",
+                        key.describe(),
+                        other,
+                        btn.action
+                    );
+                }
+            }
+        } else if let Some(container) = self.widget.downcast_ref::<Container>() {
+            for w in &container.members {
+                w.check_hotkeys(hotkeys);
+            }
+        }
+    }
+
     fn currently_hovering(&self) -> Option<&String> {
         if let Some(btn) = self.widget.downcast_ref::<Button>() {
             if btn.hovering {
                 return Some(&btn.action);
             }
         } else if let Some(container) = self.widget.downcast_ref::<Container>() {
-            for w in &container.members {
+            for w in &container.members[..container.num_visible()] {
                 if let Some(a) = w.currently_hovering() {
                     return Some(a);
                 }
@@ -627,6 +1037,25 @@ impl Widget {
         None
     }
 
+    /// The innermost widget with a tooltip under the cursor
+    fn find_tooltip(&self, pt: ScreenPt) -> Option<&Widget> {
+        if !self.rect.contains(pt) {
+            return None;
+        }
+        if let Some(container) = self.widget.downcast_ref::<Container>() {
+            for w in &container.members[..container.num_visible()] {
+                if let Some(w) = w.find_tooltip(pt) {
+                    return Some(w);
+                }
+            }
+        }
+        if self.tooltip.is_some() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
     fn restore(&mut self, ctx: &mut EventCtx, prev: &Panel) {
         if let Some(container) = self.widget.downcast_mut::<Container>() {
             for w in &mut container.members {
@@ -696,6 +1125,46 @@ impl Widget {
         None
     }
 
+    /// Enabled buttons, checkboxes, and text boxes, in the order they appear
+    fn focusable(&self) -> Vec<&Widget> {
+        let mut list = Vec::new();
+        self.collect_focusable(&mut list);
+        list
+    }
+    fn collect_focusable<'a>(&'a self, list: &mut Vec<&'a Widget>) {
+        if let Some(container) = self.widget.downcast_ref::<Container>() {
+            for w in &container.members[..container.num_visible()] {
+                w.collect_focusable(list);
+            }
+        } else if self.is_focusable() {
+            list.push(self);
+        }
+    }
+    fn focusable_mut(&mut self) -> Vec<&mut Widget> {
+        let mut list = Vec::new();
+        self.collect_focusable_mut(&mut list);
+        list
+    }
+    fn collect_focusable_mut<'a>(&'a mut self, list: &mut Vec<&'a mut Widget>) {
+        // downcast_mut would keep self borrowed in the else branch, so check is() first
+        if self.widget.is::<Container>() {
+            let container = self.widget.downcast_mut::<Container>().unwrap();
+            let num_visible = container.num_visible();
+            for w in &mut container.members[..num_visible] {
+                w.collect_focusable_mut(list);
+            }
+        } else if self.is_focusable() {
+            list.push(self);
+        }
+    }
+    fn is_focusable(&self) -> bool {
+        if let Some(btn) = self.widget.downcast_ref::<Button>() {
+            !btn.disabled
+        } else {
+            self.widget.is::<Checkbox>() || self.widget.is::<TextBox>()
+        }
+    }
+
     pub(crate) fn take_btn(self) -> Button {
         *self.widget.downcast::<Button>().ok().unwrap()
     }
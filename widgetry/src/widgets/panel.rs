@@ -1,23 +1,29 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
+use instant::Instant;
 use stretch::geometry::Size;
 use stretch::node::Stretch;
 use stretch::number::Number;
 use stretch::style::{Dimension, Style};
 
-use geom::{Percent, Polygon};
+use abstutil::elapsed_seconds;
+use geom::{Distance, Percent, Polygon};
 
+use crate::widgets::slider::BG_CROSS_AXIS_LEN;
 use crate::widgets::Container;
 use crate::{
-    AreaSlider, Autocomplete, Checkbox, Color, Dropdown, EventCtx, GfxCtx, HorizontalAlignment,
-    Menu, Outcome, PersistentSplit, ScreenDims, ScreenPt, ScreenRectangle, Slider, Spinner,
-    TextBox, VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
+    hotkeys, AreaSlider, Autocomplete, Button, Canvas, Checkbox, Color, ColorPicker, Dropdown,
+    EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, JustDraw, Key, Menu, Outcome,
+    PersistentSplit, RadioGroup, ScreenDims, ScreenPt, ScreenRectangle, Slider, Spinner, TextBox,
+    UpdateType, VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Panel {
     top_level: Widget,
     horiz: HorizontalAlignment,
     vert: VerticalAlignment,
+    /// Overrides horiz and vert
+    pinned: Option<ScreenPt>,
     dims: Dims,
 
     scrollable_x: bool,
@@ -25,15 +31,57 @@ pub struct Panel {
     contents_dims: ScreenDims,
     container_dims: ScreenDims,
     clip_rect: Option<ScreenRectangle>,
+
+    smooth_scroll: bool,
+    /// When smoothly scrolling, the offset we're easing towards
+    scroll_target: Option<(f64, f64)>,
+    /// If None, use the Canvas's gui_scroll_speed
+    scroll_speed: Option<f64>,
+    /// Flip the direction of scrolling, on top of the Canvas's invert_scroll
+    invert_scroll: bool,
+
+    /// Dim everything else and ignore clicks outside the panel
+    modal: bool,
+    /// For modal panels, clicking the backdrop returns Outcome::Dismissed
+    dismissable: bool,
+
+    /// The widget with a tooltip under the cursor, and when the mouse started hovering there
+    tooltip_hover: Option<(ScreenRectangle, Instant)>,
+
+    scrollbar_thickness: f64,
+    /// Only draw scrollbars while the cursor is over the panel or something is scrolling
+    scrollbar_auto_hide: bool,
+
+    /// Tab and Shift+Tab move focus between interactive widgets
+    keyboard_navigation: bool,
+    /// Indexes into the top-level widget's focusable()
+    focus: Option<usize>,
+    /// Set by recompute_layout, cleared at the start of every event
+    layout_changed: bool,
 }
 
+/// When smoothly scrolling, roughly how long it takes to reach the target offset
+const SMOOTH_SCROLL_TIME_S: f64 = 0.1;
+/// How long the mouse has to linger over a widget before its tooltip appears
+const TOOLTIP_DELAY_S: f64 = 0.5;
+const FOCUS_RING_THICKNESS: f64 = 3.0;
+
 impl Panel {
     pub fn new(top_level: Widget) -> PanelBuilder {
         PanelBuilder {
             top_level,
             horiz: HorizontalAlignment::Center,
             vert: VerticalAlignment::Center,
+            pinned: None,
             dims: Dims::MaxPercent(Percent::int(100), Percent::int(100)),
+            smooth_scroll: false,
+            scroll_speed: None,
+            invert_scroll: false,
+            modal: false,
+            dismissable: false,
+            scrollbar_thickness: BG_CROSS_AXIS_LEN,
+            scrollbar_auto_hide: false,
+            keyboard_navigation: false,
         }
     }
 
@@ -60,32 +108,22 @@ impl Panel {
         self.scrollable_x = self.contents_dims.width > self.container_dims.width;
         self.scrollable_y = self.contents_dims.height > self.container_dims.height;
 
-        // Unwrap the main widget from any scrollable containers if necessary.
-        if old_scrollable_y {
-            let container = self.top_level.widget.downcast_mut::<Container>().unwrap();
-            self.top_level = container.members.remove(0);
-        }
-
-        if old_scrollable_x {
-            let container = self.top_level.widget.downcast_mut::<Container>().unwrap();
-            self.top_level = container.members.remove(0);
-        }
+        self.unwrap_scrollbars(old_scrollable_x, old_scrollable_y);
 
-        let top_left = ctx
-            .canvas
-            .align_window(self.container_dims, self.horiz, self.vert);
+        let top_left = self.top_left(ctx.canvas, self.container_dims);
 
         // Wrap the main widget in scrollable containers if necessary.
         if self.scrollable_x {
             let old_top_level = std::mem::replace(&mut self.top_level, Widget::nothing());
             self.top_level = Widget::custom_col(vec![
                 old_top_level,
-                Slider::horizontal(
+                Slider::scrollbar(
                     ctx,
+                    true,
                     self.container_dims.width,
                     self.container_dims.width
                         * (self.container_dims.width / self.contents_dims.width),
-                    0.0,
+                    self.scrollbar_thickness,
                 )
                 .named("horiz scrollbar")
                 .abs(top_left.x, top_left.y + self.container_dims.height),
@@ -96,12 +134,13 @@ impl Panel {
             let old_top_level = std::mem::replace(&mut self.top_level, Widget::nothing());
             self.top_level = Widget::custom_row(vec![
                 old_top_level,
-                Slider::vertical(
+                Slider::scrollbar(
                     ctx,
+                    false,
                     self.container_dims.height,
                     self.container_dims.height
                         * (self.container_dims.height / self.contents_dims.height),
-                    0.0,
+                    self.scrollbar_thickness,
                 )
                 .named("vert scrollbar")
                 .abs(top_left.x + self.container_dims.width, top_left.y),
@@ -117,10 +156,24 @@ impl Panel {
         };
     }
 
+    /// Unwrap the main widget from any scrollable containers if necessary.
+    fn unwrap_scrollbars(&mut self, scrollable_x: bool, scrollable_y: bool) {
+        if scrollable_y {
+            let container = self.top_level.widget.downcast_mut::<Container>().unwrap();
+            self.top_level = container.members.remove(0);
+        }
+
+        if scrollable_x {
+            let container = self.top_level.widget.downcast_mut::<Container>().unwrap();
+            self.top_level = container.members.remove(0);
+        }
+    }
+
     // TODO: this method potentially gets called multiple times in a render pass as an
     // optimization, we could replace all the current call sites with a "dirty" flag, e.g.
     // `set_needs_layout()` and then call `layout_if_needed()` once at the last possible moment
     fn recompute_layout(&mut self, ctx: &EventCtx, recompute_bg: bool) {
+        self.layout_changed = true;
         self.recompute_scrollbar_layout(ctx);
         let mut stretch = Stretch::new();
         let root = stretch
@@ -150,9 +203,7 @@ impl Panel {
             let result = stretch.layout(root).unwrap();
             ScreenDims::new(result.size.width.into(), result.size.height.into())
         };
-        let top_left = ctx
-            .canvas
-            .align_window(effective_dims, self.horiz, self.vert);
+        let top_left = self.top_left(ctx.canvas, effective_dims);
         let offset = self.scroll_offset();
         self.top_level.apply_flexbox(
             &stretch,
@@ -167,16 +218,28 @@ impl Panel {
         assert!(nodes.is_empty());
     }
 
+    /// Where a panel of some size goes on the screen
+    fn top_left(&self, canvas: &Canvas, dims: ScreenDims) -> ScreenPt {
+        if let Some(pt) = self.pinned {
+            // Stay on-screen. If the panel is bigger than the window, keep the top-left visible.
+            ScreenPt::new(
+                pt.x.min(canvas.window_width - dims.width).max(0.0),
+                pt.y.min(canvas.window_height - dims.height).max(0.0),
+            )
+        } else {
+            canvas.align_window(dims, self.horiz, self.vert)
+        }
+    }
+
     fn scroll_offset(&self) -> (f64, f64) {
+        let (max_x, max_y) = self.max_scroll_offset();
         let x = if self.scrollable_x {
-            self.slider("horiz scrollbar").get_percent()
-                * (self.contents_dims.width - self.container_dims.width).max(0.0)
+            percent_to_offset(self.slider("horiz scrollbar").get_percent(), max_x)
         } else {
             0.0
         };
         let y = if self.scrollable_y {
-            self.slider("vert scrollbar").get_percent()
-                * (self.contents_dims.height - self.container_dims.height).max(0.0)
+            percent_to_offset(self.slider("vert scrollbar").get_percent(), max_y)
         } else {
             0.0
         };
@@ -185,29 +248,27 @@ impl Panel {
 
     fn update_scroll_sliders(&mut self, ctx: &EventCtx, offset: (f64, f64)) -> bool {
         let mut changed = false;
+        let (max_x, max_y) = self.max_scroll_offset();
         if self.scrollable_x {
             changed = true;
-            let max = (self.contents_dims.width - self.container_dims.width).max(0.0);
-            if max == 0.0 {
-                self.slider_mut("horiz scrollbar").set_percent(ctx, 0.0);
-            } else {
-                self.slider_mut("horiz scrollbar")
-                    .set_percent(ctx, abstutil::clamp(offset.0, 0.0, max) / max);
-            }
+            self.slider_mut("horiz scrollbar")
+                .set_percent(ctx, offset_to_percent(offset.0, max_x));
         }
         if self.scrollable_y {
             changed = true;
-            let max = (self.contents_dims.height - self.container_dims.height).max(0.0);
-            if max == 0.0 {
-                self.slider_mut("vert scrollbar").set_percent(ctx, 0.0);
-            } else {
-                self.slider_mut("vert scrollbar")
-                    .set_percent(ctx, abstutil::clamp(offset.1, 0.0, max) / max);
-            }
+            self.slider_mut("vert scrollbar")
+                .set_percent(ctx, offset_to_percent(offset.1, max_y));
         }
         changed
     }
 
+    fn max_scroll_offset(&self) -> (f64, f64) {
+        (
+            (self.contents_dims.width - self.container_dims.width).max(0.0),
+            (self.contents_dims.height - self.container_dims.height).max(0.0),
+        )
+    }
+
     fn set_scroll_offset(&mut self, ctx: &EventCtx, offset: (f64, f64)) {
         if self.update_scroll_sliders(ctx, offset) {
             self.recompute_layout(ctx, false);
@@ -215,6 +276,7 @@ impl Panel {
     }
 
     pub fn event(&mut self, ctx: &mut EventCtx) -> Outcome {
+        self.layout_changed = false;
         if (self.scrollable_x || self.scrollable_y)
             && ctx
                 .canvas
@@ -222,18 +284,56 @@ impl Panel {
                 .map(|pt| self.top_level.rect.contains(pt))
                 .unwrap_or(false)
         {
-            if let Some((dx, dy)) = ctx.input.get_mouse_scroll() {
+            if let Some((mut dx, mut dy)) = ctx.input.get_mouse_scroll() {
+                if self.invert_scroll {
+                    dx = -dx;
+                    dy = -dy;
+                }
+                let speed = self
+                    .scroll_speed
+                    .unwrap_or(ctx.canvas.gui_scroll_speed as f64);
+                // If we're still easing towards an earlier target, keep going from there.
+                let current = self.scroll_target.unwrap_or_else(|| self.scroll_offset());
                 let x_offset = if self.scrollable_x {
-                    self.scroll_offset().0 + dx * (ctx.canvas.gui_scroll_speed as f64)
+                    current.0 + dx * speed
                 } else {
                     0.0
                 };
                 let y_offset = if self.scrollable_y {
-                    self.scroll_offset().1 - dy * (ctx.canvas.gui_scroll_speed as f64)
+                    current.1 - dy * speed
                 } else {
                     0.0
                 };
-                self.set_scroll_offset(ctx, (x_offset, y_offset));
+                if self.smooth_scroll {
+                    let max = self.max_scroll_offset();
+                    self.scroll_target = Some((
+                        abstutil::clamp(x_offset, 0.0, max.0),
+                        abstutil::clamp(y_offset, 0.0, max.1),
+                    ));
+                    ctx.request_update(UpdateType::Game);
+                } else {
+                    self.set_scroll_offset(ctx, (x_offset, y_offset));
+                }
+            }
+        }
+
+        if let Some(target) = self.scroll_target {
+            if let Some(dt) = ctx.input.nonblocking_is_update_event() {
+                let current = self.scroll_offset();
+                let pct = (dt.inner_seconds() / SMOOTH_SCROLL_TIME_S).min(1.0);
+                let mut next = (
+                    current.0 + pct * (target.0 - current.0),
+                    current.1 + pct * (target.1 - current.1),
+                );
+                // Snap to the target once we're close, to avoid jitter
+                if (target.0 - next.0).abs() < 1.0 && (target.1 - next.1).abs() < 1.0 {
+                    next = target;
+                    self.scroll_target = None;
+                }
+                self.set_scroll_offset(ctx, next);
+            }
+            if self.scroll_target.is_some() {
+                ctx.request_update(UpdateType::Game);
             }
         }
 
@@ -242,17 +342,147 @@ impl Panel {
             self.recompute_layout(ctx, false);
         }
 
+        if self.keyboard_navigation {
+            if let Some(outcome) = self.keyboard_navigation_event(ctx) {
+                return outcome;
+            }
+        }
+
         let before = self.scroll_offset();
         let mut output = WidgetOutput::new();
         self.top_level.widget.event(ctx, &mut output);
         if self.scroll_offset() != before || output.redo_layout {
+            // The scrollbars were dragged manually, so stop easing somewhere else
+            if self.scroll_offset() != before {
+                self.scroll_target = None;
+            }
             self.recompute_layout(ctx, true);
         }
 
+        if ctx.redo_mouseover() {
+            let hovering = ctx
+                .canvas
+                .get_cursor_in_screen_space()
+                .and_then(|pt| self.top_level.find_tooltip(pt))
+                .map(|w| w.rect.clone());
+            if hovering != self.tooltip_hover.as_ref().map(|(rect, _)| rect.clone()) {
+                self.tooltip_hover = hovering.map(|rect| (rect, Instant::now()));
+            }
+        }
+        if let Some((_, since)) = self.tooltip_hover {
+            // Keep redrawing until the tooltip appears
+            if elapsed_seconds(since) < TOOLTIP_DELAY_S {
+                ctx.request_update(UpdateType::Game);
+            }
+        }
+
+        if self.modal && output.outcome == Outcome::Nothing && !ctx.input.has_been_consumed() {
+            let outside = ctx
+                .canvas
+                .get_cursor_in_screen_space()
+                .map(|pt| !self.top_level.rect.contains(pt))
+                .unwrap_or(false);
+            if outside {
+                if ctx.input.left_mouse_button_pressed() {
+                    ctx.input.consume_event();
+                } else if ctx.normal_left_click() && self.dismissable {
+                    return Outcome::Dismissed;
+                }
+            }
+        }
+
         output.outcome
     }
 
+    /// Moves focus around with Tab and Shift+Tab. If a button or checkbox is focused, Enter and
+    /// Space activate it, returning what clicking it would.
+    fn keyboard_navigation_event(&mut self, ctx: &mut EventCtx) -> Option<Outcome> {
+        // The mouse takes over
+        if ctx.input.left_mouse_button_pressed() {
+            self.set_focus(None);
+            return None;
+        }
+
+        let num = self.top_level.focusable().len();
+        if self.focus.map(|idx| idx >= num).unwrap_or(false) {
+            self.set_focus(None);
+        }
+        if num == 0 {
+            return None;
+        }
+
+        if ctx.input.pressed(Key::Tab) {
+            let backwards = ctx.canvas.lshift_held;
+            let next = match self.focus {
+                Some(idx) if backwards => (idx + num - 1) % num,
+                Some(idx) => (idx + 1) % num,
+                None if backwards => num - 1,
+                None => 0,
+            };
+            self.set_focus(Some(next));
+            return None;
+        }
+
+        let idx = self.focus?;
+        let mut focusable = self.top_level.focusable_mut();
+        let widget = &mut focusable[idx].widget;
+        if widget.is::<TextBox>() {
+            // Enter and Space belong to the text box
+            return None;
+        }
+        if !ctx.input.pressed(hotkeys(vec![Key::Enter, Key::Space])) {
+            return None;
+        }
+        if let Some(btn) = widget.downcast_ref::<Button>() {
+            return Some(Outcome::Clicked(btn.action.clone()));
+        }
+        widget.downcast_mut::<Checkbox>().unwrap().toggle();
+        self.recompute_layout(ctx, true);
+        Some(Outcome::Changed)
+    }
+
+    fn set_focus(&mut self, focus: Option<usize>) {
+        self.focus = focus;
+        for (idx, w) in self.top_level.focusable_mut().into_iter().enumerate() {
+            if let Some(tb) = w.widget.downcast_mut::<TextBox>() {
+                tb.set_focus(focus == Some(idx));
+            }
+        }
+    }
+
+    /// True if widgets might've moved or changed size during the last call to `event`, because
+    /// the window was resized, the panel scrolled, or a widget changed its dimensions. Use this to
+    /// redraw things that depend on `rect_of`, like something drawn into a `Filler`, only when
+    /// needed. Also true after `replace` and the other methods that lay out the panel again, until
+    /// the next call to `event`.
+    pub fn layout_changed(&self) -> bool {
+        self.layout_changed
+    }
+
+    /// With keyboard navigation enabled, the widget that currently has focus -- a button's action,
+    /// or the name of a checkbox or text box.
+    pub fn currently_focused(&self) -> Option<&str> {
+        let w = *self.top_level.focusable().get(self.focus?)?;
+        if let Some(btn) = w.widget.downcast_ref::<Button>() {
+            Some(&btn.action)
+        } else {
+            w.id.as_deref()
+        }
+    }
+
     pub fn draw(&self, g: &mut GfxCtx) {
+        if self.modal {
+            let window = g.canvas.get_window_dims();
+            let draw = g.upload(GeomBatch::from(vec![(
+                Color::BLACK.alpha(0.5),
+                Polygon::rectangle(window.width, window.height),
+            )]));
+            g.redraw_at(ScreenPt::new(0.0, 0.0), &draw);
+            // Nothing underneath should respond to the mouse
+            g.canvas
+                .mark_covered_area(ScreenRectangle::top_left(ScreenPt::new(0.0, 0.0), window));
+        }
+
         if let Some(ref rect) = self.clip_rect {
             g.enable_clipping(rect.clone());
             g.canvas.mark_covered_area(rect.clone());
@@ -265,9 +495,7 @@ impl Panel {
             g.fork_screenspace();
             g.draw_polygon(Color::RED.alpha(0.5), self.top_level.rect.to_polygon());
 
-            let top_left = g
-                .canvas
-                .align_window(self.container_dims, self.horiz, self.vert);
+            let top_left = self.top_left(g.canvas, self.container_dims);
             g.draw_polygon(
                 Color::BLUE.alpha(0.5),
                 Polygon::rectangle(self.container_dims.width, self.container_dims.height)
@@ -277,20 +505,73 @@ impl Panel {
         }
 
         self.top_level.draw(g);
+        if let Some(w) = self
+            .focus
+            .and_then(|idx| self.top_level.focusable().get(idx).copied())
+        {
+            if let Ok(ring) = w
+                .rect
+                .to_polygon()
+                .to_outline(Distance::meters(FOCUS_RING_THICKNESS))
+            {
+                g.fork_screenspace();
+                g.draw_polygon(g.style().hovering_color, ring);
+                g.unfork();
+            }
+        }
         if self.scrollable_x || self.scrollable_y {
             g.disable_clipping();
 
             // Draw the scrollbars after clipping is disabled, because they actually live just
             // outside the rectangle.
-            if self.scrollable_x {
-                self.slider("horiz scrollbar").draw(g);
+            if self.should_draw_scrollbars(g) {
+                let prev_alpha = g.push_alpha(self.top_level.alpha);
+                if self.scrollable_x {
+                    self.slider("horiz scrollbar").draw(g);
+                }
+                if self.scrollable_y {
+                    self.slider("vert scrollbar").draw(g);
+                }
+                g.pop_alpha(prev_alpha);
             }
-            if self.scrollable_y {
-                self.slider("vert scrollbar").draw(g);
+        }
+
+        if let Some((_, since)) = self.tooltip_hover {
+            if elapsed_seconds(since) >= TOOLTIP_DELAY_S {
+                if let Some(w) = g
+                    .canvas
+                    .get_cursor_in_screen_space()
+                    .and_then(|pt| self.top_level.find_tooltip(pt))
+                {
+                    // This draws on top of everything, and doesn't cover up anything
+                    g.draw_mouse_tooltip(w.tooltip.clone().unwrap());
+                }
             }
         }
     }
 
+    fn should_draw_scrollbars(&self, g: &GfxCtx) -> bool {
+        if !self.scrollbar_auto_hide || self.scroll_target.is_some() {
+            return true;
+        }
+        let mut rects = vec![self.top_level.rect.clone()];
+        for (scrollable, name) in &[
+            (self.scrollable_x, "horiz scrollbar"),
+            (self.scrollable_y, "vert scrollbar"),
+        ] {
+            if *scrollable {
+                if self.slider(name).is_dragging() {
+                    return true;
+                }
+                rects.push(self.rect_of(name).clone());
+            }
+        }
+        g.canvas
+            .get_cursor_in_screen_space()
+            .map(|pt| rects.iter().any(|r| r.contains(pt)))
+            .unwrap_or(false)
+    }
+
     pub fn get_all_click_actions(&self) -> HashSet<String> {
         let mut actions = HashSet::new();
         self.top_level.get_all_click_actions(&mut actions);
@@ -315,6 +596,13 @@ impl Panel {
         }
     }
 
+    /// Like `scroll_to_member`, but does nothing if the panel isn't scrollable.
+    pub fn scroll_to(&mut self, ctx: &EventCtx, name: &str) {
+        if self.scrollable_x || self.scrollable_y {
+            self.scroll_to_member(ctx, name.to_string());
+        }
+    }
+
     pub fn has_widget(&self, name: &str) -> bool {
         self.top_level.find(name).is_some()
     }
@@ -332,6 +620,10 @@ impl Panel {
     pub fn take_menu_choice<T: 'static>(&mut self, name: &str) -> T {
         self.find_mut::<Menu<T>>(name).take_current_choice()
     }
+    /// Everything picked so far in a menu made by `Menu::multi`
+    pub fn multi_menu_selection<T: 'static + Clone>(&self, name: &str) -> Vec<T> {
+        self.find::<Menu<T>>(name).selected_choices()
+    }
 
     pub fn is_checked(&self, name: &str) -> bool {
         self.find::<Checkbox>(name).enabled
@@ -369,6 +661,12 @@ impl Panel {
             None
         }
     }
+    pub fn radio_value(&self, name: &str) -> String {
+        self.find::<RadioGroup>(name).current_value().to_string()
+    }
+    pub fn color_value(&self, name: &str) -> Color {
+        self.find::<ColorPicker>(name).current_color()
+    }
     pub fn persistent_split_value<T: 'static + PartialEq + Clone>(&self, name: &str) -> T {
         self.find::<PersistentSplit<T>>(name).current_value()
     }
@@ -404,6 +702,22 @@ impl Panel {
         }
     }
 
+    /// Replace what a named `Widget::draw_batch` draws, without recomputing the layout. The widget
+    /// keeps its original dimensions, so the new batch should fit in the same space; anything
+    /// outside of it may overlap other widgets.
+    ///
+    /// This uploads the batch to the GPU every time it's called. That's fine for small things like
+    /// a sparkline that changes every sim step, but calling this every frame with lots of geometry
+    /// will be slow, so call it when the underlying data changes, not on every event.
+    pub fn update_batch(&mut self, ctx: &EventCtx, name: &str, batch: GeomBatch) {
+        self.find_mut::<JustDraw>(name).draw = ctx.upload(batch);
+    }
+
+    /// Enable or disable a button, without rebuilding the panel.
+    pub fn set_disabled(&mut self, name: &str, disabled: bool) {
+        self.find_mut::<Button>(name).set_disabled(disabled);
+    }
+
     pub fn rect_of(&self, name: &str) -> &ScreenRectangle {
         &self.top_level.find(name).unwrap().rect
     }
@@ -414,12 +728,23 @@ impl Panel {
     pub fn center_of_panel(&self) -> ScreenPt {
         self.top_level.rect.center()
     }
+    /// The size of the panel on the screen. When it scrolls, this is the visible part, not all of
+    /// the contents.
+    pub fn dims(&self) -> ScreenDims {
+        if self.scrollable_x || self.scrollable_y {
+            self.container_dims
+        } else {
+            self.top_level.rect.dims()
+        }
+    }
 
     pub fn align(&mut self, horiz: HorizontalAlignment, vert: VerticalAlignment) {
         self.horiz = horiz;
         self.vert = vert;
+        self.pinned = None;
     }
     pub fn align_above(&mut self, ctx: &mut EventCtx, other: &Panel) {
+        self.unpin_vertically(ctx);
         // Small padding
         self.vert = VerticalAlignment::Above(other.top_level.rect.y1 - 5.0);
         self.recompute_layout(ctx, false);
@@ -428,6 +753,7 @@ impl Panel {
         ctx.no_op_event(true, |ctx| assert_eq!(self.event(ctx), Outcome::Nothing));
     }
     pub fn align_below(&mut self, ctx: &mut EventCtx, other: &Panel, pad: f64) {
+        self.unpin_vertically(ctx);
         self.vert = VerticalAlignment::Below(other.top_level.rect.y2 + pad);
         self.recompute_layout(ctx, false);
 
@@ -435,21 +761,47 @@ impl Panel {
         ctx.no_op_event(true, |ctx| assert_eq!(self.event(ctx), Outcome::Nothing));
     }
 
-    // All margins/padding/etc from the previous widget are retained.
+    // If the panel was pinned, keep the same horizontal position.
+    fn unpin_vertically(&mut self, ctx: &EventCtx) {
+        if let Some(pt) = self.pinned.take() {
+            self.horiz = HorizontalAlignment::Percent(pt.x / ctx.canvas.window_width);
+        }
+    }
+
+    /// Swap out one named widget, without rebuilding the whole panel. All margins/padding/etc from
+    /// the previous widget are retained. Everything else keeps its state, like the scroll offset
+    /// and the position of other sliders.
     pub fn replace(&mut self, ctx: &mut EventCtx, id: &str, mut new: Widget) {
         let old = self.top_level.find_mut(id).unwrap();
         new.layout.style = old.layout.style;
         *old = new;
+        // This rebuilds the scrollbars at the same offset
         self.recompute_layout(ctx, true);
 
         // TODO Same no_op_event as align_above? Should we always do this in recompute_layout?
     }
 
+    /// Swap a named widget with another, keeping the old one intact, so that it can be swapped
+    /// back in later with all of its state. The new widget must have the same name.
+    pub fn swap_inner_content(&mut self, ctx: &EventCtx, id: &str, new: &mut Widget) {
+        let old = self.top_level.find_mut(id).unwrap();
+        std::mem::swap(old, new);
+        self.recompute_layout(ctx, true);
+    }
+
+    /// Take apart the panel, returning the widget tree without any scrollbars. See
+    /// `Widget::embed`.
+    pub fn into_widget(mut self) -> Widget {
+        self.unwrap_scrollbars(self.scrollable_x, self.scrollable_y);
+        self.top_level
+    }
+
     pub fn clicked_outside(&self, ctx: &mut EventCtx) -> bool {
         // TODO No great way to populate OSD from here with "click to cancel"
         !self.top_level.rect.contains(ctx.canvas.get_cursor()) && ctx.normal_left_click()
     }
 
+    /// The action of the button under the mouse cursor, if any. This is updated during `event`.
     pub fn currently_hovering(&self) -> Option<&String> {
         self.top_level.currently_hovering()
     }
@@ -459,7 +811,16 @@ pub struct PanelBuilder {
     top_level: Widget,
     horiz: HorizontalAlignment,
     vert: VerticalAlignment,
+    pinned: Option<ScreenPt>,
     dims: Dims,
+    smooth_scroll: bool,
+    scroll_speed: Option<f64>,
+    invert_scroll: bool,
+    modal: bool,
+    dismissable: bool,
+    scrollbar_thickness: f64,
+    scrollbar_auto_hide: bool,
+    keyboard_navigation: bool,
 }
 
 enum Dims {
@@ -479,6 +840,7 @@ impl PanelBuilder {
 
             horiz: self.horiz,
             vert: self.vert,
+            pinned: self.pinned,
             dims: self.dims,
 
             scrollable_x: false,
@@ -486,6 +848,23 @@ impl PanelBuilder {
             contents_dims: ScreenDims::new(0.0, 0.0),
             container_dims: ScreenDims::new(0.0, 0.0),
             clip_rect: None,
+
+            smooth_scroll: self.smooth_scroll,
+            scroll_target: None,
+            scroll_speed: self.scroll_speed,
+            invert_scroll: self.invert_scroll,
+
+            modal: self.modal,
+            dismissable: self.dismissable,
+
+            tooltip_hover: None,
+
+            scrollbar_thickness: self.scrollbar_thickness,
+            scrollbar_auto_hide: self.scrollbar_auto_hide,
+
+            keyboard_navigation: self.keyboard_navigation,
+            focus: None,
+            layout_changed: false,
         };
         if let Dims::ExactPercent(w, h) = panel.dims {
             // Don't set size, because then scrolling breaks -- the actual size has to be based on
@@ -513,8 +892,10 @@ impl PanelBuilder {
         panel.update_container_dims_for_canvas_dims(ctx.canvas.get_window_dims());
         panel.recompute_layout(ctx, false);
 
-        // Just trigger error if a button is double-defined
+        // Just trigger error if a button or its hotkey is double-defined
         panel.get_all_click_actions();
+        panel.top_level.check_hotkeys(&mut HashMap::new());
+        panel.top_level.check_embedded_names();
         // Let all widgets initially respond to the mouse being somewhere
         ctx.no_op_event(true, |ctx| assert_eq!(panel.event(ctx), Outcome::Nothing));
         panel
@@ -526,6 +907,14 @@ impl PanelBuilder {
         self
     }
 
+    /// Put the top-left corner of the panel at exactly this point on the screen, instead of using
+    /// `aligned`. The panel is shifted to stay inside the window. Use this to anchor a panel to
+    /// something on the map, after converting its position to screen-space.
+    pub fn at(mut self, top_left: ScreenPt) -> PanelBuilder {
+        self.pinned = Some(top_left);
+        self
+    }
+
     pub fn max_size(mut self, width: Percent, height: Percent) -> PanelBuilder {
         if width == Percent::int(100) && height == Percent::int(100) {
             panic!("By default, Panels are capped at 100% of the screen. This is redundant.");
@@ -534,8 +923,95 @@ impl PanelBuilder {
         self
     }
 
+    /// Ease towards the new offset when scrolling with the mouse wheel, instead of jumping there.
+    pub fn smooth_scroll(mut self, smooth: bool) -> PanelBuilder {
+        self.smooth_scroll = smooth;
+        self
+    }
+
+    /// Dim the rest of the screen and swallow clicks outside of this panel, for confirmation
+    /// dialogs and such.
+    pub fn modal(mut self) -> PanelBuilder {
+        self.modal = true;
+        self
+    }
+
+    /// When a modal panel's backdrop is clicked, return Outcome::Dismissed.
+    pub fn dismissable(mut self, dismissable: bool) -> PanelBuilder {
+        assert!(self.modal, "Only modal panels can be dismissed");
+        self.dismissable = dismissable;
+        self
+    }
+
+    /// How thick any scrollbars are, and whether they're hidden unless the cursor is over the
+    /// panel.
+    pub fn scrollbar_style(mut self, thickness: f64, auto_hide: bool) -> PanelBuilder {
+        self.scrollbar_thickness = thickness;
+        self.scrollbar_auto_hide = auto_hide;
+        self
+    }
+
+    /// How many pixels to scroll per tick of the mouse wheel. Defaults to the Canvas's
+    /// gui_scroll_speed.
+    pub fn scroll_speed(mut self, speed: f64) -> PanelBuilder {
+        self.scroll_speed = Some(speed);
+        self
+    }
+
+    /// Scroll this panel the other way. This applies on top of the Canvas's invert_scroll, which
+    /// affects everything.
+    pub fn invert_scroll(mut self, invert: bool) -> PanelBuilder {
+        self.invert_scroll = invert;
+        self
+    }
+
+    /// Let Tab and Shift+Tab move a focus ring between buttons, checkboxes, and text boxes, and
+    /// Enter or Space activate the focused button or checkbox. This takes over Tab, so buttons in
+    /// the panel shouldn't use it as a hotkey.
+    pub fn keyboard_navigation(mut self) -> PanelBuilder {
+        self.keyboard_navigation = true;
+        self
+    }
+
     pub fn exact_size_percent(mut self, pct_width: usize, pct_height: usize) -> PanelBuilder {
         self.dims = Dims::ExactPercent((pct_width as f64) / 100.0, (pct_height as f64) / 100.0);
         self
     }
 }
+
+/// How many pixels a scrollbar at some percent has scrolled, when it can go up to max
+fn percent_to_offset(percent: f64, max: f64) -> f64 {
+    percent * max
+}
+
+/// The opposite of percent_to_offset. Offsets past either end are clamped.
+fn offset_to_percent(offset: f64, max: f64) -> f64 {
+    if max == 0.0 {
+        0.0
+    } else {
+        abstutil::clamp(offset, 0.0, max) / max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scroll_offset_round_trip() {
+        // A panel scrolled partway down keeps its position when the scrollbar is rebuilt
+        let max = 340.0;
+        for offset in &[0.0, 12.5, 170.0, 340.0] {
+            let percent = offset_to_percent(*offset, max);
+            assert!((percent_to_offset(percent, max) - offset).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_scroll_offset_clamped() {
+        assert_eq!(offset_to_percent(-10.0, 100.0), 0.0);
+        assert_eq!(offset_to_percent(500.0, 100.0), 1.0);
+        // Nothing to scroll
+        assert_eq!(offset_to_percent(50.0, 0.0), 0.0);
+    }
+}
@@ -0,0 +1,121 @@
+use geom::Polygon;
+
+use crate::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, Outcome, ScreenDims, ScreenPt,
+    ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
+};
+
+const ROW_PADDING: f64 = 4.0;
+
+/// A column of mutually exclusive options. Exactly one is always selected; picking another
+/// returns Outcome::Changed.
+pub struct RadioGroup {
+    options: Vec<String>,
+    current: usize,
+    draw: Drawable,
+    /// Relative to top_left
+    rows: Vec<Polygon>,
+    hovering: Option<usize>,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+impl RadioGroup {
+    pub fn new<I: Into<String>>(
+        ctx: &EventCtx,
+        label: I,
+        options: Vec<String>,
+        default_index: usize,
+    ) -> Widget {
+        assert!(default_index < options.len());
+        let (draw, rows, dims) = render(ctx, &options, default_index);
+        Widget::new(Box::new(RadioGroup {
+            options,
+            current: default_index,
+            draw,
+            rows,
+            hovering: None,
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims,
+        }))
+        .named(label)
+    }
+
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    pub fn current_value(&self) -> &str {
+        &self.options[self.current]
+    }
+}
+
+/// Returns the drawn options, the hitbox of each row, and the total dimensions.
+fn render(
+    ctx: &EventCtx,
+    options: &[String],
+    current: usize,
+) -> (Drawable, Vec<Polygon>, ScreenDims) {
+    let mut batch = GeomBatch::new();
+    let mut rows = Vec::new();
+    let mut y = 0.0;
+    let mut width: f64 = 0.0;
+    for (idx, option) in options.iter().enumerate() {
+        let marker = if idx == current { "(X) " } else { "( ) " };
+        let row = Text::from(Line(format!("{}{}", marker, option))).render(ctx);
+        let dims = row.get_dims();
+        batch.append(row.translate(0.0, y));
+        rows.push(Polygon::rectangle(dims.width, dims.height + ROW_PADDING).translate(0.0, y));
+        width = width.max(dims.width);
+        y += dims.height + ROW_PADDING;
+    }
+    (ctx.upload(batch), rows, ScreenDims::new(width, y))
+}
+
+impl WidgetImpl for RadioGroup {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if ctx.redo_mouseover() {
+            self.hovering = None;
+            if let Some(cursor) = ctx.canvas.get_cursor_in_screen_space() {
+                if ScreenRectangle::top_left(self.top_left, self.dims).contains(cursor) {
+                    let pt = ScreenPt::new(cursor.x - self.top_left.x, cursor.y - self.top_left.y);
+                    self.hovering = self.rows.iter().position(|r| r.contains_pt(pt.to_pt()));
+                }
+            }
+        }
+
+        if let Some(idx) = self.hovering {
+            ctx.cursor_clickable();
+            if ctx.normal_left_click() && idx != self.current {
+                self.current = idx;
+                let (draw, rows, dims) = render(ctx, &self.options, self.current);
+                self.draw = draw;
+                self.rows = rows;
+                self.dims = dims;
+                output.outcome = Outcome::Changed;
+                output.redo_layout = true;
+            }
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        g.redraw_at(self.top_left, &self.draw);
+        if let Some(idx) = self.hovering {
+            let draw = g.upload(GeomBatch::from(vec![(
+                Color::WHITE.alpha(0.2),
+                self.rows[idx].clone(),
+            )]));
+            g.redraw_at(self.top_left, &draw);
+        }
+    }
+}
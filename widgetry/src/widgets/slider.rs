@@ -1,8 +1,8 @@
 use geom::{Circle, Distance, Polygon, Pt2D};
 
 use crate::{
-    Color, Drawable, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenPt, ScreenRectangle, Widget,
-    WidgetImpl, WidgetOutput,
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, ScreenDims, ScreenPt, ScreenRectangle,
+    Text, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Slider {
@@ -13,6 +13,20 @@ pub struct Slider {
     horiz: bool,
     main_bg_len: f64,
     dragger_len: f64,
+    /// The length along the other axis
+    thickness: f64,
+
+    /// Percentages where tick marks are drawn
+    ticks: Vec<f64>,
+    /// Always round to the nearest tick
+    snap: bool,
+    /// Describes the current percent, drawn next to the slider
+    label_fn: Option<Box<dyn Fn(f64) -> String>>,
+    /// The value at 0% and 100%. Just 0 and 1, unless the slider was made with new_ranged.
+    min: f64,
+    max: f64,
+    /// Round values to whole numbers
+    integer: bool,
 
     draw: Drawable,
 
@@ -20,7 +34,10 @@ pub struct Slider {
     dims: ScreenDims,
 }
 
-const BG_CROSS_AXIS_LEN: f64 = 20.0;
+pub(crate) const BG_CROSS_AXIS_LEN: f64 = 20.0;
+const TICK_THICKNESS: f64 = 2.0;
+const LABEL_PADDING: f64 = 8.0;
+const RANGED_DRAGGER_LEN: f64 = 25.0;
 
 impl Slider {
     pub fn horizontal(
@@ -37,6 +54,14 @@ impl Slider {
             horiz: true,
             main_bg_len: width,
             dragger_len,
+            thickness: BG_CROSS_AXIS_LEN,
+
+            ticks: Vec::new(),
+            snap: false,
+            label_fn: None,
+            min: 0.0,
+            max: 1.0,
+            integer: false,
 
             draw: ctx.upload(GeomBatch::new()),
 
@@ -56,6 +81,14 @@ impl Slider {
             horiz: false,
             main_bg_len: height,
             dragger_len,
+            thickness: BG_CROSS_AXIS_LEN,
+
+            ticks: Vec::new(),
+            snap: false,
+            label_fn: None,
+            min: 0.0,
+            max: 1.0,
+            integer: false,
 
             draw: ctx.upload(GeomBatch::new()),
 
@@ -66,21 +99,199 @@ impl Slider {
         Widget::new(Box::new(s))
     }
 
-    fn recalc(&mut self, ctx: &EventCtx) {
-        // Full dims
-        self.dims = if self.horiz {
-            ScreenDims::new(self.main_bg_len, BG_CROSS_AXIS_LEN)
+    /// A horizontal slider for picking between discrete choices. Tick marks are drawn at each
+    /// percentage in `positions`, and `label_fn` describes the current percent. If `snap` is true,
+    /// dragging always rounds to the nearest tick.
+    pub fn with_ticks(
+        ctx: &EventCtx,
+        width: f64,
+        dragger_len: f64,
+        positions: Vec<f64>,
+        snap: bool,
+        current_percent: f64,
+        label_fn: Box<dyn Fn(f64) -> String>,
+    ) -> Widget {
+        assert!(!positions.is_empty());
+        for pct in &positions {
+            assert!(*pct >= 0.0 && *pct <= 1.0);
+        }
+        let mut s = Slider {
+            current_percent,
+            mouse_on_slider: false,
+            dragging: false,
+
+            horiz: true,
+            main_bg_len: width,
+            dragger_len,
+            thickness: BG_CROSS_AXIS_LEN,
+
+            ticks: positions,
+            snap,
+            label_fn: Some(label_fn),
+            min: 0.0,
+            max: 1.0,
+            integer: false,
+
+            draw: ctx.upload(GeomBatch::new()),
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims: ScreenDims::new(0.0, 0.0),
+        };
+        s.current_percent = s.snap_percent(current_percent);
+        s.recalc(ctx);
+        Widget::new(Box::new(s))
+    }
+
+    /// A horizontal slider picking a value between min and max, shown next to it. Use
+    /// get_value to read it.
+    pub fn new_ranged(ctx: &EventCtx, min: f64, max: f64, default: f64, width: f64) -> Widget {
+        Slider::ranged(ctx, min, max, default, width, false)
+    }
+
+    /// Like new_ranged, but only whole numbers can be picked.
+    pub fn new_ranged_int(
+        ctx: &EventCtx,
+        min: isize,
+        max: isize,
+        default: isize,
+        width: f64,
+    ) -> Widget {
+        Slider::ranged(ctx, min as f64, max as f64, default as f64, width, true)
+    }
+
+    fn ranged(
+        ctx: &EventCtx,
+        min: f64,
+        max: f64,
+        default: f64,
+        width: f64,
+        integer: bool,
+    ) -> Widget {
+        assert!(min < max);
+        let label_fn: Box<dyn Fn(f64) -> String> = if integer {
+            Box::new(move |pct| format!("{}", (min + pct * (max - min)).round()))
         } else {
-            ScreenDims::new(BG_CROSS_AXIS_LEN, self.main_bg_len)
+            Box::new(move |pct| format!("{:.2}", min + pct * (max - min)))
         };
+        let mut s = Slider {
+            current_percent: 0.0,
+            mouse_on_slider: false,
+            dragging: false,
+
+            horiz: true,
+            main_bg_len: width,
+            dragger_len: RANGED_DRAGGER_LEN,
+            thickness: BG_CROSS_AXIS_LEN,
+
+            ticks: Vec::new(),
+            snap: false,
+            label_fn: Some(label_fn),
+            min,
+            max,
+            integer,
+
+            draw: ctx.upload(GeomBatch::new()),
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims: ScreenDims::new(0.0, 0.0),
+        };
+        s.current_percent = s.snap_percent((default - min) / (max - min));
+        s.recalc(ctx);
+        Widget::new(Box::new(s))
+    }
+
+    /// A Panel's scrollbar, with a custom thickness
+    pub(crate) fn scrollbar(
+        ctx: &EventCtx,
+        horiz: bool,
+        len: f64,
+        dragger_len: f64,
+        thickness: f64,
+    ) -> Widget {
+        let mut s = Slider {
+            current_percent: 0.0,
+            mouse_on_slider: false,
+            dragging: false,
+
+            horiz,
+            main_bg_len: len,
+            dragger_len,
+            thickness,
+
+            ticks: Vec::new(),
+            snap: false,
+            label_fn: None,
+            min: 0.0,
+            max: 1.0,
+            integer: false,
+
+            draw: ctx.upload(GeomBatch::new()),
+
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims: ScreenDims::new(0.0, 0.0),
+        };
+        s.recalc(ctx);
+        Widget::new(Box::new(s))
+    }
+
+    pub(crate) fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// Just the bar the dragger moves along, not including any label
+    fn bar_dims(&self) -> ScreenDims {
+        if self.horiz {
+            ScreenDims::new(self.main_bg_len, self.thickness)
+        } else {
+            ScreenDims::new(self.thickness, self.main_bg_len)
+        }
+    }
+
+    fn snap_percent(&self, percent: f64) -> f64 {
+        let percent = percent.min(1.0).max(0.0);
+        if self.integer {
+            let value = (self.min + percent * (self.max - self.min)).round();
+            return (value - self.min) / (self.max - self.min);
+        }
+        if !self.snap {
+            return percent;
+        }
+        self.ticks
+            .iter()
+            .cloned()
+            .min_by(|a, b| {
+                (a - percent)
+                    .abs()
+                    .partial_cmp(&(b - percent).abs())
+                    .unwrap()
+            })
+            .unwrap_or(percent)
+    }
+
+    fn recalc(&mut self, ctx: &EventCtx) {
+        let bar = self.bar_dims();
+        // Full dims
+        self.dims = bar;
 
         let mut batch = GeomBatch::new();
 
         // The background
-        batch.push(
-            Color::WHITE,
-            Polygon::rectangle(self.dims.width, self.dims.height),
-        );
+        batch.push(Color::WHITE, Polygon::rectangle(bar.width, bar.height));
+
+        for pct in &self.ticks {
+            // Line up with the center of the dragger
+            let offset = pct * (self.main_bg_len - self.dragger_len) + self.dragger_len / 2.0;
+            batch.push(
+                Color::grey(0.3),
+                if self.horiz {
+                    Polygon::rectangle(TICK_THICKNESS, bar.height)
+                        .translate(offset - TICK_THICKNESS / 2.0, 0.0)
+                } else {
+                    Polygon::rectangle(bar.width, TICK_THICKNESS)
+                        .translate(0.0, offset - TICK_THICKNESS / 2.0)
+                },
+            );
+        }
 
         // The draggy thing
         batch.push(
@@ -92,18 +303,29 @@ impl Slider {
             self.slider_geom(),
         );
 
+        if let Some(ref label_fn) = self.label_fn {
+            let label = Text::from(Line(label_fn(self.current_percent))).render(ctx);
+            let label_dims = label.get_dims();
+            let height = bar.height.max(label_dims.height);
+            batch.append(label.translate(
+                bar.width + LABEL_PADDING,
+                (height - label_dims.height) / 2.0,
+            ));
+            self.dims = ScreenDims::new(bar.width + LABEL_PADDING + label_dims.width, height);
+        }
+
         self.draw = ctx.upload(batch);
     }
 
     // Doesn't touch self.top_left
     fn slider_geom(&self) -> Polygon {
         if self.horiz {
-            Polygon::rectangle(self.dragger_len, BG_CROSS_AXIS_LEN).translate(
+            Polygon::rectangle(self.dragger_len, self.thickness).translate(
                 self.current_percent * (self.main_bg_len - self.dragger_len),
                 0.0,
             )
         } else {
-            Polygon::rectangle(BG_CROSS_AXIS_LEN, self.dragger_len).translate(
+            Polygon::rectangle(self.thickness, self.dragger_len).translate(
                 0.0,
                 self.current_percent * (self.main_bg_len - self.dragger_len),
             )
@@ -114,8 +336,20 @@ impl Slider {
         self.current_percent
     }
 
-    pub fn get_value(&self, num_items: usize) -> usize {
-        (self.current_percent * (num_items as f64 - 1.0)) as usize
+    /// The current percent mapped onto the slider's range. Without a range, this is the same as
+    /// get_percent.
+    pub fn get_value(&self) -> f64 {
+        let value = self.min + self.current_percent * (self.max - self.min);
+        if self.integer {
+            value.round()
+        } else {
+            value
+        }
+    }
+
+    pub fn set_value(&mut self, ctx: &EventCtx, value: f64) {
+        let percent = (value - self.min) / (self.max - self.min);
+        self.set_percent(ctx, self.snap_percent(percent));
     }
 
     pub fn set_percent(&mut self, ctx: &EventCtx, percent: f64) {
@@ -144,7 +378,7 @@ impl Slider {
                     (ctx.canvas.get_cursor().y - self.top_left.y - (self.dragger_len / 2.0))
                         / (self.main_bg_len - self.dragger_len)
                 };
-                self.current_percent = percent.min(1.0).max(0.0);
+                self.current_percent = self.snap_percent(percent);
                 return true;
             }
             if ctx.input.left_mouse_button_released() {
@@ -174,7 +408,8 @@ impl Slider {
 
             // Did we click somewhere else on the bar?
             if let Some(pt) = ctx.canvas.get_cursor_in_screen_space() {
-                if Polygon::rectangle(self.dims.width, self.dims.height)
+                let bar = self.bar_dims();
+                if Polygon::rectangle(bar.width, bar.height)
                     .translate(self.top_left.x, self.top_left.y)
                     .contains_pt(pt.to_pt())
                 {
@@ -185,7 +420,7 @@ impl Slider {
                         (pt.y - self.top_left.y - (self.dragger_len / 2.0))
                             / (self.main_bg_len - self.dragger_len)
                     };
-                    self.current_percent = percent.min(1.0).max(0.0);
+                    self.current_percent = self.snap_percent(percent);
                     self.mouse_on_slider = true;
                     self.dragging = true;
                     return true;
@@ -205,9 +440,14 @@ impl WidgetImpl for Slider {
         self.top_left = top_left;
     }
 
-    fn event(&mut self, ctx: &mut EventCtx, _: &mut WidgetOutput) {
+    fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
         if self.inner_event(ctx) {
+            let old_dims = self.dims;
             self.recalc(ctx);
+            // The label might change width
+            if self.dims != old_dims {
+                output.redo_layout = true;
+            }
         }
     }
 
@@ -0,0 +1,86 @@
+use crate::{Btn, Color, EventCtx, Panel, Widget};
+
+/// A row of tab buttons, with the content of only the active tab shown in the panel. The content
+/// of hidden tabs is kept around, so switching back to a tab preserves things like sliders and
+/// dropdowns.
+///
+/// Each tab's label is also the action of its button, so it can't be used by any other button in
+/// the panel.
+pub struct TabController {
+    id: String,
+    labels: Vec<String>,
+    /// The active tab's content lives in the panel, so it's None here
+    hidden: Vec<Option<Widget>>,
+    active: usize,
+}
+
+impl TabController {
+    /// Returns the controller and the widget to embed in a panel. The first tab starts active.
+    pub fn new<I: Into<String>>(
+        ctx: &EventCtx,
+        id: I,
+        tabs: Vec<(String, Widget)>,
+    ) -> (TabController, Widget) {
+        assert!(!tabs.is_empty());
+        let id = id.into();
+        let mut labels = Vec::new();
+        let mut hidden = Vec::new();
+        for (label, content) in tabs {
+            labels.push(label);
+            hidden.push(Some(content.named(id.clone())));
+        }
+        let first = hidden[0].take().unwrap();
+        let controller = TabController {
+            id,
+            labels,
+            hidden,
+            active: 0,
+        };
+        let widget = Widget::col(vec![controller.make_tab_bar(ctx), first]);
+        (controller, widget)
+    }
+
+    pub fn active_tab(&self) -> &str {
+        &self.labels[self.active]
+    }
+
+    /// Call this with the action of a clicked button. Returns true if it was a tab that got
+    /// switched to.
+    pub fn handle_action(&mut self, ctx: &mut EventCtx, action: &str, panel: &mut Panel) -> bool {
+        let idx = if let Some(idx) = self.labels.iter().position(|l| l == action) {
+            idx
+        } else {
+            return false;
+        };
+        if idx == self.active {
+            return false;
+        }
+
+        let mut content = self.hidden[idx].take().unwrap();
+        panel.swap_inner_content(ctx, &self.id, &mut content);
+        self.hidden[self.active] = Some(content);
+        self.active = idx;
+
+        panel.replace(ctx, &self.tab_bar_id(), self.make_tab_bar(ctx));
+        true
+    }
+
+    fn tab_bar_id(&self) -> String {
+        format!("{} tabs", self.id)
+    }
+
+    fn make_tab_bar(&self, ctx: &EventCtx) -> Widget {
+        let mut row = Vec::new();
+        for (idx, label) in self.labels.iter().enumerate() {
+            if idx == self.active {
+                row.push(Btn::text_bg2(label).inactive(ctx).centered_vert());
+            } else {
+                row.push(Btn::text_bg2(label).build_def(ctx, None).centered_vert());
+            }
+        }
+        Widget::custom_row(row)
+            .bg(Color::WHITE)
+            .margin_below(16)
+            .named(self.tab_bar_id())
+    }
+}
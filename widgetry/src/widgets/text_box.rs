@@ -36,6 +36,10 @@ impl TextBox {
     }
 
     fn calculate_text(&self) -> Text {
+        // Only show the cursor when typing would actually go here
+        if !self.has_focus && !self.autofocus {
+            return Text::from(Line(&self.line));
+        }
         let mut txt = Text::from(Line(&self.line[0..self.cursor_x]));
         if self.cursor_x < self.line.len() {
             // TODO This "cursor" looks awful!
@@ -53,6 +57,10 @@ impl TextBox {
     pub fn get_line(&self) -> String {
         self.line.clone()
     }
+
+    pub(crate) fn set_focus(&mut self, focus: bool) {
+        self.has_focus = focus;
+    }
 }
 
 impl WidgetImpl for TextBox {
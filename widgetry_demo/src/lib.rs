@@ -94,6 +94,8 @@ impl App {
                     max_x: Some(Time::START_OF_DAY + self.elapsed),
                     max_y: None,
                     disabled: HashSet::new(),
+                    log_y: false,
+                    thresholds: Vec::new(),
                 },
             ),
         ]))